@@ -0,0 +1,33 @@
+CREATE TABLE IF NOT EXISTS vouchers (
+    id INTEGER PRIMARY KEY AUTOINCREMENT,
+    code TEXT NOT NULL UNIQUE,
+    kind TEXT NOT NULL,                  -- minutes / money
+    value INTEGER NOT NULL,
+    max_uses INTEGER NOT NULL DEFAULT 1,
+    uses INTEGER NOT NULL DEFAULT 0,
+    campaign TEXT,
+    expires_at DATETIME,
+    disabled INTEGER NOT NULL DEFAULT 0,
+    created_by INTEGER REFERENCES users(id),
+    created_at DATETIME NOT NULL DEFAULT (datetime('now'))
+);
+
+CREATE INDEX IF NOT EXISTS idx_vouchers_campaign ON vouchers(campaign);
+
+CREATE TABLE IF NOT EXISTS voucher_redemptions (
+    id INTEGER PRIMARY KEY AUTOINCREMENT,
+    voucher_id INTEGER NOT NULL REFERENCES vouchers(id),
+    user_id INTEGER NOT NULL REFERENCES users(id),
+    redeemed_at DATETIME NOT NULL DEFAULT (datetime('now')),
+    UNIQUE (voucher_id, user_id)
+);
+
+CREATE TABLE IF NOT EXISTS voucher_redeem_attempts (
+    id INTEGER PRIMARY KEY AUTOINCREMENT,
+    user_id INTEGER NOT NULL REFERENCES users(id),
+    code TEXT NOT NULL,
+    success INTEGER NOT NULL DEFAULT 0,
+    attempted_at DATETIME NOT NULL DEFAULT (datetime('now'))
+);
+
+CREATE INDEX IF NOT EXISTS idx_voucher_attempts_user ON voucher_redeem_attempts(user_id, attempted_at);
@@ -0,0 +1,32 @@
+ALTER TABLE machines ADD COLUMN class TEXT NOT NULL DEFAULT 'standard';
+
+ALTER TABLE sessions ADD COLUMN included_minutes INTEGER NOT NULL DEFAULT 0;
+ALTER TABLE sessions ADD COLUMN charged_minutes INTEGER NOT NULL DEFAULT 0;
+
+CREATE TABLE IF NOT EXISTS subscription_plans (
+    id INTEGER PRIMARY KEY AUTOINCREMENT,
+    name TEXT NOT NULL UNIQUE,
+    price INTEGER NOT NULL,
+    period_days INTEGER NOT NULL DEFAULT 30,
+    included_minutes INTEGER NOT NULL DEFAULT 0,
+    extra_discount_percent INTEGER NOT NULL DEFAULT 0,
+    machine_classes TEXT,                -- comma separated, NULL covers every class
+    active INTEGER NOT NULL DEFAULT 1,
+    created_at DATETIME NOT NULL DEFAULT (datetime('now'))
+);
+
+CREATE TABLE IF NOT EXISTS subscriptions (
+    id INTEGER PRIMARY KEY AUTOINCREMENT,
+    user_id INTEGER NOT NULL REFERENCES users(id),
+    plan_id INTEGER NOT NULL REFERENCES subscription_plans(id),
+    status TEXT NOT NULL DEFAULT 'active', -- active / cancelled / lapsed
+    auto_renew INTEGER NOT NULL DEFAULT 1,
+    started_at DATETIME NOT NULL DEFAULT (datetime('now')),
+    current_period_start DATETIME NOT NULL,
+    current_period_end DATETIME NOT NULL,
+    minutes_used INTEGER NOT NULL DEFAULT 0,
+    cancelled_at DATETIME,
+    ended_at DATETIME
+);
+
+CREATE UNIQUE INDEX IF NOT EXISTS idx_subscriptions_active_user ON subscriptions(user_id) WHERE status = 'active';
@@ -0,0 +1,32 @@
+-- bonus_minutes is read by the User model and add_bonus but was never created
+ALTER TABLE users ADD COLUMN bonus_minutes INTEGER NOT NULL DEFAULT 0;
+
+CREATE TABLE IF NOT EXISTS loyalty_tiers (
+    id INTEGER PRIMARY KEY AUTOINCREMENT,
+    name TEXT NOT NULL UNIQUE,
+    min_hours INTEGER NOT NULL UNIQUE,
+    rate_discount_percent INTEGER NOT NULL DEFAULT 0,
+    bonus_minutes INTEGER NOT NULL DEFAULT 0,
+    bonus_period_days INTEGER NOT NULL DEFAULT 30,
+    created_at DATETIME NOT NULL DEFAULT (datetime('now'))
+);
+
+INSERT INTO loyalty_tiers (name, min_hours, rate_discount_percent, bonus_minutes) VALUES
+    ('Bronze', 0, 0, 0),
+    ('Silver', 50, 5, 60),
+    ('Gold', 200, 10, 180);
+
+ALTER TABLE users ADD COLUMN loyalty_tier_id INTEGER REFERENCES loyalty_tiers(id);
+ALTER TABLE users ADD COLUMN tier_bonus_at DATETIME;
+
+CREATE TABLE IF NOT EXISTS notifications (
+    id INTEGER PRIMARY KEY AUTOINCREMENT,
+    user_id INTEGER REFERENCES users(id),
+    audience TEXT NOT NULL DEFAULT 'user', -- user / staff
+    kind TEXT NOT NULL,
+    message TEXT NOT NULL,
+    created_at DATETIME NOT NULL DEFAULT (datetime('now')),
+    read_at DATETIME
+);
+
+CREATE INDEX IF NOT EXISTS idx_notifications_user ON notifications(user_id, read_at);
@@ -0,0 +1,17 @@
+CREATE TABLE IF NOT EXISTS balance_adjustments (
+    id INTEGER PRIMARY KEY AUTOINCREMENT,
+    user_id INTEGER NOT NULL REFERENCES users(id),
+    session_id INTEGER REFERENCES sessions(id),
+    direction TEXT NOT NULL,             -- credit / debit
+    minutes INTEGER NOT NULL,
+    reason TEXT NOT NULL,
+    status TEXT NOT NULL DEFAULT 'pending', -- pending / applied / rejected
+    requested_by INTEGER NOT NULL REFERENCES users(id),
+    reviewed_by INTEGER REFERENCES users(id),
+    review_note TEXT,
+    created_at DATETIME NOT NULL DEFAULT (datetime('now')),
+    reviewed_at DATETIME
+);
+
+CREATE INDEX IF NOT EXISTS idx_balance_adjustments_status ON balance_adjustments(status);
+CREATE INDEX IF NOT EXISTS idx_balance_adjustments_user ON balance_adjustments(user_id);
@@ -0,0 +1,31 @@
+CREATE TABLE IF NOT EXISTS receipt_counter (
+    id INTEGER PRIMARY KEY CHECK (id = 1),
+    last_number INTEGER NOT NULL
+);
+
+INSERT INTO receipt_counter (id, last_number) VALUES (1, 0);
+
+CREATE TABLE IF NOT EXISTS receipts (
+    id INTEGER PRIMARY KEY AUTOINCREMENT,
+    number TEXT NOT NULL UNIQUE,
+    user_id INTEGER REFERENCES users(id),
+    kind TEXT NOT NULL,                  -- top_up / package / session
+    reference_id INTEGER,
+    subtotal INTEGER NOT NULL,           -- amounts are in minor currency units, tax inclusive
+    tax INTEGER NOT NULL,
+    total INTEGER NOT NULL,
+    tax_rate_bp INTEGER NOT NULL,
+    currency TEXT NOT NULL,
+    created_at DATETIME NOT NULL DEFAULT (datetime('now'))
+);
+
+CREATE INDEX IF NOT EXISTS idx_receipts_user ON receipts(user_id);
+
+CREATE TABLE IF NOT EXISTS receipt_lines (
+    id INTEGER PRIMARY KEY AUTOINCREMENT,
+    receipt_id INTEGER NOT NULL REFERENCES receipts(id),
+    description TEXT NOT NULL,
+    quantity INTEGER NOT NULL,
+    unit_price INTEGER NOT NULL,
+    amount INTEGER NOT NULL
+);
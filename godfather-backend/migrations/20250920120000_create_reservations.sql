@@ -0,0 +1,26 @@
+CREATE TABLE IF NOT EXISTS reservations (
+    id INTEGER PRIMARY KEY AUTOINCREMENT,
+    user_id INTEGER NOT NULL REFERENCES users(id),
+    machine_id INTEGER NOT NULL REFERENCES machines(id),
+    group_id INTEGER,                    -- shared by reservations booked together as a block
+    starts_at DATETIME NOT NULL,
+    ends_at DATETIME NOT NULL,
+    status TEXT NOT NULL DEFAULT 'booked', -- booked / held / checked_in / completed / cancelled / no_show / expired
+    created_at DATETIME NOT NULL DEFAULT (datetime('now')),
+    checked_in_at DATETIME,
+    cancelled_at DATETIME
+);
+
+CREATE INDEX IF NOT EXISTS idx_reservations_machine ON reservations(machine_id, starts_at);
+CREATE INDEX IF NOT EXISTS idx_reservations_user ON reservations(user_id);
+
+CREATE TABLE IF NOT EXISTS maintenance_windows (
+    id INTEGER PRIMARY KEY AUTOINCREMENT,
+    machine_id INTEGER NOT NULL REFERENCES machines(id),
+    starts_at DATETIME NOT NULL,
+    ends_at DATETIME NOT NULL,
+    reason TEXT,
+    created_at DATETIME NOT NULL DEFAULT (datetime('now'))
+);
+
+CREATE INDEX IF NOT EXISTS idx_maintenance_windows_machine ON maintenance_windows(machine_id, starts_at);
@@ -0,0 +1,14 @@
+CREATE TABLE IF NOT EXISTS waitlist_entries (
+    id INTEGER PRIMARY KEY AUTOINCREMENT,
+    user_id INTEGER NOT NULL REFERENCES users(id),
+    machine_class TEXT,                  -- NULL accepts any machine
+    status TEXT NOT NULL DEFAULT 'waiting', -- waiting / offered / seated / expired / cancelled
+    created_at DATETIME NOT NULL DEFAULT (datetime('now')),
+    offered_machine_id INTEGER REFERENCES machines(id),
+    offered_at DATETIME,
+    offer_expires_at DATETIME,
+    seated_at DATETIME
+);
+
+CREATE UNIQUE INDEX IF NOT EXISTS idx_waitlist_live_user ON waitlist_entries(user_id) WHERE status IN ('waiting', 'offered');
+CREATE INDEX IF NOT EXISTS idx_waitlist_status ON waitlist_entries(status, created_at);
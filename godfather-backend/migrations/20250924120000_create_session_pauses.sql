@@ -0,0 +1,22 @@
+ALTER TABLE sessions ADD COLUMN paused_minutes INTEGER NOT NULL DEFAULT 0;
+
+CREATE TABLE IF NOT EXISTS session_pauses (
+    id INTEGER PRIMARY KEY AUTOINCREMENT,
+    session_id INTEGER NOT NULL REFERENCES sessions(id),
+    paused_at DATETIME NOT NULL DEFAULT (datetime('now')),
+    resumed_at DATETIME
+);
+
+-- a session has at most one pause running
+CREATE UNIQUE INDEX IF NOT EXISTS idx_session_pauses_open ON session_pauses(session_id) WHERE resumed_at IS NULL;
+
+-- lock / unlock and similar instructions picked up by the agent on its next heartbeat
+CREATE TABLE IF NOT EXISTS machine_commands (
+    id INTEGER PRIMARY KEY AUTOINCREMENT,
+    machine_id INTEGER NOT NULL REFERENCES machines(id),
+    command TEXT NOT NULL,
+    created_at DATETIME NOT NULL DEFAULT (datetime('now')),
+    delivered_at DATETIME
+);
+
+CREATE INDEX IF NOT EXISTS idx_machine_commands_pending ON machine_commands(machine_id) WHERE delivered_at IS NULL;
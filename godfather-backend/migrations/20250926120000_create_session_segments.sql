@@ -0,0 +1,15 @@
+-- the stretch of a session spent on each machine, a session moved between
+-- machines keeps one billing record with a segment per machine
+CREATE TABLE IF NOT EXISTS session_segments (
+    id INTEGER PRIMARY KEY AUTOINCREMENT,
+    session_id INTEGER NOT NULL REFERENCES sessions(id),
+    machine_id INTEGER NOT NULL REFERENCES machines(id),
+    started_at DATETIME NOT NULL DEFAULT (datetime('now')),
+    ended_at DATETIME,
+    end_reason TEXT -- transfer / session_end
+);
+
+CREATE INDEX IF NOT EXISTS idx_session_segments_session ON session_segments(session_id);
+
+INSERT INTO session_segments (session_id, machine_id, started_at, ended_at, end_reason)
+SELECT id, machine_id, started_at, ended_at, CASE WHEN ended_at IS NULL THEN NULL ELSE 'session_end' END FROM sessions;
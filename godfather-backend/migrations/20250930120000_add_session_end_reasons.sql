@@ -0,0 +1,6 @@
+-- why a session ended: user_logout / balance_exhausted / staff_forced / machine_offline / ban / crash / pause_expired
+ALTER TABLE sessions ADD COLUMN end_reason TEXT;
+-- staff member who force-ended the session
+ALTER TABLE sessions ADD COLUMN ended_by INTEGER REFERENCES users(id);
+
+CREATE INDEX IF NOT EXISTS idx_sessions_end_reason ON sessions(end_reason);
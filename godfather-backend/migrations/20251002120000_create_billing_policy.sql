@@ -0,0 +1,19 @@
+-- single row describing how elapsed time turns into billed time
+CREATE TABLE IF NOT EXISTS billing_policy (
+    id INTEGER PRIMARY KEY CHECK (id = 1),
+    granularity_seconds INTEGER NOT NULL DEFAULT 60, -- 1 per second, 60 per minute, 900 per 15-minute block
+    rounding TEXT NOT NULL DEFAULT 'down',           -- up / down, applied to partial blocks
+    minimum_seconds INTEGER NOT NULL DEFAULT 60,     -- least a billed session is charged
+    grace_seconds INTEGER NOT NULL DEFAULT 0,        -- sessions ending within this window are free
+    updated_at DATETIME NOT NULL DEFAULT (datetime('now'))
+);
+
+-- matches the previous whole-minute billing with a one minute minimum
+INSERT OR IGNORE INTO billing_policy (id) VALUES (1);
+
+-- seconds billed but not yet charged, balances are kept in whole minutes
+ALTER TABLE users ADD COLUMN billing_carry_seconds INTEGER NOT NULL DEFAULT 0;
+
+-- exact playtime, lifetime_hours is kept as the whole hours of it
+ALTER TABLE users ADD COLUMN lifetime_minutes INTEGER NOT NULL DEFAULT 0;
+UPDATE users SET lifetime_minutes = COALESCE(lifetime_hours, 0) * 60;
@@ -0,0 +1,16 @@
+-- prepaid tickets for walk-ins without an account. Each ticket is backed by a
+-- hidden user with role 'guest' so sessions, billing and receipts work unchanged.
+CREATE TABLE IF NOT EXISTS guest_tickets (
+    id INTEGER PRIMARY KEY AUTOINCREMENT,
+    code TEXT NOT NULL UNIQUE,
+    user_id INTEGER NOT NULL REFERENCES users(id),
+    minutes_sold INTEGER NOT NULL,
+    price INTEGER NOT NULL,
+    status TEXT NOT NULL DEFAULT 'active', -- active / expired / converted
+    expires_at DATETIME NOT NULL,
+    sold_by INTEGER REFERENCES users(id),
+    created_at DATETIME NOT NULL DEFAULT (datetime('now')),
+    converted_at DATETIME
+);
+
+CREATE INDEX IF NOT EXISTS idx_guest_tickets_status ON guest_tickets(status, expires_at);
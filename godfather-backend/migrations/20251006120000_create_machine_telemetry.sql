@@ -0,0 +1,52 @@
+-- latest agent details reported in heartbeats
+ALTER TABLE machines ADD COLUMN agent_version TEXT;
+ALTER TABLE machines ADD COLUMN logged_in_user TEXT;
+
+-- raw heartbeat samples, kept for a short window
+CREATE TABLE IF NOT EXISTS telemetry_samples (
+    id INTEGER PRIMARY KEY AUTOINCREMENT,
+    machine_id INTEGER NOT NULL REFERENCES machines(id),
+    recorded_at DATETIME NOT NULL DEFAULT (datetime('now')),
+    cpu_temp_c REAL,
+    gpu_temp_c REAL,
+    cpu_load_percent REAL,
+    gpu_load_percent REAL,
+    ram_used_mb INTEGER,
+    ram_total_mb INTEGER,
+    disk_free_mb INTEGER,
+    uptime_seconds INTEGER
+);
+
+CREATE INDEX IF NOT EXISTS idx_telemetry_samples_machine ON telemetry_samples(machine_id, recorded_at);
+
+-- hourly down-sampled series, kept much longer
+CREATE TABLE IF NOT EXISTS telemetry_hourly (
+    machine_id INTEGER NOT NULL REFERENCES machines(id),
+    hour DATETIME NOT NULL,
+    samples INTEGER NOT NULL,
+    avg_cpu_temp_c REAL,
+    max_cpu_temp_c REAL,
+    avg_gpu_temp_c REAL,
+    max_gpu_temp_c REAL,
+    avg_cpu_load_percent REAL,
+    avg_gpu_load_percent REAL,
+    avg_ram_used_mb REAL,
+    min_disk_free_mb INTEGER,
+    max_uptime_seconds INTEGER,
+    PRIMARY KEY (machine_id, hour)
+);
+
+-- threshold breaches raised for staff, resolved once readings recover
+CREATE TABLE IF NOT EXISTS machine_alerts (
+    id INTEGER PRIMARY KEY AUTOINCREMENT,
+    machine_id INTEGER NOT NULL REFERENCES machines(id),
+    kind TEXT NOT NULL, -- cpu_temp / gpu_temp / disk_free
+    value REAL NOT NULL,
+    threshold REAL NOT NULL,
+    raised_at DATETIME NOT NULL DEFAULT (datetime('now')),
+    acknowledged_at DATETIME,
+    acknowledged_by INTEGER REFERENCES users(id),
+    resolved_at DATETIME
+);
+
+CREATE UNIQUE INDEX IF NOT EXISTS idx_machine_alerts_open ON machine_alerts(machine_id, kind) WHERE resolved_at IS NULL;
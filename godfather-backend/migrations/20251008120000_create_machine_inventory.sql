@@ -0,0 +1,37 @@
+ALTER TABLE machines ADD COLUMN cpu TEXT;
+ALTER TABLE machines ADD COLUMN gpu TEXT;
+ALTER TABLE machines ADD COLUMN ram_gb INTEGER;
+ALTER TABLE machines ADD COLUMN monitor_hz INTEGER;
+
+CREATE TABLE IF NOT EXISTS machine_peripherals (
+    id INTEGER PRIMARY KEY AUTOINCREMENT,
+    machine_id INTEGER NOT NULL REFERENCES machines(id),
+    kind TEXT NOT NULL,                  -- monitor / keyboard / mouse / headset / ...
+    model TEXT,
+    serial TEXT NOT NULL,
+    attached_at DATETIME NOT NULL DEFAULT (datetime('now')),
+    detached_at DATETIME                 -- kept for history once removed
+);
+
+CREATE INDEX IF NOT EXISTS idx_machine_peripherals_machine ON machine_peripherals(machine_id);
+-- a serial can only be attached to one machine at a time
+CREATE UNIQUE INDEX IF NOT EXISTS idx_machine_peripherals_serial ON machine_peripherals(serial) WHERE detached_at IS NULL;
+
+CREATE TABLE IF NOT EXISTS maintenance_tickets (
+    id INTEGER PRIMARY KEY AUTOINCREMENT,
+    machine_id INTEGER NOT NULL REFERENCES machines(id),
+    title TEXT NOT NULL,
+    description TEXT,
+    blocking INTEGER NOT NULL DEFAULT 0, -- takes the machine out of service while open
+    status TEXT NOT NULL DEFAULT 'open', -- open / assigned / resolved
+    opened_by INTEGER REFERENCES users(id),
+    opened_at DATETIME NOT NULL DEFAULT (datetime('now')),
+    assigned_to INTEGER REFERENCES users(id),
+    assigned_at DATETIME,
+    resolved_by INTEGER REFERENCES users(id),
+    resolved_at DATETIME,
+    resolution TEXT
+);
+
+CREATE INDEX IF NOT EXISTS idx_maintenance_tickets_machine ON maintenance_tickets(machine_id, opened_at);
+CREATE INDEX IF NOT EXISTS idx_maintenance_tickets_status ON maintenance_tickets(status);
\ No newline at end of file
@@ -0,0 +1,19 @@
+ALTER TABLE machines ADD COLUMN zone TEXT;
+ALTER TABLE machines ADD COLUMN decommissioned_at DATETIME;
+-- set by staff when the PC behind a seat is replaced; the next registration under the seat's name takes it over
+ALTER TABLE machines ADD COLUMN reenroll_requested_at DATETIME;
+
+-- retired machines give up their name
+CREATE UNIQUE INDEX IF NOT EXISTS idx_machines_active_name ON machines(name) WHERE decommissioned_at IS NULL;
+CREATE INDEX IF NOT EXISTS idx_machines_zone ON machines(zone);
+
+CREATE TABLE IF NOT EXISTS machine_enrollments (
+    id INTEGER PRIMARY KEY AUTOINCREMENT,
+    machine_id INTEGER NOT NULL REFERENCES machines(id),
+    requested_by INTEGER REFERENCES users(id),
+    requested_at DATETIME NOT NULL DEFAULT (datetime('now')),
+    note TEXT,
+    enrolled_at DATETIME                 -- when the replacement PC registered
+);
+
+CREATE INDEX IF NOT EXISTS idx_machine_enrollments_machine ON machine_enrollments(machine_id);
\ No newline at end of file
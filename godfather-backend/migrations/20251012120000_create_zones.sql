@@ -0,0 +1,24 @@
+CREATE TABLE IF NOT EXISTS zones (
+    id INTEGER PRIMARY KEY AUTOINCREMENT,
+    name TEXT NOT NULL UNIQUE,           -- referenced by machines.zone
+    label TEXT NOT NULL,
+    sort_order INTEGER NOT NULL DEFAULT 0,
+    created_at DATETIME NOT NULL DEFAULT (datetime('now'))
+);
+
+INSERT OR IGNORE INTO zones (name, label, sort_order) VALUES
+    ('main_hall', 'Main hall', 1),
+    ('vip', 'VIP room', 2),
+    ('console_lounge', 'Console lounge', 3);
+
+-- zones already typed onto machines become real zones
+INSERT OR IGNORE INTO zones (name, label, sort_order)
+SELECT DISTINCT zone, zone, 100 FROM machines WHERE zone IS NOT NULL;
+
+-- seat order within the zone and coordinates on the floor map
+ALTER TABLE machines ADD COLUMN seat_position INTEGER;
+ALTER TABLE machines ADD COLUMN pos_x REAL;
+ALTER TABLE machines ADD COLUMN pos_y REAL;
+
+CREATE UNIQUE INDEX IF NOT EXISTS idx_machines_seat ON machines(zone, seat_position)
+    WHERE decommissioned_at IS NULL AND seat_position IS NOT NULL;
\ No newline at end of file
@@ -0,0 +1,9 @@
+CREATE TABLE IF NOT EXISTS machine_favorites (
+    user_id INTEGER NOT NULL REFERENCES users(id),
+    machine_id INTEGER NOT NULL REFERENCES machines(id),
+    created_at DATETIME NOT NULL DEFAULT (datetime('now')),
+    PRIMARY KEY (user_id, machine_id)
+);
+
+-- wear balancing sums segment time per machine
+CREATE INDEX IF NOT EXISTS idx_session_segments_machine ON session_segments(machine_id);
\ No newline at end of file
@@ -0,0 +1,9 @@
+-- continuous stretches of heartbeats; a gap longer than MACHINE_SILENT_MINUTES starts a new span
+CREATE TABLE IF NOT EXISTS machine_uptime_spans (
+    id INTEGER PRIMARY KEY AUTOINCREMENT,
+    machine_id INTEGER NOT NULL REFERENCES machines(id),
+    started_at DATETIME NOT NULL DEFAULT (datetime('now')),
+    last_seen_at DATETIME NOT NULL DEFAULT (datetime('now'))
+);
+
+CREATE INDEX IF NOT EXISTS idx_machine_uptime_spans_machine ON machine_uptime_spans(machine_id, last_seen_at);
\ No newline at end of file
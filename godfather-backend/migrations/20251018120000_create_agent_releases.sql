@@ -0,0 +1,20 @@
+CREATE TABLE IF NOT EXISTS agent_releases (
+    id INTEGER PRIMARY KEY AUTOINCREMENT,
+    version TEXT NOT NULL UNIQUE,
+    checksum TEXT NOT NULL,              -- sha256 of the package, hex encoded
+    download_path TEXT NOT NULL,
+    rollout_percent INTEGER NOT NULL DEFAULT 100,
+    zone TEXT,                           -- NULL rolls out to every zone
+    notes TEXT,
+    status TEXT NOT NULL DEFAULT 'active', -- active / withdrawn
+    published_by INTEGER REFERENCES users(id),
+    published_at DATETIME NOT NULL DEFAULT (datetime('now'))
+);
+
+-- machines on these versions cannot host sessions until they update
+CREATE TABLE IF NOT EXISTS agent_blocked_versions (
+    version TEXT PRIMARY KEY,
+    reason TEXT,
+    blocked_by INTEGER REFERENCES users(id),
+    blocked_at DATETIME NOT NULL DEFAULT (datetime('now'))
+);
\ No newline at end of file
@@ -0,0 +1,25 @@
+ALTER TABLE machines ADD COLUMN mac_address TEXT;
+ALTER TABLE machines ADD COLUMN power_state TEXT NOT NULL DEFAULT 'on'; -- on / shutdown_pending / off
+
+CREATE TABLE IF NOT EXISTS power_policies (
+    zone TEXT PRIMARY KEY REFERENCES zones(name),
+    enabled INTEGER NOT NULL DEFAULT 1,
+    idle_shutdown_minutes INTEGER NOT NULL DEFAULT 30,
+    shutdown_after TEXT,                 -- HH:MM, idle shutdown only from here until wake_at; NULL applies all day
+    wake_at TEXT,                        -- HH:MM, machines that were shut down are woken from this time
+    idle_watts INTEGER NOT NULL DEFAULT 90,
+    off_watts INTEGER NOT NULL DEFAULT 2,
+    last_wake_on TEXT,                   -- date of the last wake run, so it happens once a day
+    updated_by INTEGER REFERENCES users(id),
+    updated_at DATETIME NOT NULL DEFAULT (datetime('now'))
+);
+
+-- stretches a machine spent switched off by a power policy
+CREATE TABLE IF NOT EXISTS machine_power_offs (
+    id INTEGER PRIMARY KEY AUTOINCREMENT,
+    machine_id INTEGER NOT NULL REFERENCES machines(id),
+    started_at DATETIME NOT NULL DEFAULT (datetime('now')),
+    ended_at DATETIME
+);
+
+CREATE INDEX IF NOT EXISTS idx_machine_power_offs_machine ON machine_power_offs(machine_id, started_at);
\ No newline at end of file
@@ -0,0 +1,14 @@
+-- applications the agent saw running during a session
+CREATE TABLE IF NOT EXISTS app_usage (
+    id INTEGER PRIMARY KEY AUTOINCREMENT,
+    session_id INTEGER NOT NULL REFERENCES sessions(id),
+    machine_id INTEGER NOT NULL REFERENCES machines(id),
+    user_id INTEGER NOT NULL REFERENCES users(id),
+    app TEXT NOT NULL,
+    started_at DATETIME NOT NULL DEFAULT (datetime('now')),
+    ended_at DATETIME
+);
+
+CREATE UNIQUE INDEX IF NOT EXISTS idx_app_usage_running ON app_usage(session_id, app) WHERE ended_at IS NULL;
+CREATE INDEX IF NOT EXISTS idx_app_usage_app ON app_usage(app, started_at);
+CREATE INDEX IF NOT EXISTS idx_app_usage_user ON app_usage(user_id, started_at);
\ No newline at end of file
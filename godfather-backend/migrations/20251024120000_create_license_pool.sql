@@ -0,0 +1,48 @@
+-- launcher accounts the venue owns for a title, lent out to sessions
+CREATE TABLE IF NOT EXISTS license_titles (
+    id INTEGER PRIMARY KEY AUTOINCREMENT,
+    title TEXT NOT NULL UNIQUE,
+    max_concurrent INTEGER,              -- NULL lets every account in the pool be out at once
+    created_at DATETIME NOT NULL DEFAULT (datetime('now'))
+);
+
+CREATE TABLE IF NOT EXISTS license_accounts (
+    id INTEGER PRIMARY KEY AUTOINCREMENT,
+    title_id INTEGER NOT NULL REFERENCES license_titles(id),
+    label TEXT,
+    username TEXT NOT NULL,
+    password_ciphertext TEXT NOT NULL,   -- base64 of the AES-256-GCM nonce followed by the sealed password
+    disabled_at DATETIME,
+    created_at DATETIME NOT NULL DEFAULT (datetime('now'))
+);
+
+CREATE UNIQUE INDEX IF NOT EXISTS idx_license_accounts_username ON license_accounts(title_id, username) WHERE disabled_at IS NULL;
+
+CREATE TABLE IF NOT EXISTS license_checkouts (
+    id INTEGER PRIMARY KEY AUTOINCREMENT,
+    title_id INTEGER NOT NULL REFERENCES license_titles(id),
+    account_id INTEGER NOT NULL REFERENCES license_accounts(id),
+    session_id INTEGER NOT NULL REFERENCES sessions(id),
+    user_id INTEGER NOT NULL REFERENCES users(id),
+    machine_id INTEGER NOT NULL REFERENCES machines(id),
+    checked_out_at DATETIME NOT NULL DEFAULT (datetime('now')),
+    delivered_at DATETIME,               -- when the agent on machine_id was handed the credentials
+    returned_at DATETIME,
+    return_reason TEXT                   -- returned / session_end
+);
+
+CREATE UNIQUE INDEX IF NOT EXISTS idx_license_checkouts_account ON license_checkouts(account_id) WHERE returned_at IS NULL;
+CREATE UNIQUE INDEX IF NOT EXISTS idx_license_checkouts_session ON license_checkouts(session_id, title_id) WHERE returned_at IS NULL;
+
+CREATE TABLE IF NOT EXISTS license_waitlist (
+    id INTEGER PRIMARY KEY AUTOINCREMENT,
+    title_id INTEGER NOT NULL REFERENCES license_titles(id),
+    user_id INTEGER NOT NULL REFERENCES users(id),
+    session_id INTEGER NOT NULL REFERENCES sessions(id),
+    status TEXT NOT NULL DEFAULT 'waiting', -- waiting / fulfilled / cancelled
+    created_at DATETIME NOT NULL DEFAULT (datetime('now')),
+    resolved_at DATETIME
+);
+
+CREATE UNIQUE INDEX IF NOT EXISTS idx_license_waitlist_live ON license_waitlist(session_id, title_id) WHERE status = 'waiting';
+CREATE INDEX IF NOT EXISTS idx_license_waitlist_title ON license_waitlist(title_id, status, created_at);
\ No newline at end of file
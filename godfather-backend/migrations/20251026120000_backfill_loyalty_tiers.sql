@@ -0,0 +1,9 @@
+-- users who played before tiers existed were never placed in one
+UPDATE users
+SET loyalty_tier_id = (
+    SELECT t.id FROM loyalty_tiers t
+    WHERE t.min_hours <= COALESCE(users.lifetime_hours, 0)
+    ORDER BY t.min_hours DESC
+    LIMIT 1
+)
+WHERE loyalty_tier_id IS NULL;
\ No newline at end of file
@@ -0,0 +1,2 @@
+-- machines registered or heartbeating before statuses were unified were left ONLINE
+UPDATE machines SET status = 'available' WHERE status = 'ONLINE';
\ No newline at end of file
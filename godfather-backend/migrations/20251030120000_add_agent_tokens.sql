@@ -0,0 +1,3 @@
+-- SHA-256 of the secret handed to a machine's agent at registration. Machines
+-- enrolled before agents had to authenticate have none and must be re-enrolled.
+ALTER TABLE machines ADD COLUMN agent_token_hash TEXT;
\ No newline at end of file
@@ -0,0 +1,5 @@
+-- How a receipt was paid when no money changed hands at issue time. Session
+-- receipts are settled from minutes prepaid on an earlier top-up receipt.
+ALTER TABLE receipts ADD COLUMN settled_by TEXT;
+
+UPDATE receipts SET settled_by = 'prepaid_minutes' WHERE kind = 'session';
\ No newline at end of file
@@ -0,0 +1,5 @@
+-- Credentials are handed to the agent on every heartbeat until it confirms the
+-- sign-in, which is when delivered_at is now set. offered_at is the first hand-out.
+ALTER TABLE license_checkouts ADD COLUMN offered_at DATETIME;
+
+UPDATE license_checkouts SET offered_at = delivered_at WHERE delivered_at IS NOT NULL;
\ No newline at end of file
@@ -11,6 +11,12 @@ pub struct JwtClaims {
     pub iat: usize,       // issued at
 }
 
+impl JwtClaims {
+    pub fn user_id(&self) -> Option<i64> {
+        self.sub.parse().ok()
+    }
+}
+
 pub fn create_token(user_id: i64, username: &str, role: &str, secret: &str) -> Result<String, Box<dyn std::error::Error>> {
     let now = SystemTime::now()
         .duration_since(UNIX_EPOCH)?
@@ -0,0 +1,264 @@
+use axum::{
+    Extension, Json,
+    extract::{Path, Query, State},
+    http::StatusCode,
+};
+use serde::Serialize;
+use sqlx::SqliteConnection;
+use crate::{
+    auth::jwt::JwtClaims,
+    models::adjustment::{BalanceAdjustment, CreateAdjustmentReq, ReviewAdjustmentReq, AdjustmentFilter},
+    services::notification_service,
+    state::AppState,
+};
+
+// adjustments larger than this many minutes wait for a second admin
+const DEFAULT_APPROVAL_THRESHOLD: i64 = 60;
+
+#[derive(Serialize)]
+pub struct AdjustmentResponse {
+    pub adjustment: BalanceAdjustment,
+    pub message: String,
+}
+
+#[derive(Serialize)]
+pub struct AdjustmentsResponse {
+    pub adjustments: Vec<BalanceAdjustment>,
+    pub message: String,
+}
+
+fn approval_threshold() -> i64 {
+    std::env::var("ADJUSTMENT_APPROVAL_THRESHOLD")
+        .ok()
+        .and_then(|v| v.parse().ok())
+        .unwrap_or(DEFAULT_APPROVAL_THRESHOLD)
+}
+
+async fn apply_adjustment(
+    conn: &mut SqliteConnection,
+    adjustment: &BalanceAdjustment,
+) -> Result<(), (StatusCode, String)> {
+    let result = if adjustment.direction == "credit" {
+        sqlx::query("UPDATE users SET minutes_balance = minutes_balance + ? WHERE id = ?")
+            .bind(adjustment.minutes)
+            .bind(adjustment.user_id)
+            .execute(&mut *conn)
+            .await
+    } else {
+        // debits come out of the paid balance first and only then out of bonus minutes
+        sqlx::query(
+            "UPDATE users
+             SET bonus_minutes = bonus_minutes - MAX(? - MAX(minutes_balance, 0), 0),
+                 minutes_balance = minutes_balance - MIN(?, MAX(minutes_balance, 0))
+             WHERE id = ? AND MAX(minutes_balance, 0) + bonus_minutes >= ?",
+        )
+        .bind(adjustment.minutes)
+        .bind(adjustment.minutes)
+        .bind(adjustment.user_id)
+        .bind(adjustment.minutes)
+        .execute(&mut *conn)
+        .await
+    }
+    .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, format!("Failed to apply adjustment: {}", e)))?;
+
+    if result.rows_affected() == 0 {
+        return Err((StatusCode::CONFLICT, "Debit exceeds the user's minutes and bonus balance".to_string()));
+    }
+
+    let verb = if adjustment.direction == "credit" { "credited to" } else { "debited from" };
+    let message = format!("{} minutes {} your balance: {}", adjustment.minutes, verb, adjustment.reason);
+    notification_service::notify_user(conn, adjustment.user_id, "balance_adjusted", &message)
+        .await
+        .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, format!("Failed to notify user: {}", e)))?;
+
+    Ok(())
+}
+
+pub async fn create_adjustment(
+    Path(user_id): Path<i64>,
+    State(state): State<AppState>,
+    Extension(claims): Extension<JwtClaims>,
+    Json(req): Json<CreateAdjustmentReq>,
+) -> Result<Json<AdjustmentResponse>, (StatusCode, String)> {
+    let admin_id = claims.user_id()
+        .ok_or((StatusCode::UNAUTHORIZED, "Invalid token subject".to_string()))?;
+
+    if req.direction != "credit" && req.direction != "debit" {
+        return Err((StatusCode::BAD_REQUEST, "Direction must be 'credit' or 'debit'".to_string()));
+    }
+    if req.minutes <= 0 {
+        return Err((StatusCode::BAD_REQUEST, "Minutes must be positive".to_string()));
+    }
+    if req.reason.trim().is_empty() {
+        return Err((StatusCode::BAD_REQUEST, "A reason is required".to_string()));
+    }
+
+    let mut tx = state.pool.begin()
+        .await
+        .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, format!("Database error: {}", e)))?;
+
+    sqlx::query("SELECT 1 FROM users WHERE id = ?")
+        .bind(user_id)
+        .fetch_optional(&mut *tx)
+        .await
+        .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, format!("Database error: {}", e)))?
+        .ok_or((StatusCode::NOT_FOUND, "User not found".to_string()))?;
+
+    if let Some(session_id) = req.session_id {
+        sqlx::query("SELECT 1 FROM sessions WHERE id = ? AND user_id = ?")
+            .bind(session_id)
+            .bind(user_id)
+            .fetch_optional(&mut *tx)
+            .await
+            .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, format!("Database error: {}", e)))?
+            .ok_or((StatusCode::BAD_REQUEST, "Session does not belong to this user".to_string()))?;
+    }
+
+    let needs_approval = req.minutes > approval_threshold();
+
+    let adjustment = sqlx::query_as::<_, BalanceAdjustment>(
+        "INSERT INTO balance_adjustments (user_id, session_id, direction, minutes, reason, status, requested_by)
+         VALUES (?, ?, ?, ?, ?, ?, ?)
+         RETURNING id, user_id, session_id, direction, minutes, reason, status, requested_by, reviewed_by, review_note, created_at, reviewed_at",
+    )
+    .bind(user_id)
+    .bind(req.session_id)
+    .bind(&req.direction)
+    .bind(req.minutes)
+    .bind(req.reason.trim())
+    .bind(if needs_approval { "pending" } else { "applied" })
+    .bind(admin_id)
+    .fetch_one(&mut *tx)
+    .await
+    .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, format!("Failed to create adjustment: {}", e)))?;
+
+    if !needs_approval {
+        apply_adjustment(&mut tx, &adjustment).await?;
+    }
+
+    tx.commit()
+        .await
+        .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, format!("Failed to create adjustment: {}", e)))?;
+
+    let message = if needs_approval {
+        format!("Adjustment of {} minutes is above the approval threshold and awaits a second admin", adjustment.minutes)
+    } else {
+        format!("Adjustment of {} minutes applied", adjustment.minutes)
+    };
+
+    Ok(Json(AdjustmentResponse { adjustment, message }))
+}
+
+pub async fn get_adjustments(
+    State(state): State<AppState>,
+    Query(filter): Query<AdjustmentFilter>,
+) -> Result<Json<AdjustmentsResponse>, (StatusCode, String)> {
+    let adjustments = sqlx::query_as::<_, BalanceAdjustment>(
+        "SELECT id, user_id, session_id, direction, minutes, reason, status, requested_by, reviewed_by, review_note, created_at, reviewed_at
+         FROM balance_adjustments
+         WHERE (? IS NULL OR status = ?) AND (? IS NULL OR user_id = ?)
+         ORDER BY created_at DESC, id DESC",
+    )
+    .bind(&filter.status)
+    .bind(&filter.status)
+    .bind(filter.user_id)
+    .bind(filter.user_id)
+    .fetch_all(&state.pool)
+    .await
+    .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, format!("Failed to fetch adjustments: {}", e)))?;
+
+    let response = AdjustmentsResponse {
+        adjustments,
+        message: "Adjustments retrieved successfully".to_string(),
+    };
+
+    Ok(Json(response))
+}
+
+async fn review_adjustment(
+    state: &AppState,
+    adjustment_id: i64,
+    claims: &JwtClaims,
+    note: Option<String>,
+    approve: bool,
+) -> Result<BalanceAdjustment, (StatusCode, String)> {
+    let admin_id = claims.user_id()
+        .ok_or((StatusCode::UNAUTHORIZED, "Invalid token subject".to_string()))?;
+
+    let mut tx = state.pool.begin()
+        .await
+        .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, format!("Database error: {}", e)))?;
+
+    let pending = sqlx::query_as::<_, BalanceAdjustment>(
+        "SELECT id, user_id, session_id, direction, minutes, reason, status, requested_by, reviewed_by, review_note, created_at, reviewed_at
+         FROM balance_adjustments WHERE id = ?",
+    )
+    .bind(adjustment_id)
+    .fetch_optional(&mut *tx)
+    .await
+    .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, format!("Database error: {}", e)))?
+    .ok_or((StatusCode::NOT_FOUND, "Adjustment not found".to_string()))?;
+
+    if pending.status != "pending" {
+        return Err((StatusCode::CONFLICT, format!("Adjustment is already {}", pending.status)));
+    }
+    if pending.requested_by == admin_id {
+        return Err((StatusCode::FORBIDDEN, "Adjustments must be reviewed by a different admin".to_string()));
+    }
+
+    let adjustment = sqlx::query_as::<_, BalanceAdjustment>(
+        "UPDATE balance_adjustments
+         SET status = ?, reviewed_by = ?, review_note = ?, reviewed_at = datetime('now')
+         WHERE id = ?
+         RETURNING id, user_id, session_id, direction, minutes, reason, status, requested_by, reviewed_by, review_note, created_at, reviewed_at",
+    )
+    .bind(if approve { "applied" } else { "rejected" })
+    .bind(admin_id)
+    .bind(&note)
+    .bind(adjustment_id)
+    .fetch_one(&mut *tx)
+    .await
+    .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, format!("Failed to review adjustment: {}", e)))?;
+
+    if approve {
+        apply_adjustment(&mut tx, &adjustment).await?;
+    }
+
+    tx.commit()
+        .await
+        .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, format!("Failed to review adjustment: {}", e)))?;
+
+    Ok(adjustment)
+}
+
+pub async fn approve_adjustment(
+    Path(adjustment_id): Path<i64>,
+    State(state): State<AppState>,
+    Extension(claims): Extension<JwtClaims>,
+    Json(req): Json<ReviewAdjustmentReq>,
+) -> Result<Json<AdjustmentResponse>, (StatusCode, String)> {
+    let adjustment = review_adjustment(&state, adjustment_id, &claims, req.note, true).await?;
+
+    let response = AdjustmentResponse {
+        message: format!("Adjustment {} approved and applied", adjustment.id),
+        adjustment,
+    };
+
+    Ok(Json(response))
+}
+
+pub async fn reject_adjustment(
+    Path(adjustment_id): Path<i64>,
+    State(state): State<AppState>,
+    Extension(claims): Extension<JwtClaims>,
+    Json(req): Json<ReviewAdjustmentReq>,
+) -> Result<Json<AdjustmentResponse>, (StatusCode, String)> {
+    let adjustment = review_adjustment(&state, adjustment_id, &claims, req.note, false).await?;
+
+    let response = AdjustmentResponse {
+        message: format!("Adjustment {} rejected", adjustment.id),
+        adjustment,
+    };
+
+    Ok(Json(response))
+}
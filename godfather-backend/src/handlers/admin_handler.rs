@@ -8,6 +8,7 @@ use crate::{
     models::user::User,
     state::AppState,
     models::session, 
+    services::session_service::{self, SessionEnd},
 };
 
 #[derive(Serialize)]
@@ -34,13 +35,24 @@ pub async fn ban_user(
 ) -> Result<Json<UserResponse>, (StatusCode, String)> {
     let user = sqlx::query_as::<_, User>(
         "UPDATE users SET banned = 1 WHERE id = ? 
-         RETURNING id, username, email, role, banned, created_at, last_login, balance, minutes_balance, bonus_minutes, password_hash",
+         RETURNING id, username, email, role, banned, created_at, last_login, balance, minutes_balance, bonus_minutes, lifetime_hours, password_hash",
     )
     .bind(user_id)
     .fetch_one(&state.pool)
     .await
     .map_err(|_| (StatusCode::NOT_FOUND, "User not found".to_string()))?;
 
+    // a banned user is taken off any machine they are on
+    let open_sessions: Vec<i64> = sqlx::query_scalar("SELECT id FROM sessions WHERE user_id = ? AND ended_at IS NULL")
+        .bind(user_id)
+        .fetch_all(&state.pool)
+        .await
+        .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, format!("Failed to fetch sessions: {}", e)))?;
+
+    for session_id in open_sessions {
+        session_service::close_session(&state.pool, session_id, SessionEnd::new("ban")).await?;
+    }
+
     let username = user.username.clone(); 
     let response = UserResponse {
         user, 
@@ -56,7 +68,7 @@ pub async fn unban_user(
 ) -> Result<Json<UserResponse>, (StatusCode, String)> {
     let user = sqlx::query_as::<_, User>(
         "UPDATE users SET banned = 0 WHERE id = ? 
-         RETURNING id, username, email, role, banned, created_at, last_login, balance, minutes_balance, bonus_minutes, password_hash",
+         RETURNING id, username, email, role, banned, created_at, last_login, balance, minutes_balance, bonus_minutes, lifetime_hours, password_hash",
     )
     .bind(user_id)
     .fetch_one(&state.pool)
@@ -94,7 +106,7 @@ pub async fn get_active_sessions(
     State(state): State<AppState>,
 ) -> Result<Json<SessionsResponse>, (StatusCode, String)> {
     let sessions = sqlx::query_as::<_, session::Session>( 
-        "SELECT id, user_id, machine_id, started_at, ended_at, minutes_consumed FROM sessions WHERE ended_at IS NULL ORDER BY started_at DESC"
+        "SELECT id, user_id, machine_id, started_at, ended_at, minutes_consumed, included_minutes, charged_minutes, paused_minutes, end_reason FROM sessions WHERE ended_at IS NULL ORDER BY started_at DESC"
     )
     .fetch_all(&state.pool)
     .await
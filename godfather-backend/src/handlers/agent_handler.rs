@@ -0,0 +1,298 @@
+use axum::{
+    Extension, Json,
+    extract::{Path, State},
+    http::StatusCode,
+};
+use serde::Serialize;
+use crate::{
+    auth::jwt::JwtClaims,
+    models::agent::{
+        AgentRelease, AgentVersionCount, BlockVersionReq, BlockedAgentVersion, PublishReleaseReq, UpdateReleaseReq,
+    },
+    services::agent_service::{self, RELEASE_COLUMNS},
+    state::AppState,
+};
+
+#[derive(Serialize)]
+pub struct ReleaseResponse {
+    pub release: AgentRelease,
+    pub message: String,
+}
+
+#[derive(Serialize)]
+pub struct ReleasesResponse {
+    pub releases: Vec<AgentRelease>,
+    pub message: String,
+}
+
+#[derive(Serialize)]
+pub struct BlockedVersionResponse {
+    pub blocked: BlockedAgentVersion,
+    pub message: String,
+}
+
+#[derive(Serialize)]
+pub struct BlockedVersionsResponse {
+    pub blocked: Vec<BlockedAgentVersion>,
+    pub message: String,
+}
+
+#[derive(Serialize)]
+pub struct FleetVersionsResponse {
+    pub versions: Vec<AgentVersionCount>,
+    pub message: String,
+}
+
+fn validate_version(version: &str) -> Result<(), (StatusCode, String)> {
+    if version.is_empty() || !version.chars().all(|c| c.is_ascii_alphanumeric() || matches!(c, '.' | '-' | '+')) {
+        return Err((StatusCode::BAD_REQUEST, "Version may only contain letters, digits, dots, dashes and plus signs".to_string()));
+    }
+    Ok(())
+}
+
+fn validate_rollout(rollout_percent: Option<i64>) -> Result<(), (StatusCode, String)> {
+    if rollout_percent.is_some_and(|p| !(0..=100).contains(&p)) {
+        return Err((StatusCode::BAD_REQUEST, "Rollout percentage must be between 0 and 100".to_string()));
+    }
+    Ok(())
+}
+
+async fn validate_zone(pool: &sqlx::SqlitePool, zone: Option<&str>) -> Result<(), (StatusCode, String)> {
+    let Some(zone) = zone.filter(|z| !z.is_empty()) else {
+        return Ok(());
+    };
+
+    let known = sqlx::query("SELECT 1 FROM zones WHERE name = ?")
+        .bind(zone)
+        .fetch_optional(pool)
+        .await
+        .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, format!("Database error: {}", e)))?;
+
+    match known {
+        Some(_) => Ok(()),
+        None => Err((StatusCode::BAD_REQUEST, format!("Unknown zone {}", zone))),
+    }
+}
+
+pub async fn get_releases(
+    State(state): State<AppState>,
+) -> Result<Json<ReleasesResponse>, (StatusCode, String)> {
+    let mut releases = sqlx::query_as::<_, AgentRelease>(&format!("SELECT {} FROM agent_releases", RELEASE_COLUMNS))
+        .fetch_all(&state.pool)
+        .await
+        .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, format!("Failed to fetch releases: {}", e)))?;
+
+    releases.sort_by(|a, b| agent_service::compare_versions(&b.version, &a.version));
+
+    let response = ReleasesResponse {
+        releases,
+        message: "Agent releases retrieved successfully".to_string(),
+    };
+
+    Ok(Json(response))
+}
+
+pub async fn publish_release(
+    State(state): State<AppState>,
+    Extension(claims): Extension<JwtClaims>,
+    Json(req): Json<PublishReleaseReq>,
+) -> Result<Json<ReleaseResponse>, (StatusCode, String)> {
+    let staff_id = claims.user_id()
+        .ok_or((StatusCode::UNAUTHORIZED, "Invalid token subject".to_string()))?;
+
+    let version = req.version.trim();
+    validate_version(version)?;
+    validate_rollout(req.rollout_percent)?;
+
+    let checksum = req.checksum.trim().to_lowercase();
+    if checksum.len() != 64 || !checksum.chars().all(|c| c.is_ascii_hexdigit()) {
+        return Err((StatusCode::BAD_REQUEST, "Checksum must be a hex encoded SHA-256 digest".to_string()));
+    }
+
+    if req.download_path.trim().is_empty() {
+        return Err((StatusCode::BAD_REQUEST, "Download path is required".to_string()));
+    }
+
+    let zone = req.zone.as_deref().map(str::trim).filter(|z| !z.is_empty());
+    validate_zone(&state.pool, zone).await?;
+
+    let release = sqlx::query_as::<_, AgentRelease>(&format!(
+        "INSERT INTO agent_releases (version, checksum, download_path, rollout_percent, zone, notes, published_by)
+         VALUES (?, ?, ?, ?, ?, ?, ?)
+         RETURNING {}",
+        RELEASE_COLUMNS
+    ))
+    .bind(version)
+    .bind(&checksum)
+    .bind(req.download_path.trim())
+    .bind(req.rollout_percent.unwrap_or(100))
+    .bind(zone)
+    .bind(&req.notes)
+    .bind(staff_id)
+    .fetch_one(&state.pool)
+    .await
+    .map_err(|e| match e {
+        sqlx::Error::Database(ref d) if d.is_unique_violation() => {
+            (StatusCode::CONFLICT, format!("Version {} is already published", version))
+        }
+        e => (StatusCode::INTERNAL_SERVER_ERROR, format!("Failed to publish release: {}", e)),
+    })?;
+
+    let response = ReleaseResponse {
+        message: format!("Agent {} published to {}% of machines", release.version, release.rollout_percent),
+        release,
+    };
+
+    Ok(Json(response))
+}
+
+pub async fn update_release(
+    State(state): State<AppState>,
+    Path(release_id): Path<i64>,
+    Json(req): Json<UpdateReleaseReq>,
+) -> Result<Json<ReleaseResponse>, (StatusCode, String)> {
+    validate_rollout(req.rollout_percent)?;
+
+    if req.status.as_deref().is_some_and(|s| s != "active" && s != "withdrawn") {
+        return Err((StatusCode::BAD_REQUEST, "Status must be active or withdrawn".to_string()));
+    }
+
+    let zone = req.zone.as_deref().map(str::trim);
+    validate_zone(&state.pool, zone).await?;
+
+    let release = sqlx::query_as::<_, AgentRelease>(&format!(
+        "UPDATE agent_releases
+         SET rollout_percent = COALESCE(?, rollout_percent),
+             zone = CASE WHEN ? IS NULL THEN zone ELSE NULLIF(?, '') END,
+             status = COALESCE(?, status)
+         WHERE id = ?
+         RETURNING {}",
+        RELEASE_COLUMNS
+    ))
+    .bind(req.rollout_percent)
+    .bind(zone)
+    .bind(zone)
+    .bind(&req.status)
+    .bind(release_id)
+    .fetch_optional(&state.pool)
+    .await
+    .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, format!("Failed to update release: {}", e)))?
+    .ok_or((StatusCode::NOT_FOUND, "Release not found".to_string()))?;
+
+    let response = ReleaseResponse {
+        release,
+        message: "Agent release updated".to_string(),
+    };
+
+    Ok(Json(response))
+}
+
+pub async fn get_blocked_versions(
+    State(state): State<AppState>,
+) -> Result<Json<BlockedVersionsResponse>, (StatusCode, String)> {
+    let blocked = sqlx::query_as::<_, BlockedAgentVersion>(
+        "SELECT version, reason, blocked_by, blocked_at FROM agent_blocked_versions ORDER BY blocked_at DESC",
+    )
+    .fetch_all(&state.pool)
+    .await
+    .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, format!("Failed to fetch blocked versions: {}", e)))?;
+
+    let response = BlockedVersionsResponse {
+        blocked,
+        message: "Blocked agent versions retrieved successfully".to_string(),
+    };
+
+    Ok(Json(response))
+}
+
+pub async fn block_version(
+    State(state): State<AppState>,
+    Extension(claims): Extension<JwtClaims>,
+    Json(req): Json<BlockVersionReq>,
+) -> Result<Json<BlockedVersionResponse>, (StatusCode, String)> {
+    let staff_id = claims.user_id()
+        .ok_or((StatusCode::UNAUTHORIZED, "Invalid token subject".to_string()))?;
+
+    let version = req.version.trim();
+    validate_version(version)?;
+
+    let blocked = sqlx::query_as::<_, BlockedAgentVersion>(
+        "INSERT INTO agent_blocked_versions (version, reason, blocked_by) VALUES (?, ?, ?)
+         RETURNING version, reason, blocked_by, blocked_at",
+    )
+    .bind(version)
+    .bind(&req.reason)
+    .bind(staff_id)
+    .fetch_one(&state.pool)
+    .await
+    .map_err(|e| match e {
+        sqlx::Error::Database(ref d) if d.is_unique_violation() => {
+            (StatusCode::CONFLICT, format!("Version {} is already blocked", version))
+        }
+        e => (StatusCode::INTERNAL_SERVER_ERROR, format!("Failed to block version: {}", e)),
+    })?;
+
+    // running sessions are left alone, the gate applies from the next session on
+    let affected: i64 = sqlx::query_scalar("SELECT COUNT(*) FROM machines WHERE agent_version = ? AND decommissioned_at IS NULL")
+        .bind(version)
+        .fetch_one(&state.pool)
+        .await
+        .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, format!("Database error: {}", e)))?;
+
+    let response = BlockedVersionResponse {
+        blocked,
+        message: format!("Version {} blocked, {} machine(s) will refuse new sessions until updated", version, affected),
+    };
+
+    Ok(Json(response))
+}
+
+pub async fn unblock_version(
+    State(state): State<AppState>,
+    Path(version): Path<String>,
+) -> Result<Json<BlockedVersionResponse>, (StatusCode, String)> {
+    let blocked = sqlx::query_as::<_, BlockedAgentVersion>(
+        "DELETE FROM agent_blocked_versions WHERE version = ?
+         RETURNING version, reason, blocked_by, blocked_at",
+    )
+    .bind(&version)
+    .fetch_optional(&state.pool)
+    .await
+    .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, format!("Failed to unblock version: {}", e)))?
+    .ok_or((StatusCode::NOT_FOUND, "Version is not blocked".to_string()))?;
+
+    let response = BlockedVersionResponse {
+        blocked,
+        message: format!("Version {} unblocked", version),
+    };
+
+    Ok(Json(response))
+}
+
+pub async fn get_fleet_versions(
+    State(state): State<AppState>,
+) -> Result<Json<FleetVersionsResponse>, (StatusCode, String)> {
+    let mut versions = sqlx::query_as::<_, AgentVersionCount>(
+        "SELECT m.agent_version AS version, COUNT(*) AS machines,
+                EXISTS (SELECT 1 FROM agent_blocked_versions b WHERE b.version = m.agent_version) AS blocked
+         FROM machines m
+         WHERE m.decommissioned_at IS NULL
+         GROUP BY m.agent_version",
+    )
+    .fetch_all(&state.pool)
+    .await
+    .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, format!("Failed to fetch agent versions: {}", e)))?;
+
+    // newest first, machines that never reported a version last
+    versions.sort_by(|a, b| match (&a.version, &b.version) {
+        (Some(a), Some(b)) => agent_service::compare_versions(b, a),
+        (a, b) => b.is_some().cmp(&a.is_some()),
+    });
+
+    let response = FleetVersionsResponse {
+        versions,
+        message: "Agent versions retrieved successfully".to_string(),
+    };
+
+    Ok(Json(response))
+}
\ No newline at end of file
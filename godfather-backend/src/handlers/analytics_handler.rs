@@ -0,0 +1,97 @@
+use axum::{
+    Json,
+    extract::{Query, State},
+    http::StatusCode,
+};
+use serde::Serialize;
+use crate::{
+    models::analytics::{AnalyticsQuery, HeatmapCell, MachineUtilization, ZoneUtilization},
+    services::analytics_service::{self, AnalyticsRange},
+    state::AppState,
+};
+
+#[derive(Serialize)]
+pub struct MachineAnalyticsResponse {
+    pub from: String,
+    pub to: String,
+    pub machines: Vec<MachineUtilization>,
+    pub message: String,
+}
+
+#[derive(Serialize)]
+pub struct ZoneAnalyticsResponse {
+    pub from: String,
+    pub to: String,
+    pub zones: Vec<ZoneUtilization>,
+    pub message: String,
+}
+
+#[derive(Serialize)]
+pub struct HeatmapResponse {
+    pub from: String,
+    pub to: String,
+    pub cells: Vec<HeatmapCell>,
+    pub message: String,
+}
+
+const TIME_FORMAT: &str = "%Y-%m-%d %H:%M:%S";
+
+pub async fn get_machine_analytics(
+    State(state): State<AppState>,
+    Query(query): Query<AnalyticsQuery>,
+) -> Result<Json<MachineAnalyticsResponse>, (StatusCode, String)> {
+    let range = AnalyticsRange::from_query(&query)?;
+
+    let machines = analytics_service::machine_utilization(&state.pool, &range, &query.zone)
+        .await
+        .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, format!("Failed to build machine analytics: {}", e)))?;
+
+    let response = MachineAnalyticsResponse {
+        from: range.from.format(TIME_FORMAT).to_string(),
+        to: range.to.format(TIME_FORMAT).to_string(),
+        machines,
+        message: "Machine analytics retrieved successfully".to_string(),
+    };
+
+    Ok(Json(response))
+}
+
+pub async fn get_zone_analytics(
+    State(state): State<AppState>,
+    Query(query): Query<AnalyticsQuery>,
+) -> Result<Json<ZoneAnalyticsResponse>, (StatusCode, String)> {
+    let range = AnalyticsRange::from_query(&query)?;
+
+    let machines = analytics_service::machine_utilization(&state.pool, &range, &query.zone)
+        .await
+        .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, format!("Failed to build zone analytics: {}", e)))?;
+
+    let response = ZoneAnalyticsResponse {
+        from: range.from.format(TIME_FORMAT).to_string(),
+        to: range.to.format(TIME_FORMAT).to_string(),
+        zones: analytics_service::zone_utilization(&machines, &range),
+        message: "Zone analytics retrieved successfully".to_string(),
+    };
+
+    Ok(Json(response))
+}
+
+pub async fn get_heatmap(
+    State(state): State<AppState>,
+    Query(query): Query<AnalyticsQuery>,
+) -> Result<Json<HeatmapResponse>, (StatusCode, String)> {
+    let range = AnalyticsRange::from_query(&query)?;
+
+    let cells = analytics_service::heatmap(&state.pool, &range, &query.zone)
+        .await
+        .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, format!("Failed to build heatmap: {}", e)))?;
+
+    let response = HeatmapResponse {
+        from: range.from.format(TIME_FORMAT).to_string(),
+        to: range.to.format(TIME_FORMAT).to_string(),
+        cells,
+        message: "Heatmap retrieved successfully".to_string(),
+    };
+
+    Ok(Json(response))
+}
\ No newline at end of file
@@ -0,0 +1,176 @@
+use axum::{
+    Extension, Json,
+    extract::{Path, Query, State},
+    http::{HeaderMap, StatusCode},
+};
+use serde::Serialize;
+use crate::{
+    auth::jwt::JwtClaims,
+    models::{
+        analytics::AnalyticsQuery,
+        app_usage::{AppEventsReq, AppUsage, AppUsageSummary},
+    },
+    services::{analytics_service::AnalyticsRange, app_usage_service, machine_service},
+    state::AppState,
+};
+
+// longest window or process title we keep
+const MAX_APP_NAME_LEN: usize = 200;
+
+const TIME_FORMAT: &str = "%Y-%m-%d %H:%M:%S";
+
+#[derive(Serialize)]
+pub struct AppEventsResponse {
+    pub session_id: i64,
+    pub running: Vec<AppUsage>,
+    pub message: String,
+}
+
+#[derive(Serialize)]
+pub struct AppHistoryResponse {
+    pub from: String,
+    pub to: String,
+    pub usage: Vec<AppUsage>,
+    pub message: String,
+}
+
+#[derive(Serialize)]
+pub struct AppReportResponse {
+    pub from: String,
+    pub to: String,
+    pub apps: Vec<AppUsageSummary>,
+    pub message: String,
+}
+
+/// Called by the machine agent when applications start or stop. Events only
+/// count while a session is running on the machine.
+pub async fn record_app_events(
+    State(state): State<AppState>,
+    headers: HeaderMap,
+    Json(req): Json<AppEventsReq>,
+) -> Result<Json<AppEventsResponse>, (StatusCode, String)> {
+    let db_err = |e: sqlx::Error| (StatusCode::INTERNAL_SERVER_ERROR, format!("Failed to record app events: {}", e));
+
+    for event in &req.events {
+        let app = event.app.trim();
+        if app.is_empty() || app.chars().count() > MAX_APP_NAME_LEN {
+            return Err((StatusCode::BAD_REQUEST, format!("App names must be 1 to {} characters", MAX_APP_NAME_LEN)));
+        }
+        if event.event != "start" && event.event != "stop" {
+            return Err((StatusCode::BAD_REQUEST, "Event must be start or stop".to_string()));
+        }
+    }
+
+    let mut tx = state.pool.begin().await.map_err(db_err)?;
+
+    let token = headers.get(machine_service::AGENT_TOKEN_HEADER).and_then(|v| v.to_str().ok());
+    if !machine_service::authenticate_agent(&mut tx, req.machine_id, token).await.map_err(db_err)? {
+        return Err((StatusCode::UNAUTHORIZED, "Invalid machine or agent token".to_string()));
+    }
+
+    let (session_id, user_id): (i64, i64) = sqlx::query_as(
+        "SELECT s.id, s.user_id FROM sessions s
+         JOIN machines m ON m.id = s.machine_id
+         WHERE s.machine_id = ? AND s.ended_at IS NULL AND m.decommissioned_at IS NULL",
+    )
+    .bind(req.machine_id)
+    .fetch_optional(&mut *tx)
+    .await
+    .map_err(db_err)?
+    .ok_or((StatusCode::CONFLICT, "No session is running on this machine".to_string()))?;
+
+    for event in &req.events {
+        let app = event.app.trim();
+
+        if event.event == "start" {
+            // a repeated start for an app already running is ignored
+            sqlx::query(
+                "INSERT INTO app_usage (session_id, machine_id, user_id, app)
+                 SELECT ?, ?, ?, ?
+                 WHERE NOT EXISTS (SELECT 1 FROM app_usage WHERE session_id = ? AND app = ? AND ended_at IS NULL)",
+            )
+            .bind(session_id)
+            .bind(req.machine_id)
+            .bind(user_id)
+            .bind(app)
+            .bind(session_id)
+            .bind(app)
+            .execute(&mut *tx)
+            .await
+            .map_err(db_err)?;
+        } else {
+            sqlx::query("UPDATE app_usage SET ended_at = datetime('now') WHERE session_id = ? AND app = ? AND ended_at IS NULL")
+                .bind(session_id)
+                .bind(app)
+                .execute(&mut *tx)
+                .await
+                .map_err(db_err)?;
+        }
+    }
+
+    let running = app_usage_service::running(&mut tx, session_id).await.map_err(db_err)?;
+
+    tx.commit().await.map_err(db_err)?;
+
+    let response = AppEventsResponse {
+        session_id,
+        running,
+        message: format!("Recorded {} app event(s)", req.events.len()),
+    };
+
+    Ok(Json(response))
+}
+
+async fn history_for(state: &AppState, user_id: i64, query: &AnalyticsQuery) -> Result<AppHistoryResponse, (StatusCode, String)> {
+    let range = AnalyticsRange::from_query(query)?;
+
+    let usage = app_usage_service::history(&state.pool, user_id, &range)
+        .await
+        .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, format!("Failed to fetch play history: {}", e)))?;
+
+    Ok(AppHistoryResponse {
+        from: range.from.format(TIME_FORMAT).to_string(),
+        to: range.to.format(TIME_FORMAT).to_string(),
+        usage,
+        message: "Play history retrieved successfully".to_string(),
+    })
+}
+
+pub async fn get_my_app_history(
+    State(state): State<AppState>,
+    Extension(claims): Extension<JwtClaims>,
+    Query(query): Query<AnalyticsQuery>,
+) -> Result<Json<AppHistoryResponse>, (StatusCode, String)> {
+    let user_id = claims.user_id()
+        .ok_or((StatusCode::UNAUTHORIZED, "Invalid token subject".to_string()))?;
+
+    Ok(Json(history_for(&state, user_id, &query).await?))
+}
+
+pub async fn get_user_app_history(
+    State(state): State<AppState>,
+    Path(user_id): Path<i64>,
+    Query(query): Query<AnalyticsQuery>,
+) -> Result<Json<AppHistoryResponse>, (StatusCode, String)> {
+    Ok(Json(history_for(&state, user_id, &query).await?))
+}
+
+pub async fn get_app_report(
+    State(state): State<AppState>,
+    Query(query): Query<AnalyticsQuery>,
+) -> Result<Json<AppReportResponse>, (StatusCode, String)> {
+    let range = AnalyticsRange::from_query(&query)?;
+
+    let apps = app_usage_service::top_apps(&state.pool, &range, &query.zone)
+        .await
+        .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, format!("Failed to build app report: {}", e)))?;
+
+    let response = AppReportResponse {
+        from: range.from.format(TIME_FORMAT).to_string(),
+        to: range.to.format(TIME_FORMAT).to_string(),
+        apps,
+        message: "App usage retrieved successfully".to_string(),
+    };
+
+    Ok(Json(response))
+}
\ No newline at end of file
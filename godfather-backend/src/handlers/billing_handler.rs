@@ -0,0 +1,71 @@
+use axum::{
+    Json,
+    extract::State,
+    http::StatusCode,
+};
+use serde::Serialize;
+use crate::{
+    models::billing::{BillingPolicy, UpdateBillingPolicyReq},
+    services::billing_service,
+    state::AppState,
+};
+
+#[derive(Serialize)]
+pub struct BillingPolicyResponse {
+    pub policy: BillingPolicy,
+    pub message: String,
+}
+
+pub async fn get_billing_policy(
+    State(state): State<AppState>,
+) -> Result<Json<BillingPolicy>, (StatusCode, String)> {
+    let mut conn = state.pool.acquire()
+        .await
+        .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, format!("Database error: {}", e)))?;
+
+    let policy = billing_service::load_policy(&mut conn)
+        .await
+        .map_err(|_| (StatusCode::INTERNAL_SERVER_ERROR, "Failed to fetch billing policy".to_string()))?;
+
+    Ok(Json(policy))
+}
+
+pub async fn update_billing_policy(
+    State(state): State<AppState>,
+    Json(req): Json<UpdateBillingPolicyReq>,
+) -> Result<Json<BillingPolicyResponse>, (StatusCode, String)> {
+    if req.granularity_seconds.is_some_and(|g| !(1..=3600).contains(&g)) {
+        return Err((StatusCode::BAD_REQUEST, "Granularity must be between 1 and 3600 seconds".to_string()));
+    }
+    if req.rounding.as_deref().is_some_and(|r| r != "up" && r != "down") {
+        return Err((StatusCode::BAD_REQUEST, "Rounding must be up or down".to_string()));
+    }
+    if req.minimum_seconds.is_some_and(|m| m < 0) || req.grace_seconds.is_some_and(|g| g < 0) {
+        return Err((StatusCode::BAD_REQUEST, "Minimum charge and grace period must not be negative".to_string()));
+    }
+
+    let policy = sqlx::query_as::<_, BillingPolicy>(
+        "UPDATE billing_policy
+         SET granularity_seconds = COALESCE(?, granularity_seconds),
+             rounding = COALESCE(?, rounding),
+             minimum_seconds = COALESCE(?, minimum_seconds),
+             grace_seconds = COALESCE(?, grace_seconds),
+             updated_at = datetime('now')
+         WHERE id = 1
+         RETURNING granularity_seconds, rounding, minimum_seconds, grace_seconds, updated_at",
+    )
+    .bind(req.granularity_seconds)
+    .bind(&req.rounding)
+    .bind(req.minimum_seconds)
+    .bind(req.grace_seconds)
+    .fetch_one(&state.pool)
+    .await
+    .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, format!("Failed to update billing policy: {}", e)))?;
+
+    let response = BillingPolicyResponse {
+        policy,
+        message: "Billing policy updated, it applies to sessions ending from now on".to_string(),
+    };
+
+    Ok(Json(response))
+}
\ No newline at end of file
@@ -0,0 +1,105 @@
+use axum::{
+    Extension, Json,
+    extract::{Path, State},
+    http::StatusCode,
+};
+use serde::Serialize;
+use crate::{
+    auth::jwt::JwtClaims,
+    models::machine::Machine,
+    state::AppState,
+};
+
+#[derive(Serialize)]
+pub struct FavoritesResponse {
+    pub machines: Vec<Machine>,
+    pub message: String,
+}
+
+async fn list_favorites(pool: &sqlx::SqlitePool, user_id: i64) -> Result<Vec<Machine>, (StatusCode, String)> {
+    sqlx::query_as::<_, Machine>(
+        "SELECT m.id, m.name, m.status, m.class, m.zone
+         FROM machine_favorites f
+         JOIN machines m ON m.id = f.machine_id
+         WHERE f.user_id = ? AND m.decommissioned_at IS NULL
+         ORDER BY m.name",
+    )
+    .bind(user_id)
+    .fetch_all(pool)
+    .await
+    .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, format!("Failed to fetch favorites: {}", e)))
+}
+
+pub async fn get_my_favorites(
+    State(state): State<AppState>,
+    Extension(claims): Extension<JwtClaims>,
+) -> Result<Json<FavoritesResponse>, (StatusCode, String)> {
+    let user_id = claims.user_id()
+        .ok_or((StatusCode::UNAUTHORIZED, "Invalid token subject".to_string()))?;
+
+    let response = FavoritesResponse {
+        machines: list_favorites(&state.pool, user_id).await?,
+        message: "Favorites retrieved successfully".to_string(),
+    };
+
+    Ok(Json(response))
+}
+
+pub async fn add_favorite(
+    State(state): State<AppState>,
+    Extension(claims): Extension<JwtClaims>,
+    Path(machine_id): Path<i64>,
+) -> Result<Json<FavoritesResponse>, (StatusCode, String)> {
+    let user_id = claims.user_id()
+        .ok_or((StatusCode::UNAUTHORIZED, "Invalid token subject".to_string()))?;
+
+    let added = sqlx::query(
+        "INSERT OR IGNORE INTO machine_favorites (user_id, machine_id)
+         SELECT ?, id FROM machines WHERE id = ? AND decommissioned_at IS NULL",
+    )
+    .bind(user_id)
+    .bind(machine_id)
+    .execute(&state.pool)
+    .await
+    .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, format!("Failed to add favorite: {}", e)))?;
+
+    let machines = list_favorites(&state.pool, user_id).await?;
+
+    if added.rows_affected() == 0 && !machines.iter().any(|m| m.id == machine_id) {
+        return Err((StatusCode::NOT_FOUND, "Machine not found".to_string()));
+    }
+
+    let response = FavoritesResponse {
+        machines,
+        message: "Machine added to favorites".to_string(),
+    };
+
+    Ok(Json(response))
+}
+
+pub async fn remove_favorite(
+    State(state): State<AppState>,
+    Extension(claims): Extension<JwtClaims>,
+    Path(machine_id): Path<i64>,
+) -> Result<Json<FavoritesResponse>, (StatusCode, String)> {
+    let user_id = claims.user_id()
+        .ok_or((StatusCode::UNAUTHORIZED, "Invalid token subject".to_string()))?;
+
+    let removed = sqlx::query("DELETE FROM machine_favorites WHERE user_id = ? AND machine_id = ?")
+        .bind(user_id)
+        .bind(machine_id)
+        .execute(&state.pool)
+        .await
+        .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, format!("Failed to remove favorite: {}", e)))?;
+
+    if removed.rows_affected() == 0 {
+        return Err((StatusCode::NOT_FOUND, "Machine is not in your favorites".to_string()));
+    }
+
+    let response = FavoritesResponse {
+        machines: list_favorites(&state.pool, user_id).await?,
+        message: "Machine removed from favorites".to_string(),
+    };
+
+    Ok(Json(response))
+}
\ No newline at end of file
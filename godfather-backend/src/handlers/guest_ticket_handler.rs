@@ -0,0 +1,266 @@
+use axum::{
+    Extension, Json,
+    extract::{Path, Query, State},
+    http::StatusCode,
+};
+use bcrypt::{hash, DEFAULT_COST};
+use serde::Serialize;
+use crate::{
+    auth::jwt::{JwtClaims, create_token},
+    models::{
+        guest_ticket::{GuestTicket, SellTicketReq, TicketFilter, GuestLoginReq, ConvertTicketReq},
+        receipt::Receipt,
+        session::Session,
+    },
+    services::{code_service::{generate_code, normalize_code}, guest_ticket_service, receipt_service::{self, LineItem}, session_service},
+    state::AppState,
+};
+
+#[derive(Serialize)]
+pub struct SellTicketResponse {
+    pub ticket: GuestTicket,
+    pub receipt: Receipt,
+    pub message: String,
+}
+
+#[derive(Serialize)]
+pub struct TicketsResponse {
+    pub tickets: Vec<GuestTicket>,
+    pub message: String,
+}
+
+#[derive(Serialize)]
+pub struct GuestLoginResponse {
+    pub token: String,
+    pub ticket: GuestTicket,
+    pub session: Session,
+    pub message: String,
+}
+
+#[derive(Serialize)]
+pub struct ConvertTicketResponse {
+    pub user_id: i64,
+    pub username: String,
+    pub minutes_balance: i64,
+    pub message: String,
+}
+
+/// Looks up a ticket that can still be played on.
+async fn usable_ticket(state: &AppState, code: &str) -> Result<GuestTicket, (StatusCode, String)> {
+    let mut conn = state.pool.acquire()
+        .await
+        .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, format!("Database error: {}", e)))?;
+
+    let ticket = guest_ticket_service::find_by_code(&mut conn, &normalize_code(code))
+        .await
+        .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, format!("Database error: {}", e)))?
+        .ok_or((StatusCode::NOT_FOUND, "Ticket not found".to_string()))?;
+
+    let expired = chrono::NaiveDateTime::parse_from_str(&ticket.expires_at, "%Y-%m-%d %H:%M:%S")
+        .map_or(true, |expires| expires <= chrono::Utc::now().naive_utc());
+
+    if ticket.status != "active" || expired {
+        let status = if ticket.status == "active" { "expired" } else { ticket.status.as_str() };
+        return Err((StatusCode::GONE, format!("Ticket is {}", status)));
+    }
+
+    Ok(ticket)
+}
+
+pub async fn sell_ticket(
+    State(state): State<AppState>,
+    Extension(claims): Extension<JwtClaims>,
+    Json(req): Json<SellTicketReq>,
+) -> Result<Json<SellTicketResponse>, (StatusCode, String)> {
+    if req.minutes <= 0 {
+        return Err((StatusCode::BAD_REQUEST, "Minutes must be positive".to_string()));
+    }
+    if req.price.is_some_and(|p| p < 0) {
+        return Err((StatusCode::BAD_REQUEST, "Price must not be negative".to_string()));
+    }
+    if req.valid_days.is_some_and(|d| d <= 0) {
+        return Err((StatusCode::BAD_REQUEST, "Validity must be positive".to_string()));
+    }
+
+    let price = req.price.unwrap_or(req.minutes * receipt_service::minute_rate());
+    let valid_days = req.valid_days.unwrap_or_else(guest_ticket_service::valid_days);
+
+    let db_err = |e: sqlx::Error| (StatusCode::INTERNAL_SERVER_ERROR, format!("Failed to sell ticket: {}", e));
+
+    let mut tx = state.pool.begin().await.map_err(db_err)?;
+
+    let code = generate_code();
+
+    let user_id: i64 = sqlx::query_scalar(
+        "INSERT INTO users (username, role, balance, minutes_balance) VALUES (?, 'guest', 0, ?) RETURNING id",
+    )
+    .bind(format!("guest-{}", code))
+    .bind(req.minutes)
+    .fetch_one(&mut *tx)
+    .await
+    .map_err(db_err)?;
+
+    let ticket_id: i64 = sqlx::query_scalar(
+        "INSERT INTO guest_tickets (code, user_id, minutes_sold, price, expires_at, sold_by)
+         VALUES (?, ?, ?, ?, datetime('now', ?), ?)
+         RETURNING id",
+    )
+    .bind(&code)
+    .bind(user_id)
+    .bind(req.minutes)
+    .bind(price)
+    .bind(format!("+{} days", valid_days))
+    .bind(claims.user_id())
+    .fetch_one(&mut *tx)
+    .await
+    .map_err(db_err)?;
+
+    // the code goes on the receipt so the cashier can hand it over printed
+    let receipt = receipt_service::issue_receipt(
+        &mut tx,
+        Some(user_id),
+        "guest_ticket",
+        Some(ticket_id),
+        vec![LineItem::new(format!("Guest ticket {} ({} min)", code, req.minutes), 1, price)],
+    )
+    .await
+    .map_err(db_err)?;
+
+    let ticket = guest_ticket_service::find_by_code(&mut tx, &code)
+        .await
+        .map_err(db_err)?
+        .ok_or((StatusCode::INTERNAL_SERVER_ERROR, "Ticket vanished".to_string()))?;
+
+    tx.commit().await.map_err(db_err)?;
+
+    let response = SellTicketResponse {
+        message: format!("Guest ticket {} sold with {} minutes", ticket.code, ticket.minutes_sold),
+        ticket,
+        receipt,
+    };
+
+    Ok(Json(response))
+}
+
+pub async fn get_tickets(
+    State(state): State<AppState>,
+    Query(filter): Query<TicketFilter>,
+) -> Result<Json<TicketsResponse>, (StatusCode, String)> {
+    let tickets = sqlx::query_as::<_, GuestTicket>(
+        "SELECT t.id, t.code, t.user_id, t.minutes_sold, u.minutes_balance AS minutes_remaining, t.price, t.status,
+                t.expires_at, t.sold_by, t.created_at, t.converted_at
+         FROM guest_tickets t
+         JOIN users u ON u.id = t.user_id
+         WHERE (? IS NULL OR t.status = ?)
+         ORDER BY t.id DESC",
+    )
+    .bind(&filter.status)
+    .bind(&filter.status)
+    .fetch_all(&state.pool)
+    .await
+    .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, format!("Failed to fetch tickets: {}", e)))?;
+
+    let response = TicketsResponse {
+        tickets,
+        message: "Guest tickets retrieved successfully".to_string(),
+    };
+
+    Ok(Json(response))
+}
+
+/// Lets a guest check what is left on their ticket.
+pub async fn get_ticket(
+    Path(code): Path<String>,
+    State(state): State<AppState>,
+) -> Result<Json<GuestTicket>, (StatusCode, String)> {
+    let mut conn = state.pool.acquire()
+        .await
+        .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, format!("Database error: {}", e)))?;
+
+    let ticket = guest_ticket_service::find_by_code(&mut conn, &normalize_code(&code))
+        .await
+        .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, format!("Database error: {}", e)))?
+        .ok_or((StatusCode::NOT_FOUND, "Ticket not found".to_string()))?;
+
+    Ok(Json(ticket))
+}
+
+/// Signs in with a ticket code at a machine and starts a session on it.
+pub async fn guest_login(
+    State(state): State<AppState>,
+    Json(req): Json<GuestLoginReq>,
+) -> Result<Json<GuestLoginResponse>, (StatusCode, String)> {
+    let ticket = usable_ticket(&state, &req.code).await?;
+
+    if ticket.minutes_remaining <= 0 {
+        return Err((StatusCode::FORBIDDEN, "No time left on this ticket".to_string()));
+    }
+
+    let (session, machine) = session_service::open_session(&state.pool, ticket.user_id, req.machine_id).await?;
+
+    let token = create_token(ticket.user_id, &format!("guest-{}", ticket.code), "guest", &state.jwt_secret)
+        .map_err(|_| (StatusCode::INTERNAL_SERVER_ERROR, "Failed to create token".to_string()))?;
+
+    let response = GuestLoginResponse {
+        message: format!("Session started on machine {} with {} minutes left", machine.name, ticket.minutes_remaining),
+        token,
+        ticket,
+        session,
+    };
+
+    Ok(Json(response))
+}
+
+/// Turns a ticket into a regular account, keeping its minutes and history.
+pub async fn convert_ticket(
+    State(state): State<AppState>,
+    Json(req): Json<ConvertTicketReq>,
+) -> Result<Json<ConvertTicketResponse>, (StatusCode, String)> {
+    let ticket = usable_ticket(&state, &req.code).await?;
+
+    let hashed_password = hash(&req.password, DEFAULT_COST)
+        .map_err(|_| (StatusCode::INTERNAL_SERVER_ERROR, "Failed to hash password".to_string()))?;
+
+    let db_err = |e: sqlx::Error| (StatusCode::INTERNAL_SERVER_ERROR, format!("Failed to convert ticket: {}", e));
+
+    let mut tx = state.pool.begin().await.map_err(db_err)?;
+
+    let taken = sqlx::query("SELECT 1 FROM users WHERE username = ? OR email = ?")
+        .bind(&req.username)
+        .bind(&req.email)
+        .fetch_optional(&mut *tx)
+        .await
+        .map_err(db_err)?;
+
+    if taken.is_some() {
+        return Err((StatusCode::CONFLICT, "Username or email already exists".to_string()));
+    }
+
+    let minutes_balance: i64 = sqlx::query_scalar(
+        "UPDATE users SET username = ?, email = ?, password_hash = ?, role = 'user' WHERE id = ? RETURNING minutes_balance",
+    )
+    .bind(&req.username)
+    .bind(&req.email)
+    .bind(&hashed_password)
+    .bind(ticket.user_id)
+    .fetch_one(&mut *tx)
+    .await
+    .map_err(db_err)?;
+
+    sqlx::query("UPDATE guest_tickets SET status = 'converted', converted_at = datetime('now') WHERE id = ?")
+        .bind(ticket.id)
+        .execute(&mut *tx)
+        .await
+        .map_err(db_err)?;
+
+    tx.commit().await.map_err(db_err)?;
+
+    let response = ConvertTicketResponse {
+        user_id: ticket.user_id,
+        username: req.username,
+        minutes_balance,
+        message: "Ticket converted, log in with your new account".to_string(),
+    };
+
+    Ok(Json(response))
+}
\ No newline at end of file
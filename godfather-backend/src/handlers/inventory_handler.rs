@@ -0,0 +1,561 @@
+use axum::{
+    Extension, Json,
+    extract::{Path, Query, State},
+    http::StatusCode,
+};
+use serde::Serialize;
+use crate::{
+    auth::jwt::JwtClaims,
+    models::{
+        inventory::{
+            AssignTicketReq, AttachPeripheralReq, MachineEnrollment, MachineInventory, MachineSpecs, MaintenanceTicket,
+            OpenTicketReq, Peripheral, ReenrollMachineReq, ResolveTicketReq, TicketFilter, UpdateMachineReq,
+        },
+        machine::Machine,
+    },
+    services::{machine_service, notification_service},
+    state::AppState,
+};
+
+const PERIPHERAL_COLUMNS: &str = "id, machine_id, kind, model, serial, attached_at, detached_at";
+
+const TICKET_COLUMNS: &str =
+    "id, machine_id, title, description, blocking, status, opened_by, opened_at, assigned_to, assigned_at, resolved_by, resolved_at, resolution";
+
+#[derive(Serialize)]
+pub struct MachineInventoryResponse {
+    pub inventory: MachineInventory,
+    pub message: String,
+}
+
+#[derive(Serialize)]
+pub struct PeripheralResponse {
+    pub peripheral: Peripheral,
+    pub message: String,
+}
+
+#[derive(Serialize)]
+pub struct TicketResponse {
+    pub ticket: MaintenanceTicket,
+    pub machine_status: String,
+    pub message: String,
+}
+
+#[derive(Serialize)]
+pub struct TicketsResponse {
+    pub tickets: Vec<MaintenanceTicket>,
+    pub message: String,
+}
+
+async fn load_inventory(pool: &sqlx::SqlitePool, machine_id: i64) -> Result<MachineInventory, (StatusCode, String)> {
+    let db_err = |e: sqlx::Error| (StatusCode::INTERNAL_SERVER_ERROR, format!("Failed to fetch machine: {}", e));
+
+    let machine = sqlx::query_as::<_, Machine>("SELECT id, name, status, class, zone FROM machines WHERE id = ?")
+        .bind(machine_id)
+        .fetch_optional(pool)
+        .await
+        .map_err(db_err)?
+        .ok_or((StatusCode::NOT_FOUND, "Machine not found".to_string()))?;
+
+    let specs = sqlx::query_as::<_, MachineSpecs>("SELECT cpu, gpu, ram_gb, monitor_hz FROM machines WHERE id = ?")
+        .bind(machine_id)
+        .fetch_one(pool)
+        .await
+        .map_err(db_err)?;
+
+    let (decommissioned_at, reenroll_requested_at): (Option<String>, Option<String>) =
+        sqlx::query_as("SELECT decommissioned_at, reenroll_requested_at FROM machines WHERE id = ?")
+            .bind(machine_id)
+            .fetch_one(pool)
+            .await
+            .map_err(db_err)?;
+
+    let peripherals = sqlx::query_as::<_, Peripheral>(&format!(
+        "SELECT {} FROM machine_peripherals WHERE machine_id = ? ORDER BY detached_at IS NOT NULL, attached_at DESC, id DESC",
+        PERIPHERAL_COLUMNS
+    ))
+    .bind(machine_id)
+    .fetch_all(pool)
+    .await
+    .map_err(db_err)?;
+
+    let tickets = sqlx::query_as::<_, MaintenanceTicket>(&format!(
+        "SELECT {} FROM maintenance_tickets WHERE machine_id = ? ORDER BY opened_at DESC, id DESC",
+        TICKET_COLUMNS
+    ))
+    .bind(machine_id)
+    .fetch_all(pool)
+    .await
+    .map_err(db_err)?;
+
+    let enrollments = sqlx::query_as::<_, MachineEnrollment>(
+        "SELECT id, machine_id, requested_by, requested_at, note, enrolled_at
+         FROM machine_enrollments WHERE machine_id = ? ORDER BY requested_at DESC, id DESC",
+    )
+    .bind(machine_id)
+    .fetch_all(pool)
+    .await
+    .map_err(db_err)?;
+
+    Ok(MachineInventory { machine, specs, decommissioned_at, reenroll_requested_at, peripherals, tickets, enrollments })
+}
+
+pub async fn get_machine_inventory(
+    State(state): State<AppState>,
+    Path(machine_id): Path<i64>,
+) -> Result<Json<MachineInventoryResponse>, (StatusCode, String)> {
+    let inventory = load_inventory(&state.pool, machine_id).await?;
+
+    let response = MachineInventoryResponse {
+        inventory,
+        message: "Machine inventory retrieved successfully".to_string(),
+    };
+
+    Ok(Json(response))
+}
+
+pub async fn update_machine(
+    State(state): State<AppState>,
+    Path(machine_id): Path<i64>,
+    Json(req): Json<UpdateMachineReq>,
+) -> Result<Json<MachineInventoryResponse>, (StatusCode, String)> {
+    if req.name.as_deref().is_some_and(|n| n.trim().is_empty()) {
+        return Err((StatusCode::BAD_REQUEST, "Machine name cannot be empty".to_string()));
+    }
+
+    if req.class.as_deref().is_some_and(|c| c.trim().is_empty()) {
+        return Err((StatusCode::BAD_REQUEST, "Machine class cannot be empty".to_string()));
+    }
+
+    if req.ram_gb.is_some_and(|r| r <= 0) || req.monitor_hz.is_some_and(|h| h <= 0) {
+        return Err((StatusCode::BAD_REQUEST, "RAM and monitor refresh rate must be positive".to_string()));
+    }
+
+    if req.seat_position.is_some_and(|p| p <= 0) {
+        return Err((StatusCode::BAD_REQUEST, "Seat position must be positive".to_string()));
+    }
+
+    let name = req.name.as_deref().map(str::trim);
+    let zone = req.zone.as_deref().map(str::trim);
+    let class = req.class.as_deref().map(str::trim);
+
+    if let Some(zone) = zone.filter(|z| !z.is_empty()) {
+        let known = sqlx::query("SELECT 1 FROM zones WHERE name = ?")
+            .bind(zone)
+            .fetch_optional(&state.pool)
+            .await
+            .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, format!("Failed to update machine: {}", e)))?;
+
+        if known.is_none() {
+            return Err((StatusCode::BAD_REQUEST, format!("Unknown zone {}", zone)));
+        }
+    }
+
+    let updated = sqlx::query(
+        "UPDATE machines
+         SET name = COALESCE(?, name), zone = CASE WHEN ? IS NULL THEN zone ELSE NULLIF(?, '') END, class = COALESCE(?, class),
+             seat_position = COALESCE(?, seat_position), pos_x = COALESCE(?, pos_x), pos_y = COALESCE(?, pos_y),
+             cpu = COALESCE(?, cpu), gpu = COALESCE(?, gpu), ram_gb = COALESCE(?, ram_gb), monitor_hz = COALESCE(?, monitor_hz)
+         WHERE id = ? AND decommissioned_at IS NULL",
+    )
+    .bind(name)
+    .bind(zone)
+    .bind(zone)
+    .bind(class)
+    .bind(req.seat_position)
+    .bind(req.pos_x)
+    .bind(req.pos_y)
+    .bind(&req.cpu)
+    .bind(&req.gpu)
+    .bind(req.ram_gb)
+    .bind(req.monitor_hz)
+    .bind(machine_id)
+    .execute(&state.pool)
+    .await
+    .map_err(|e| match e {
+        sqlx::Error::Database(ref d) if d.is_unique_violation() && d.message().contains("seat_position") => {
+            (StatusCode::CONFLICT, "That seat in the zone is already taken".to_string())
+        }
+        sqlx::Error::Database(ref d) if d.is_unique_violation() => {
+            (StatusCode::CONFLICT, "Machine with this name already exists".to_string())
+        }
+        e => (StatusCode::INTERNAL_SERVER_ERROR, format!("Failed to update machine: {}", e)),
+    })?;
+
+    if updated.rows_affected() == 0 {
+        return Err((StatusCode::NOT_FOUND, "Machine not found or decommissioned".to_string()));
+    }
+
+    let inventory = load_inventory(&state.pool, machine_id).await?;
+
+    let response = MachineInventoryResponse {
+        inventory,
+        message: "Machine updated".to_string(),
+    };
+
+    Ok(Json(response))
+}
+
+pub async fn decommission_machine(
+    State(state): State<AppState>,
+    Path(machine_id): Path<i64>,
+) -> Result<Json<MachineInventoryResponse>, (StatusCode, String)> {
+    let db_err = |e: sqlx::Error| (StatusCode::INTERNAL_SERVER_ERROR, format!("Failed to decommission machine: {}", e));
+
+    let mut tx = state.pool.begin().await.map_err(db_err)?;
+
+    let status: String = sqlx::query_scalar("SELECT status FROM machines WHERE id = ? AND decommissioned_at IS NULL")
+        .bind(machine_id)
+        .fetch_optional(&mut *tx)
+        .await
+        .map_err(db_err)?
+        .ok_or((StatusCode::NOT_FOUND, "Machine not found or already decommissioned".to_string()))?;
+
+    if status == "in_use" || status == "reserved" {
+        return Err((StatusCode::CONFLICT, "Machine is occupied or held, end or move its session first".to_string()));
+    }
+
+    let booked = sqlx::query(
+        "SELECT 1 FROM reservations WHERE machine_id = ? AND status IN ('booked', 'held') AND ends_at > datetime('now') LIMIT 1",
+    )
+    .bind(machine_id)
+    .fetch_optional(&mut *tx)
+    .await
+    .map_err(db_err)?;
+
+    if booked.is_some() {
+        return Err((StatusCode::CONFLICT, "Machine has upcoming reservations, cancel or move them first".to_string()));
+    }
+
+    // sessions, receipts and tickets keep pointing at the row, only the machine leaves the floor
+    sqlx::query(
+        "UPDATE machines SET status = 'decommissioned', decommissioned_at = datetime('now'), reenroll_requested_at = NULL
+         WHERE id = ?",
+    )
+    .bind(machine_id)
+    .execute(&mut *tx)
+    .await
+    .map_err(db_err)?;
+
+    tx.commit().await.map_err(db_err)?;
+
+    let inventory = load_inventory(&state.pool, machine_id).await?;
+
+    let response = MachineInventoryResponse {
+        message: format!("Machine {} decommissioned", inventory.machine.name),
+        inventory,
+    };
+
+    Ok(Json(response))
+}
+
+pub async fn reenroll_machine(
+    State(state): State<AppState>,
+    Extension(claims): Extension<JwtClaims>,
+    Path(machine_id): Path<i64>,
+    Json(req): Json<ReenrollMachineReq>,
+) -> Result<Json<MachineInventoryResponse>, (StatusCode, String)> {
+    let staff_id = claims.user_id()
+        .ok_or((StatusCode::UNAUTHORIZED, "Invalid token subject".to_string()))?;
+
+    let db_err = |e: sqlx::Error| (StatusCode::INTERNAL_SERVER_ERROR, format!("Failed to request re-enrollment: {}", e));
+
+    let mut tx = state.pool.begin().await.map_err(db_err)?;
+
+    let updated = sqlx::query(
+        "UPDATE machines SET reenroll_requested_at = datetime('now')
+         WHERE id = ? AND decommissioned_at IS NULL AND reenroll_requested_at IS NULL",
+    )
+    .bind(machine_id)
+    .execute(&mut *tx)
+    .await
+    .map_err(db_err)?;
+
+    if updated.rows_affected() == 0 {
+        return Err((StatusCode::NOT_FOUND, "Machine not found, decommissioned or already awaiting re-enrollment".to_string()));
+    }
+
+    sqlx::query("INSERT INTO machine_enrollments (machine_id, requested_by, note) VALUES (?, ?, ?)")
+        .bind(machine_id)
+        .bind(staff_id)
+        .bind(&req.note)
+        .execute(&mut *tx)
+        .await
+        .map_err(db_err)?;
+
+    tx.commit().await.map_err(db_err)?;
+
+    let inventory = load_inventory(&state.pool, machine_id).await?;
+
+    let response = MachineInventoryResponse {
+        message: format!("Register the replacement PC as {} to take over the seat", inventory.machine.name),
+        inventory,
+    };
+
+    Ok(Json(response))
+}
+
+pub async fn attach_peripheral(
+    State(state): State<AppState>,
+    Path(machine_id): Path<i64>,
+    Json(req): Json<AttachPeripheralReq>,
+) -> Result<Json<PeripheralResponse>, (StatusCode, String)> {
+    let kind = req.kind.trim().to_lowercase();
+    let serial = req.serial.trim().to_string();
+
+    if kind.is_empty() || serial.is_empty() {
+        return Err((StatusCode::BAD_REQUEST, "Peripheral kind and serial are required".to_string()));
+    }
+
+    let peripheral = sqlx::query_as::<_, Peripheral>(&format!(
+        "INSERT INTO machine_peripherals (machine_id, kind, model, serial)
+         SELECT id, ?, ?, ? FROM machines WHERE id = ?
+         RETURNING {}",
+        PERIPHERAL_COLUMNS
+    ))
+    .bind(&kind)
+    .bind(&req.model)
+    .bind(&serial)
+    .bind(machine_id)
+    .fetch_optional(&state.pool)
+    .await
+    .map_err(|e| match e {
+        sqlx::Error::Database(ref d) if d.is_unique_violation() => {
+            (StatusCode::CONFLICT, format!("Serial {} is already attached to a machine", serial))
+        }
+        e => (StatusCode::INTERNAL_SERVER_ERROR, format!("Failed to attach peripheral: {}", e)),
+    })?
+    .ok_or((StatusCode::NOT_FOUND, "Machine not found".to_string()))?;
+
+    let response = PeripheralResponse {
+        peripheral,
+        message: "Peripheral attached".to_string(),
+    };
+
+    Ok(Json(response))
+}
+
+pub async fn detach_peripheral(
+    State(state): State<AppState>,
+    Path(peripheral_id): Path<i64>,
+) -> Result<Json<PeripheralResponse>, (StatusCode, String)> {
+    let peripheral = sqlx::query_as::<_, Peripheral>(&format!(
+        "UPDATE machine_peripherals SET detached_at = datetime('now')
+         WHERE id = ? AND detached_at IS NULL
+         RETURNING {}",
+        PERIPHERAL_COLUMNS
+    ))
+    .bind(peripheral_id)
+    .fetch_optional(&state.pool)
+    .await
+    .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, format!("Failed to detach peripheral: {}", e)))?
+    .ok_or((StatusCode::NOT_FOUND, "Peripheral not found or already detached".to_string()))?;
+
+    let response = PeripheralResponse {
+        peripheral,
+        message: "Peripheral detached".to_string(),
+    };
+
+    Ok(Json(response))
+}
+
+pub async fn open_ticket(
+    State(state): State<AppState>,
+    Extension(claims): Extension<JwtClaims>,
+    Path(machine_id): Path<i64>,
+    Json(req): Json<OpenTicketReq>,
+) -> Result<Json<TicketResponse>, (StatusCode, String)> {
+    let staff_id = claims.user_id()
+        .ok_or((StatusCode::UNAUTHORIZED, "Invalid token subject".to_string()))?;
+
+    let title = req.title.trim();
+    if title.is_empty() {
+        return Err((StatusCode::BAD_REQUEST, "Ticket title is required".to_string()));
+    }
+
+    let db_err = |e: sqlx::Error| (StatusCode::INTERNAL_SERVER_ERROR, format!("Failed to open ticket: {}", e));
+
+    let mut tx = state.pool.begin().await.map_err(db_err)?;
+
+    let ticket = sqlx::query_as::<_, MaintenanceTicket>(&format!(
+        "INSERT INTO maintenance_tickets (machine_id, title, description, blocking, opened_by)
+         SELECT id, ?, ?, ?, ? FROM machines WHERE id = ?
+         RETURNING {}",
+        TICKET_COLUMNS
+    ))
+    .bind(title)
+    .bind(&req.description)
+    .bind(req.blocking.unwrap_or(false))
+    .bind(staff_id)
+    .bind(machine_id)
+    .fetch_optional(&mut *tx)
+    .await
+    .map_err(db_err)?
+    .ok_or((StatusCode::NOT_FOUND, "Machine not found".to_string()))?;
+
+    // a machine in use finishes its session first and goes to maintenance on release,
+    // a free or held one goes now and lets go of whoever it was held for
+    if ticket.blocking {
+        let status: String = sqlx::query_scalar("SELECT status FROM machines WHERE id = ?")
+            .bind(machine_id)
+            .fetch_one(&mut *tx)
+            .await
+            .map_err(db_err)?;
+
+        if status == "available" || status == "reserved" {
+            machine_service::release_machine(&mut tx, machine_id).await.map_err(db_err)?;
+        }
+    }
+
+    let machine_status: String = sqlx::query_scalar("SELECT status FROM machines WHERE id = ?")
+        .bind(machine_id)
+        .fetch_one(&mut *tx)
+        .await
+        .map_err(db_err)?;
+
+    tx.commit().await.map_err(db_err)?;
+
+    let message = if ticket.blocking && machine_status != "maintenance" {
+        "Ticket opened, machine will go to maintenance when its session ends".to_string()
+    } else {
+        "Ticket opened".to_string()
+    };
+
+    Ok(Json(TicketResponse { ticket, machine_status, message }))
+}
+
+pub async fn get_tickets(
+    State(state): State<AppState>,
+    Query(filter): Query<TicketFilter>,
+) -> Result<Json<TicketsResponse>, (StatusCode, String)> {
+    if filter.status.as_deref().is_some_and(|s| !matches!(s, "open" | "assigned" | "resolved")) {
+        return Err((StatusCode::BAD_REQUEST, "Status must be open, assigned or resolved".to_string()));
+    }
+
+    let tickets = sqlx::query_as::<_, MaintenanceTicket>(&format!(
+        "SELECT {} FROM maintenance_tickets
+         WHERE (? IS NULL OR machine_id = ?) AND (? IS NULL OR status = ?)
+         ORDER BY opened_at DESC, id DESC",
+        TICKET_COLUMNS
+    ))
+    .bind(filter.machine_id)
+    .bind(filter.machine_id)
+    .bind(&filter.status)
+    .bind(&filter.status)
+    .fetch_all(&state.pool)
+    .await
+    .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, format!("Failed to fetch tickets: {}", e)))?;
+
+    let response = TicketsResponse {
+        tickets,
+        message: "Tickets retrieved successfully".to_string(),
+    };
+
+    Ok(Json(response))
+}
+
+pub async fn assign_ticket(
+    State(state): State<AppState>,
+    Path(ticket_id): Path<i64>,
+    Json(req): Json<AssignTicketReq>,
+) -> Result<Json<TicketResponse>, (StatusCode, String)> {
+    let db_err = |e: sqlx::Error| (StatusCode::INTERNAL_SERVER_ERROR, format!("Failed to assign ticket: {}", e));
+
+    let mut tx = state.pool.begin().await.map_err(db_err)?;
+
+    let staff: Option<i64> = sqlx::query_scalar("SELECT id FROM users WHERE id = ? AND role = 'admin' AND banned = 0")
+        .bind(req.assignee_id)
+        .fetch_optional(&mut *tx)
+        .await
+        .map_err(db_err)?;
+
+    if staff.is_none() {
+        return Err((StatusCode::BAD_REQUEST, "Tickets can only be assigned to staff".to_string()));
+    }
+
+    let ticket = sqlx::query_as::<_, MaintenanceTicket>(&format!(
+        "UPDATE maintenance_tickets SET status = 'assigned', assigned_to = ?, assigned_at = datetime('now')
+         WHERE id = ? AND status != 'resolved'
+         RETURNING {}",
+        TICKET_COLUMNS
+    ))
+    .bind(req.assignee_id)
+    .bind(ticket_id)
+    .fetch_optional(&mut *tx)
+    .await
+    .map_err(db_err)?
+    .ok_or((StatusCode::NOT_FOUND, "Ticket not found or already resolved".to_string()))?;
+
+    let (machine_name, machine_status): (String, String) = sqlx::query_as("SELECT name, status FROM machines WHERE id = ?")
+        .bind(ticket.machine_id)
+        .fetch_one(&mut *tx)
+        .await
+        .map_err(db_err)?;
+
+    notification_service::notify_user(
+        &mut tx,
+        req.assignee_id,
+        "ticket_assigned",
+        &format!("You were assigned ticket #{} on {}: {}", ticket.id, machine_name, ticket.title),
+    )
+    .await
+    .map_err(db_err)?;
+
+    tx.commit().await.map_err(db_err)?;
+
+    let response = TicketResponse {
+        ticket,
+        machine_status,
+        message: "Ticket assigned".to_string(),
+    };
+
+    Ok(Json(response))
+}
+
+pub async fn resolve_ticket(
+    State(state): State<AppState>,
+    Extension(claims): Extension<JwtClaims>,
+    Path(ticket_id): Path<i64>,
+    Json(req): Json<ResolveTicketReq>,
+) -> Result<Json<TicketResponse>, (StatusCode, String)> {
+    let staff_id = claims.user_id()
+        .ok_or((StatusCode::UNAUTHORIZED, "Invalid token subject".to_string()))?;
+
+    let db_err = |e: sqlx::Error| (StatusCode::INTERNAL_SERVER_ERROR, format!("Failed to resolve ticket: {}", e));
+
+    let mut tx = state.pool.begin().await.map_err(db_err)?;
+
+    let ticket = sqlx::query_as::<_, MaintenanceTicket>(&format!(
+        "UPDATE maintenance_tickets
+         SET status = 'resolved', resolved_by = ?, resolved_at = datetime('now'), resolution = ?
+         WHERE id = ? AND status != 'resolved'
+         RETURNING {}",
+        TICKET_COLUMNS
+    ))
+    .bind(staff_id)
+    .bind(&req.resolution)
+    .bind(ticket_id)
+    .fetch_optional(&mut *tx)
+    .await
+    .map_err(db_err)?
+    .ok_or((StatusCode::NOT_FOUND, "Ticket not found or already resolved".to_string()))?;
+
+    let returned = ticket.blocking
+        && machine_service::return_to_service(&mut tx, ticket.machine_id).await.map_err(db_err)?;
+
+    let machine_status: String = sqlx::query_scalar("SELECT status FROM machines WHERE id = ?")
+        .bind(ticket.machine_id)
+        .fetch_one(&mut *tx)
+        .await
+        .map_err(db_err)?;
+
+    tx.commit().await.map_err(db_err)?;
+
+    let message = if returned {
+        "Ticket resolved, machine returned to service".to_string()
+    } else {
+        "Ticket resolved".to_string()
+    };
+
+    Ok(Json(TicketResponse { ticket, machine_status, message }))
+}
\ No newline at end of file
@@ -0,0 +1,572 @@
+use axum::{
+    Extension, Json,
+    extract::{Path, State},
+    http::StatusCode,
+};
+use serde::Serialize;
+use sqlx::SqliteConnection;
+use crate::{
+    auth::jwt::JwtClaims,
+    models::license::{
+        AddLicenseAccountReq, CreateLicenseTitleReq, LicenseAccount, LicenseCheckout, LicenseTitle, LicenseWaitlistEntry,
+        UpdateLicenseTitleReq,
+    },
+    services::license_service::{self, ACCOUNT_COLUMNS, CHECKOUT_COLUMNS, TITLE_COLUMNS, WAITLIST_COLUMNS},
+    state::AppState,
+};
+
+#[derive(Serialize)]
+pub struct LicenseTitleResponse {
+    pub title: LicenseTitle,
+    pub message: String,
+}
+
+#[derive(Serialize)]
+pub struct LicenseTitlesResponse {
+    pub titles: Vec<LicenseTitle>,
+    pub message: String,
+}
+
+#[derive(Serialize)]
+pub struct LicenseDetailResponse {
+    pub title: LicenseTitle,
+    pub accounts: Vec<LicenseAccount>,
+    pub checkouts: Vec<LicenseCheckout>,
+    pub waitlist: Vec<LicenseWaitlistEntry>,
+    pub message: String,
+}
+
+#[derive(Serialize)]
+pub struct LicenseAccountResponse {
+    pub account: LicenseAccount,
+    pub message: String,
+}
+
+#[derive(Serialize)]
+pub struct MyLicensesResponse {
+    pub titles: Vec<LicenseTitle>,
+    pub checkouts: Vec<LicenseCheckout>,
+    pub waitlist: Vec<LicenseWaitlistEntry>,
+    pub message: String,
+}
+
+/// Either an account was lent straight away or the session joined the queue.
+#[derive(Serialize)]
+pub struct CheckoutResponse {
+    pub checkout: Option<LicenseCheckout>,
+    pub waitlist: Option<LicenseWaitlistEntry>,
+    pub message: String,
+}
+
+#[derive(Serialize)]
+pub struct LicenseCheckoutResponse {
+    pub checkout: LicenseCheckout,
+    pub message: String,
+}
+
+async fn load_title(conn: &mut SqliteConnection, title_id: i64) -> Result<Option<LicenseTitle>, sqlx::Error> {
+    sqlx::query_as::<_, LicenseTitle>(&format!("SELECT {} FROM license_titles t WHERE t.id = ?", TITLE_COLUMNS))
+        .bind(title_id)
+        .fetch_optional(&mut *conn)
+        .await
+}
+
+async fn load_titles(conn: &mut SqliteConnection) -> Result<Vec<LicenseTitle>, sqlx::Error> {
+    sqlx::query_as::<_, LicenseTitle>(&format!("SELECT {} FROM license_titles t ORDER BY t.title", TITLE_COLUMNS))
+        .fetch_all(&mut *conn)
+        .await
+}
+
+fn validate_limit(max_concurrent: Option<i64>) -> Result<(), (StatusCode, String)> {
+    if max_concurrent.is_some_and(|max| max < 0) {
+        return Err((StatusCode::BAD_REQUEST, "max_concurrent cannot be negative".to_string()));
+    }
+    Ok(())
+}
+
+pub async fn get_titles(
+    State(state): State<AppState>,
+) -> Result<Json<LicenseTitlesResponse>, (StatusCode, String)> {
+    let db_err = |e: sqlx::Error| (StatusCode::INTERNAL_SERVER_ERROR, format!("Failed to fetch license titles: {}", e));
+
+    let mut conn = state.pool.acquire().await.map_err(db_err)?;
+    let titles = load_titles(&mut conn).await.map_err(db_err)?;
+
+    let response = LicenseTitlesResponse {
+        titles,
+        message: "License titles retrieved successfully".to_string(),
+    };
+
+    Ok(Json(response))
+}
+
+pub async fn create_title(
+    State(state): State<AppState>,
+    Json(req): Json<CreateLicenseTitleReq>,
+) -> Result<Json<LicenseTitleResponse>, (StatusCode, String)> {
+    let db_err = |e: sqlx::Error| (StatusCode::INTERNAL_SERVER_ERROR, format!("Failed to create license title: {}", e));
+
+    let name = req.title.trim();
+    if name.is_empty() {
+        return Err((StatusCode::BAD_REQUEST, "Title is required".to_string()));
+    }
+    validate_limit(req.max_concurrent)?;
+
+    let mut conn = state.pool.acquire().await.map_err(db_err)?;
+
+    let title_id: i64 = sqlx::query_scalar("INSERT INTO license_titles (title, max_concurrent) VALUES (?, NULLIF(?, 0)) RETURNING id")
+        .bind(name)
+        .bind(req.max_concurrent)
+        .fetch_one(&mut *conn)
+        .await
+        .map_err(|e| match e {
+            sqlx::Error::Database(ref d) if d.is_unique_violation() => {
+                (StatusCode::CONFLICT, format!("A license pool for {} already exists", name))
+            }
+            e => db_err(e),
+        })?;
+
+    let title = load_title(&mut conn, title_id)
+        .await
+        .map_err(db_err)?
+        .ok_or((StatusCode::INTERNAL_SERVER_ERROR, "License title vanished".to_string()))?;
+
+    let response = LicenseTitleResponse {
+        title,
+        message: "License pool created".to_string(),
+    };
+
+    Ok(Json(response))
+}
+
+pub async fn update_title(
+    State(state): State<AppState>,
+    Path(title_id): Path<i64>,
+    Json(req): Json<UpdateLicenseTitleReq>,
+) -> Result<Json<LicenseTitleResponse>, (StatusCode, String)> {
+    let db_err = |e: sqlx::Error| (StatusCode::INTERNAL_SERVER_ERROR, format!("Failed to update license title: {}", e));
+
+    if req.title.as_deref().is_some_and(|t| t.trim().is_empty()) {
+        return Err((StatusCode::BAD_REQUEST, "Title cannot be empty".to_string()));
+    }
+    validate_limit(req.max_concurrent)?;
+
+    let mut tx = state.pool.begin().await.map_err(db_err)?;
+
+    let updated = sqlx::query(
+        "UPDATE license_titles
+         SET title = COALESCE(?, title),
+             max_concurrent = CASE WHEN ? IS NULL THEN max_concurrent ELSE NULLIF(?, 0) END
+         WHERE id = ?",
+    )
+    .bind(req.title.as_deref().map(str::trim))
+    .bind(req.max_concurrent)
+    .bind(req.max_concurrent)
+    .bind(title_id)
+    .execute(&mut *tx)
+    .await
+    .map_err(|e| match e {
+        sqlx::Error::Database(ref d) if d.is_unique_violation() => {
+            (StatusCode::CONFLICT, "Another license pool already has that title".to_string())
+        }
+        e => db_err(e),
+    })?;
+
+    if updated.rows_affected() == 0 {
+        return Err((StatusCode::NOT_FOUND, "License title not found".to_string()));
+    }
+
+    // a raised limit may let queued sessions in
+    license_service::fulfill_waitlist(&mut tx, title_id).await.map_err(db_err)?;
+
+    let title = load_title(&mut tx, title_id)
+        .await
+        .map_err(db_err)?
+        .ok_or((StatusCode::NOT_FOUND, "License title not found".to_string()))?;
+
+    tx.commit().await.map_err(db_err)?;
+
+    let response = LicenseTitleResponse {
+        title,
+        message: "License pool updated".to_string(),
+    };
+
+    Ok(Json(response))
+}
+
+pub async fn get_title(
+    State(state): State<AppState>,
+    Path(title_id): Path<i64>,
+) -> Result<Json<LicenseDetailResponse>, (StatusCode, String)> {
+    let db_err = |e: sqlx::Error| (StatusCode::INTERNAL_SERVER_ERROR, format!("Failed to fetch license pool: {}", e));
+
+    let mut conn = state.pool.acquire().await.map_err(db_err)?;
+
+    let title = load_title(&mut conn, title_id)
+        .await
+        .map_err(db_err)?
+        .ok_or((StatusCode::NOT_FOUND, "License title not found".to_string()))?;
+
+    let accounts = sqlx::query_as::<_, LicenseAccount>(&format!(
+        "SELECT {} FROM license_accounts a WHERE a.title_id = ? ORDER BY a.disabled_at IS NOT NULL, a.id",
+        ACCOUNT_COLUMNS
+    ))
+    .bind(title_id)
+    .fetch_all(&mut *conn)
+    .await
+    .map_err(db_err)?;
+
+    let checkouts = sqlx::query_as::<_, LicenseCheckout>(&format!(
+        "SELECT {} FROM license_checkouts c JOIN license_titles t ON t.id = c.title_id
+         WHERE c.title_id = ? AND c.returned_at IS NULL
+         ORDER BY c.checked_out_at",
+        CHECKOUT_COLUMNS
+    ))
+    .bind(title_id)
+    .fetch_all(&mut *conn)
+    .await
+    .map_err(db_err)?;
+
+    let waitlist = sqlx::query_as::<_, LicenseWaitlistEntry>(&format!(
+        "SELECT {} FROM license_waitlist w JOIN license_titles t ON t.id = w.title_id
+         WHERE w.title_id = ? AND w.status = 'waiting'
+         ORDER BY w.id",
+        WAITLIST_COLUMNS
+    ))
+    .bind(title_id)
+    .fetch_all(&mut *conn)
+    .await
+    .map_err(db_err)?;
+
+    let response = LicenseDetailResponse {
+        title,
+        accounts,
+        checkouts,
+        waitlist,
+        message: "License pool retrieved successfully".to_string(),
+    };
+
+    Ok(Json(response))
+}
+
+pub async fn add_account(
+    State(state): State<AppState>,
+    Path(title_id): Path<i64>,
+    Json(req): Json<AddLicenseAccountReq>,
+) -> Result<Json<LicenseAccountResponse>, (StatusCode, String)> {
+    let db_err = |e: sqlx::Error| (StatusCode::INTERNAL_SERVER_ERROR, format!("Failed to add license account: {}", e));
+
+    let username = req.username.trim();
+    if username.is_empty() || req.password.is_empty() {
+        return Err((StatusCode::BAD_REQUEST, "Username and password are required".to_string()));
+    }
+
+    let sealed = license_service::seal(&req.password).ok_or((
+        StatusCode::INTERNAL_SERVER_ERROR,
+        "LICENSE_KEY must be set to a base64 encoded 32 byte key".to_string(),
+    ))?;
+
+    let mut tx = state.pool.begin().await.map_err(db_err)?;
+
+    if load_title(&mut tx, title_id).await.map_err(db_err)?.is_none() {
+        return Err((StatusCode::NOT_FOUND, "License title not found".to_string()));
+    }
+
+    let account_id: i64 = sqlx::query_scalar(
+        "INSERT INTO license_accounts (title_id, label, username, password_ciphertext) VALUES (?, NULLIF(?, ''), ?, ?) RETURNING id",
+    )
+    .bind(title_id)
+    .bind(req.label.as_deref().map(str::trim))
+    .bind(username)
+    .bind(&sealed)
+    .fetch_one(&mut *tx)
+    .await
+    .map_err(|e| match e {
+        sqlx::Error::Database(ref d) if d.is_unique_violation() => {
+            (StatusCode::CONFLICT, format!("Account {} is already in this pool", username))
+        }
+        e => db_err(e),
+    })?;
+
+    license_service::fulfill_waitlist(&mut tx, title_id).await.map_err(db_err)?;
+
+    let account = sqlx::query_as::<_, LicenseAccount>(&format!("SELECT {} FROM license_accounts a WHERE a.id = ?", ACCOUNT_COLUMNS))
+        .bind(account_id)
+        .fetch_one(&mut *tx)
+        .await
+        .map_err(db_err)?;
+
+    tx.commit().await.map_err(db_err)?;
+
+    let response = LicenseAccountResponse {
+        account,
+        message: "License account added".to_string(),
+    };
+
+    Ok(Json(response))
+}
+
+pub async fn disable_account(
+    State(state): State<AppState>,
+    Path(account_id): Path<i64>,
+) -> Result<Json<LicenseAccountResponse>, (StatusCode, String)> {
+    let db_err = |e: sqlx::Error| (StatusCode::INTERNAL_SERVER_ERROR, format!("Failed to disable license account: {}", e));
+
+    let mut tx = state.pool.begin().await.map_err(db_err)?;
+
+    let account = sqlx::query_as::<_, LicenseAccount>(&format!("SELECT {} FROM license_accounts a WHERE a.id = ?", ACCOUNT_COLUMNS))
+        .bind(account_id)
+        .fetch_optional(&mut *tx)
+        .await
+        .map_err(db_err)?
+        .ok_or((StatusCode::NOT_FOUND, "License account not found".to_string()))?;
+
+    if account.disabled_at.is_some() {
+        return Err((StatusCode::CONFLICT, "License account is already disabled".to_string()));
+    }
+    if account.checked_out {
+        return Err((StatusCode::CONFLICT, "License account is checked out, wait for it to be returned".to_string()));
+    }
+
+    sqlx::query("UPDATE license_accounts SET disabled_at = datetime('now') WHERE id = ?")
+        .bind(account_id)
+        .execute(&mut *tx)
+        .await
+        .map_err(db_err)?;
+
+    let account = sqlx::query_as::<_, LicenseAccount>(&format!("SELECT {} FROM license_accounts a WHERE a.id = ?", ACCOUNT_COLUMNS))
+        .bind(account_id)
+        .fetch_one(&mut *tx)
+        .await
+        .map_err(db_err)?;
+
+    tx.commit().await.map_err(db_err)?;
+
+    let response = LicenseAccountResponse {
+        account,
+        message: "License account disabled".to_string(),
+    };
+
+    Ok(Json(response))
+}
+
+pub async fn get_my_licenses(
+    State(state): State<AppState>,
+    Extension(claims): Extension<JwtClaims>,
+) -> Result<Json<MyLicensesResponse>, (StatusCode, String)> {
+    let user_id = claims.user_id()
+        .ok_or((StatusCode::UNAUTHORIZED, "Invalid token subject".to_string()))?;
+
+    let db_err = |e: sqlx::Error| (StatusCode::INTERNAL_SERVER_ERROR, format!("Failed to fetch licenses: {}", e));
+
+    let mut conn = state.pool.acquire().await.map_err(db_err)?;
+
+    let titles = load_titles(&mut conn).await.map_err(db_err)?;
+
+    let checkouts = sqlx::query_as::<_, LicenseCheckout>(&format!(
+        "SELECT {} FROM license_checkouts c JOIN license_titles t ON t.id = c.title_id
+         WHERE c.user_id = ? AND c.returned_at IS NULL
+         ORDER BY c.checked_out_at",
+        CHECKOUT_COLUMNS
+    ))
+    .bind(user_id)
+    .fetch_all(&mut *conn)
+    .await
+    .map_err(db_err)?;
+
+    let waitlist = sqlx::query_as::<_, LicenseWaitlistEntry>(&format!(
+        "SELECT {} FROM license_waitlist w JOIN license_titles t ON t.id = w.title_id
+         WHERE w.user_id = ? AND w.status = 'waiting'
+         ORDER BY w.id",
+        WAITLIST_COLUMNS
+    ))
+    .bind(user_id)
+    .fetch_all(&mut *conn)
+    .await
+    .map_err(db_err)?;
+
+    let response = MyLicensesResponse {
+        titles,
+        checkouts,
+        waitlist,
+        message: "Licenses retrieved successfully".to_string(),
+    };
+
+    Ok(Json(response))
+}
+
+/// Lends the caller's running session an account for the title, or queues
+/// the session when every account is out or the title is at its limit.
+pub async fn checkout_license(
+    State(state): State<AppState>,
+    Extension(claims): Extension<JwtClaims>,
+    Path(title_id): Path<i64>,
+) -> Result<Json<CheckoutResponse>, (StatusCode, String)> {
+    let user_id = claims.user_id()
+        .ok_or((StatusCode::UNAUTHORIZED, "Invalid token subject".to_string()))?;
+
+    let db_err = |e: sqlx::Error| (StatusCode::INTERNAL_SERVER_ERROR, format!("Failed to check out license: {}", e));
+
+    let mut tx = state.pool.begin().await.map_err(db_err)?;
+
+    let title = load_title(&mut tx, title_id)
+        .await
+        .map_err(db_err)?
+        .ok_or((StatusCode::NOT_FOUND, "License title not found".to_string()))?;
+
+    let (session_id, machine_id): (i64, i64) = sqlx::query_as("SELECT id, machine_id FROM sessions WHERE user_id = ? AND ended_at IS NULL")
+        .bind(user_id)
+        .fetch_optional(&mut *tx)
+        .await
+        .map_err(db_err)?
+        .ok_or((StatusCode::CONFLICT, "Start a session before checking out a license".to_string()))?;
+
+    let holding: Option<i64> = sqlx::query_scalar(
+        "SELECT 1 FROM license_checkouts WHERE session_id = ? AND title_id = ? AND returned_at IS NULL
+         UNION ALL
+         SELECT 1 FROM license_waitlist WHERE session_id = ? AND title_id = ? AND status = 'waiting'",
+    )
+    .bind(session_id)
+    .bind(title_id)
+    .bind(session_id)
+    .bind(title_id)
+    .fetch_optional(&mut *tx)
+    .await
+    .map_err(db_err)?;
+
+    if holding.is_some() {
+        return Err((StatusCode::CONFLICT, format!("This session already holds or is waiting for {}", title.title)));
+    }
+
+    // nobody jumps a queue that is already forming
+    let checkout_id = if title.waiting == 0 {
+        license_service::try_checkout(&mut tx, title_id, session_id, user_id, machine_id)
+            .await
+            .map_err(db_err)?
+    } else {
+        None
+    };
+
+    let response = match checkout_id {
+        Some(checkout_id) => CheckoutResponse {
+            checkout: license_service::load_checkout(&mut tx, checkout_id).await.map_err(db_err)?,
+            waitlist: None,
+            message: format!("{} account checked out, your machine will sign in shortly", title.title),
+        },
+        None => {
+            let entry_id: i64 = sqlx::query_scalar(
+                "INSERT INTO license_waitlist (title_id, user_id, session_id) VALUES (?, ?, ?) RETURNING id",
+            )
+            .bind(title_id)
+            .bind(user_id)
+            .bind(session_id)
+            .fetch_one(&mut *tx)
+            .await
+            .map_err(db_err)?;
+
+            let entry = sqlx::query_as::<_, LicenseWaitlistEntry>(&format!(
+                "SELECT {} FROM license_waitlist w JOIN license_titles t ON t.id = w.title_id WHERE w.id = ?",
+                WAITLIST_COLUMNS
+            ))
+            .bind(entry_id)
+            .fetch_one(&mut *tx)
+            .await
+            .map_err(db_err)?;
+
+            CheckoutResponse {
+                message: format!("All {} accounts are in use, you are number {} in line", title.title, entry.position),
+                checkout: None,
+                waitlist: Some(entry),
+            }
+        }
+    };
+
+    tx.commit().await.map_err(db_err)?;
+
+    Ok(Json(response))
+}
+
+pub async fn return_license(
+    State(state): State<AppState>,
+    Extension(claims): Extension<JwtClaims>,
+    Path(checkout_id): Path<i64>,
+) -> Result<Json<LicenseCheckoutResponse>, (StatusCode, String)> {
+    let user_id = claims.user_id()
+        .ok_or((StatusCode::UNAUTHORIZED, "Invalid token subject".to_string()))?;
+
+    let db_err = |e: sqlx::Error| (StatusCode::INTERNAL_SERVER_ERROR, format!("Failed to return license: {}", e));
+
+    let mut tx = state.pool.begin().await.map_err(db_err)?;
+
+    let owned: Option<i64> = sqlx::query_scalar("SELECT 1 FROM license_checkouts WHERE id = ? AND user_id = ? AND returned_at IS NULL")
+        .bind(checkout_id)
+        .bind(user_id)
+        .fetch_optional(&mut *tx)
+        .await
+        .map_err(db_err)?;
+
+    if owned.is_none() {
+        return Err((StatusCode::NOT_FOUND, "No open checkout found".to_string()));
+    }
+
+    if let Some(title_id) = license_service::return_checkout(&mut tx, checkout_id, "returned").await.map_err(db_err)? {
+        license_service::fulfill_waitlist(&mut tx, title_id).await.map_err(db_err)?;
+    }
+
+    let checkout = license_service::load_checkout(&mut tx, checkout_id)
+        .await
+        .map_err(db_err)?
+        .ok_or((StatusCode::NOT_FOUND, "No open checkout found".to_string()))?;
+
+    tx.commit().await.map_err(db_err)?;
+
+    let response = LicenseCheckoutResponse {
+        checkout,
+        message: "License returned".to_string(),
+    };
+
+    Ok(Json(response))
+}
+
+pub async fn leave_license_waitlist(
+    State(state): State<AppState>,
+    Extension(claims): Extension<JwtClaims>,
+    Path(title_id): Path<i64>,
+) -> Result<Json<LicenseTitleResponse>, (StatusCode, String)> {
+    let user_id = claims.user_id()
+        .ok_or((StatusCode::UNAUTHORIZED, "Invalid token subject".to_string()))?;
+
+    let db_err = |e: sqlx::Error| (StatusCode::INTERNAL_SERVER_ERROR, format!("Failed to leave license waitlist: {}", e));
+
+    let mut tx = state.pool.begin().await.map_err(db_err)?;
+
+    // only the place held by the running session, like checkout_license
+    let left = sqlx::query(
+        "UPDATE license_waitlist SET status = 'cancelled', resolved_at = datetime('now')
+         WHERE title_id = ? AND status = 'waiting'
+           AND session_id = (SELECT id FROM sessions WHERE user_id = ? AND ended_at IS NULL)",
+    )
+    .bind(title_id)
+    .bind(user_id)
+    .execute(&mut *tx)
+    .await
+    .map_err(db_err)?;
+
+    if left.rows_affected() == 0 {
+        return Err((StatusCode::NOT_FOUND, "Your session is not waiting for this title".to_string()));
+    }
+
+    let title = load_title(&mut tx, title_id)
+        .await
+        .map_err(db_err)?
+        .ok_or((StatusCode::NOT_FOUND, "License title not found".to_string()))?;
+
+    tx.commit().await.map_err(db_err)?;
+
+    let response = LicenseTitleResponse {
+        title,
+        message: "Left the license waitlist".to_string(),
+    };
+
+    Ok(Json(response))
+}
\ No newline at end of file
@@ -0,0 +1,149 @@
+use axum::{
+    Json,
+    extract::{Path, State},
+    http::StatusCode,
+};
+use serde::Serialize;
+use crate::{
+    models::loyalty::{LoyaltyTier, CreateTierReq, UpdateTierReq},
+    services::loyalty_service,
+    state::AppState,
+};
+
+#[derive(Serialize)]
+pub struct TierResponse {
+    pub tier: LoyaltyTier,
+    pub message: String,
+}
+
+fn validate_perks(discount: Option<i64>, bonus_minutes: Option<i64>, period_days: Option<i64>) -> Result<(), (StatusCode, String)> {
+    if discount.is_some_and(|d| !(0..=100).contains(&d)) {
+        return Err((StatusCode::BAD_REQUEST, "Discount must be between 0 and 100 percent".to_string()));
+    }
+    if bonus_minutes.is_some_and(|m| m < 0) {
+        return Err((StatusCode::BAD_REQUEST, "Bonus minutes must not be negative".to_string()));
+    }
+    if period_days.is_some_and(|d| d <= 0) {
+        return Err((StatusCode::BAD_REQUEST, "Bonus period must be positive".to_string()));
+    }
+    Ok(())
+}
+
+async fn refresh_tiers(state: &AppState) -> Result<(), (StatusCode, String)> {
+    loyalty_service::refresh_all_tiers(&state.pool)
+        .await
+        .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, format!("Failed to recompute tiers: {}", e)))
+}
+
+pub async fn get_tiers(
+    State(state): State<AppState>,
+) -> Result<Json<Vec<LoyaltyTier>>, (StatusCode, String)> {
+    let tiers = sqlx::query_as::<_, LoyaltyTier>(
+        "SELECT id, name, min_hours, rate_discount_percent, bonus_minutes, bonus_period_days, created_at
+         FROM loyalty_tiers ORDER BY min_hours",
+    )
+    .fetch_all(&state.pool)
+    .await
+    .map_err(|_| (StatusCode::INTERNAL_SERVER_ERROR, "Failed to fetch tiers".to_string()))?;
+
+    Ok(Json(tiers))
+}
+
+pub async fn create_tier(
+    State(state): State<AppState>,
+    Json(req): Json<CreateTierReq>,
+) -> Result<Json<TierResponse>, (StatusCode, String)> {
+    if req.min_hours < 0 {
+        return Err((StatusCode::BAD_REQUEST, "Minimum hours must not be negative".to_string()));
+    }
+    validate_perks(req.rate_discount_percent, req.bonus_minutes, req.bonus_period_days)?;
+
+    let tier = sqlx::query_as::<_, LoyaltyTier>(
+        "INSERT INTO loyalty_tiers (name, min_hours, rate_discount_percent, bonus_minutes, bonus_period_days)
+         VALUES (?, ?, ?, ?, ?)
+         RETURNING id, name, min_hours, rate_discount_percent, bonus_minutes, bonus_period_days, created_at",
+    )
+    .bind(&req.name)
+    .bind(req.min_hours)
+    .bind(req.rate_discount_percent.unwrap_or(0))
+    .bind(req.bonus_minutes.unwrap_or(0))
+    .bind(req.bonus_period_days.unwrap_or(30))
+    .fetch_one(&state.pool)
+    .await
+    .map_err(|_| (StatusCode::CONFLICT, "A tier with this name or threshold already exists".to_string()))?;
+
+    refresh_tiers(&state).await?;
+
+    let response = TierResponse {
+        message: format!("Tier {} created successfully", tier.name),
+        tier,
+    };
+
+    Ok(Json(response))
+}
+
+pub async fn update_tier(
+    Path(tier_id): Path<i64>,
+    State(state): State<AppState>,
+    Json(req): Json<UpdateTierReq>,
+) -> Result<Json<TierResponse>, (StatusCode, String)> {
+    if req.min_hours.is_some_and(|h| h < 0) {
+        return Err((StatusCode::BAD_REQUEST, "Minimum hours must not be negative".to_string()));
+    }
+    validate_perks(req.rate_discount_percent, req.bonus_minutes, req.bonus_period_days)?;
+
+    let tier = sqlx::query_as::<_, LoyaltyTier>(
+        "UPDATE loyalty_tiers SET
+             name = COALESCE(?, name),
+             min_hours = COALESCE(?, min_hours),
+             rate_discount_percent = COALESCE(?, rate_discount_percent),
+             bonus_minutes = COALESCE(?, bonus_minutes),
+             bonus_period_days = COALESCE(?, bonus_period_days)
+         WHERE id = ?
+         RETURNING id, name, min_hours, rate_discount_percent, bonus_minutes, bonus_period_days, created_at",
+    )
+    .bind(&req.name)
+    .bind(req.min_hours)
+    .bind(req.rate_discount_percent)
+    .bind(req.bonus_minutes)
+    .bind(req.bonus_period_days)
+    .bind(tier_id)
+    .fetch_optional(&state.pool)
+    .await
+    .map_err(|_| (StatusCode::CONFLICT, "A tier with this name or threshold already exists".to_string()))?
+    .ok_or((StatusCode::NOT_FOUND, "Tier not found".to_string()))?;
+
+    refresh_tiers(&state).await?;
+
+    let response = TierResponse {
+        message: format!("Tier {} updated successfully", tier.name),
+        tier,
+    };
+
+    Ok(Json(response))
+}
+
+pub async fn delete_tier(
+    Path(tier_id): Path<i64>,
+    State(state): State<AppState>,
+) -> Result<Json<TierResponse>, (StatusCode, String)> {
+    let mut tx = state.pool.begin()
+        .await
+        .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, format!("Database error: {}", e)))?;
+
+    let tier = loyalty_service::delete_tier(&mut tx, tier_id)
+        .await
+        .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, format!("Failed to delete tier: {}", e)))?
+        .ok_or((StatusCode::NOT_FOUND, "Tier not found".to_string()))?;
+
+    tx.commit()
+        .await
+        .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, format!("Failed to delete tier: {}", e)))?;
+
+    let response = TierResponse {
+        message: format!("Tier {} deleted", tier.name),
+        tier,
+    };
+
+    Ok(Json(response))
+}
@@ -1,50 +1,118 @@
 use axum::{
     Json,
-    extract::{Path, State},
-    http::StatusCode, 
+    extract::{Path, Query, State},
+    http::{HeaderMap, StatusCode},
 };
 use serde::Serialize;
 use crate::{
-    models::machine::{Machine, RegisterMachineReq, HeartbeatReq},
+    models::{
+        agent::AgentUpdate,
+        license::LicenseDelivery,
+        machine::{Machine, MachineCommand, MachineFilter, RegisterMachineReq, HeartbeatReq},
+    },
+    services::{agent_service, license_service, machine_service, power_service, telemetry_service},
     state::AppState,
 };
 
 #[derive(Serialize)]
-pub struct MachineResponse {
+pub struct RegisterMachineResponse {
     pub machine: Machine,
+    // shown once, the agent sends it with every heartbeat
+    pub agent_token: String,
     pub message: String,
 }
 
+#[derive(Serialize)]
+pub struct HeartbeatResponse {
+    pub machine: Machine,
+    pub commands: Vec<MachineCommand>,
+    pub update: Option<AgentUpdate>,
+    pub licenses: Vec<LicenseDelivery>,
+    pub message: String,
+}
+
+/// Enrols a PC, or the replacement for a seat awaiting re-enrollment, and
+/// issues its agent token. Staff only, the token unlocks license credentials.
 pub async fn register_machine(
     State(state): State<AppState>,
     Json(req): Json<RegisterMachineReq>,
-) -> Result<Json<MachineResponse>, (StatusCode, String)> {
+) -> Result<Json<RegisterMachineResponse>, (StatusCode, String)> {
     let machine_name = req.name; 
+
+    let (agent_token, agent_token_hash) = machine_service::issue_agent_token()
+        .ok_or((StatusCode::INTERNAL_SERVER_ERROR, "Failed to issue agent token".to_string()))?;
     
-    let existing = sqlx::query_scalar!(
-        "SELECT id FROM machines WHERE name = ?",
-        machine_name 
+    let existing: Option<(i64, Option<String>)> = sqlx::query_as(
+        "SELECT id, reenroll_requested_at FROM machines WHERE name = ? AND decommissioned_at IS NULL",
     )
+    .bind(&machine_name)
     .fetch_optional(&state.pool)
     .await
     .map_err(|_| (StatusCode::INTERNAL_SERVER_ERROR, "Database error".to_string()))?;
-    
-    if existing.is_some() {
-        return Err((StatusCode::CONFLICT, "Machine with this name already exists".to_string()));
+
+    if let Some((machine_id, reenroll_requested_at)) = existing {
+        if reenroll_requested_at.is_none() {
+            return Err((StatusCode::CONFLICT, "Machine with this name already exists".to_string()));
+        }
+
+        // a replacement PC takes over the seat, keeping its id and history
+        let mut tx = state.pool.begin()
+            .await
+            .map_err(|_| (StatusCode::INTERNAL_SERVER_ERROR, "Database error".to_string()))?;
+
+        let machine = sqlx::query_as::<_, Machine>(
+            "UPDATE machines
+             SET status = 'available', last_seen_at = datetime('now'), class = COALESCE(?, class),
+                 reenroll_requested_at = NULL, agent_version = NULL, logged_in_user = NULL, agent_token_hash = ?
+             WHERE id = ?
+             RETURNING id, name, status, class, zone",
+        )
+        .bind(&req.class)
+        .bind(&agent_token_hash)
+        .bind(machine_id)
+        .fetch_one(&mut *tx)
+        .await
+        .map_err(|_| (StatusCode::INTERNAL_SERVER_ERROR, "Failed to re-enroll machine".to_string()))?;
+
+        sqlx::query("UPDATE machine_enrollments SET enrolled_at = datetime('now') WHERE machine_id = ? AND enrolled_at IS NULL")
+            .bind(machine_id)
+            .execute(&mut *tx)
+            .await
+            .map_err(|_| (StatusCode::INTERNAL_SERVER_ERROR, "Failed to re-enroll machine".to_string()))?;
+
+        tx.commit()
+            .await
+            .map_err(|_| (StatusCode::INTERNAL_SERVER_ERROR, "Failed to re-enroll machine".to_string()))?;
+
+        let response = RegisterMachineResponse {
+            machine,
+            agent_token,
+            message: format!("Machine {} re-enrolled successfully", machine_name),
+        };
+
+        return Ok(Json(response));
     }
     
     let machine = sqlx::query_as::<_, Machine>(
-        "INSERT INTO machines (name, status, last_seen_at) 
-         VALUES (?, 'ONLINE', datetime('now')) 
-         RETURNING id, name, status, last_seen_at",
+        "INSERT INTO machines (name, status, class, last_seen_at, agent_token_hash) 
+         VALUES (?, 'available', ?, datetime('now'), ?) 
+         RETURNING id, name, status, class, zone",
     )
     .bind(&machine_name) 
+    .bind(req.class.as_deref().unwrap_or("standard"))
+    .bind(&agent_token_hash)
     .fetch_one(&state.pool)
     .await
-    .map_err(|_| (StatusCode::INTERNAL_SERVER_ERROR, "Failed to register machine".to_string()))?;
+    .map_err(|e| match e {
+        sqlx::Error::Database(ref d) if d.is_unique_violation() => {
+            (StatusCode::CONFLICT, "Machine with this name already exists".to_string())
+        }
+        _ => (StatusCode::INTERNAL_SERVER_ERROR, "Failed to register machine".to_string()),
+    })?;
     
-    let response = MachineResponse {
+    let response = RegisterMachineResponse {
         machine,
+        agent_token,
         message: format!("Machine {} registered successfully", machine_name),
     };
     
@@ -53,21 +121,80 @@ pub async fn register_machine(
 
 pub async fn heartbeat(
     State(state): State<AppState>,
+    headers: HeaderMap,
     Json(req): Json<HeartbeatReq>,
-) -> Result<Json<MachineResponse>, (StatusCode, String)> {
+) -> Result<Json<HeartbeatResponse>, (StatusCode, String)> {
+    // commands are only marked delivered if the response goes out, license
+    // credentials keep being sent until the agent confirms them
+    let mut tx = state.pool.begin()
+        .await
+        .map_err(|_| (StatusCode::INTERNAL_SERVER_ERROR, "Database error".to_string()))?;
+
+    let token = headers.get(machine_service::AGENT_TOKEN_HEADER).and_then(|v| v.to_str().ok());
+    let authenticated = machine_service::authenticate_agent(&mut tx, req.machine_id, token)
+        .await
+        .map_err(|_| (StatusCode::INTERNAL_SERVER_ERROR, "Database error".to_string()))?;
+
+    if !authenticated {
+        return Err((StatusCode::UNAUTHORIZED, "Invalid machine or agent token".to_string()));
+    }
+
+    // the status belongs to sessions, holds and maintenance, a heartbeat only proves the machine is there
     let machine = sqlx::query_as::<_, Machine>(
-        "UPDATE machines SET status = 'ONLINE', last_seen_at = datetime('now') 
-         WHERE id = ? 
-         RETURNING id, name, status, last_seen_at",
+        "UPDATE machines SET last_seen_at = datetime('now'), mac_address = COALESCE(?, mac_address)
+         WHERE id = ? AND decommissioned_at IS NULL
+         RETURNING id, name, status, class, zone",
     )
+    .bind(&req.mac_address)
     .bind(req.machine_id)
-    .fetch_one(&state.pool)
+    .fetch_one(&mut *tx)
     .await
     .map_err(|_| (StatusCode::NOT_FOUND, "Machine not found".to_string()))?;
+
+    machine_service::track_uptime(&mut tx, machine.id)
+        .await
+        .map_err(|_| (StatusCode::INTERNAL_SERVER_ERROR, "Failed to record uptime".to_string()))?;
+
+    telemetry_service::record(&mut tx, &machine.name, &req)
+        .await
+        .map_err(|_| (StatusCode::INTERNAL_SERVER_ERROR, "Failed to record telemetry".to_string()))?;
+
+    let commands = machine_service::take_pending_commands(&mut tx, machine.id)
+        .await
+        .map_err(|_| (StatusCode::INTERNAL_SERVER_ERROR, "Failed to fetch machine commands".to_string()))?;
+
+    power_service::on_heartbeat(&mut tx, machine.id, &commands)
+        .await
+        .map_err(|_| (StatusCode::INTERNAL_SERVER_ERROR, "Failed to update power state".to_string()))?;
+
+    let agent_version: Option<String> = sqlx::query_scalar("SELECT agent_version FROM machines WHERE id = ?")
+        .bind(machine.id)
+        .fetch_one(&mut *tx)
+        .await
+        .map_err(|_| (StatusCode::INTERNAL_SERVER_ERROR, "Database error".to_string()))?;
+
+    let update = agent_service::pending_update(&mut tx, machine.id, machine.zone.as_deref(), agent_version.as_deref())
+        .await
+        .map_err(|_| (StatusCode::INTERNAL_SERVER_ERROR, "Failed to check for agent updates".to_string()))?;
+
+    license_service::confirm_deliveries(&mut tx, machine.id, req.delivered_licenses.as_deref().unwrap_or_default())
+        .await
+        .map_err(|_| (StatusCode::INTERNAL_SERVER_ERROR, "Failed to confirm license deliveries".to_string()))?;
+
+    let licenses = license_service::take_deliveries(&mut tx, machine.id)
+        .await
+        .map_err(|_| (StatusCode::INTERNAL_SERVER_ERROR, "Failed to deliver licenses".to_string()))?;
+
+    tx.commit()
+        .await
+        .map_err(|_| (StatusCode::INTERNAL_SERVER_ERROR, "Database error".to_string()))?;
     
-    let response = MachineResponse {
+    let response = HeartbeatResponse {
         machine,
-        message: "Heartbeat received, machine status updated to ONLINE".to_string(),
+        commands,
+        update,
+        licenses,
+        message: "Heartbeat received".to_string(),
     };
     
     Ok(Json(response))
@@ -75,10 +202,22 @@ pub async fn heartbeat(
 
 pub async fn get_machines(
     State(state): State<AppState>,
+    Query(filter): Query<MachineFilter>,
 ) -> Result<Json<Vec<Machine>>, (StatusCode, String)> {
+    // retired machines only show up when asked for
+    let include_decommissioned = filter.include_decommissioned.unwrap_or(false)
+        || filter.status.as_deref() == Some("decommissioned");
+
     let machines = sqlx::query_as::<_, Machine>(
-        "SELECT id, name, status, last_seen_at FROM machines ORDER BY name"
+        "SELECT id, name, status, class, zone FROM machines
+         WHERE (? OR decommissioned_at IS NULL) AND (? IS NULL OR status = ?) AND (? IS NULL OR zone = ?)
+         ORDER BY name"
     )
+    .bind(include_decommissioned)
+    .bind(&filter.status)
+    .bind(&filter.status)
+    .bind(&filter.zone)
+    .bind(&filter.zone)
     .fetch_all(&state.pool)
     .await
     .map_err(|_| (StatusCode::INTERNAL_SERVER_ERROR, "Failed to fetch machines".to_string()))?;
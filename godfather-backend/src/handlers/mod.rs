@@ -4,4 +4,25 @@ pub mod profile_handler;
 pub mod balance_handler;
 pub mod session_handler;
 pub mod machine_handler;
-pub mod admin_handler;
\ No newline at end of file
+pub mod admin_handler;
+pub mod voucher_handler;
+pub mod subscription_handler;
+pub mod loyalty_handler;
+pub mod notification_handler;
+pub mod adjustment_handler;
+pub mod receipt_handler;
+pub mod reservation_handler;
+pub mod waitlist_handler;
+pub mod session_history_handler;
+pub mod reconcile_handler;
+pub mod billing_handler;
+pub mod guest_ticket_handler;
+pub mod telemetry_handler;
+pub mod inventory_handler;
+pub mod zone_handler;
+pub mod favorite_handler;
+pub mod analytics_handler;
+pub mod agent_handler;
+pub mod power_handler;
+pub mod app_usage_handler;
+pub mod license_handler;
\ No newline at end of file
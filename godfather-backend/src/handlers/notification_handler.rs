@@ -0,0 +1,88 @@
+use axum::{
+    Extension, Json,
+    extract::{Path, State},
+    http::StatusCode,
+};
+use crate::{
+    auth::jwt::JwtClaims,
+    models::notification::Notification,
+    state::AppState,
+};
+
+pub async fn get_my_notifications(
+    State(state): State<AppState>,
+    Extension(claims): Extension<JwtClaims>,
+) -> Result<Json<Vec<Notification>>, (StatusCode, String)> {
+    let user_id = claims.user_id()
+        .ok_or((StatusCode::UNAUTHORIZED, "Invalid token subject".to_string()))?;
+
+    let notifications = sqlx::query_as::<_, Notification>(
+        "SELECT id, user_id, audience, kind, message, created_at, read_at
+         FROM notifications
+         WHERE user_id = ? AND audience = 'user'
+         ORDER BY read_at IS NOT NULL, created_at DESC
+         LIMIT 50",
+    )
+    .bind(user_id)
+    .fetch_all(&state.pool)
+    .await
+    .map_err(|_| (StatusCode::INTERNAL_SERVER_ERROR, "Failed to fetch notifications".to_string()))?;
+
+    Ok(Json(notifications))
+}
+
+pub async fn mark_notification_read(
+    Path(notification_id): Path<i64>,
+    State(state): State<AppState>,
+    Extension(claims): Extension<JwtClaims>,
+) -> Result<Json<Notification>, (StatusCode, String)> {
+    let user_id = claims.user_id()
+        .ok_or((StatusCode::UNAUTHORIZED, "Invalid token subject".to_string()))?;
+
+    let notification = sqlx::query_as::<_, Notification>(
+        "UPDATE notifications SET read_at = COALESCE(read_at, datetime('now'))
+         WHERE id = ? AND user_id = ?
+         RETURNING id, user_id, audience, kind, message, created_at, read_at",
+    )
+    .bind(notification_id)
+    .bind(user_id)
+    .fetch_one(&state.pool)
+    .await
+    .map_err(|_| (StatusCode::NOT_FOUND, "Notification not found".to_string()))?;
+
+    Ok(Json(notification))
+}
+
+pub async fn get_staff_notifications(
+    State(state): State<AppState>,
+) -> Result<Json<Vec<Notification>>, (StatusCode, String)> {
+    let notifications = sqlx::query_as::<_, Notification>(
+        "SELECT id, user_id, audience, kind, message, created_at, read_at
+         FROM notifications
+         WHERE audience = 'staff'
+         ORDER BY read_at IS NOT NULL, created_at DESC
+         LIMIT 100",
+    )
+    .fetch_all(&state.pool)
+    .await
+    .map_err(|_| (StatusCode::INTERNAL_SERVER_ERROR, "Failed to fetch notifications".to_string()))?;
+
+    Ok(Json(notifications))
+}
+
+pub async fn mark_staff_notification_read(
+    Path(notification_id): Path<i64>,
+    State(state): State<AppState>,
+) -> Result<Json<Notification>, (StatusCode, String)> {
+    let notification = sqlx::query_as::<_, Notification>(
+        "UPDATE notifications SET read_at = COALESCE(read_at, datetime('now'))
+         WHERE id = ? AND audience = 'staff'
+         RETURNING id, user_id, audience, kind, message, created_at, read_at",
+    )
+    .bind(notification_id)
+    .fetch_one(&state.pool)
+    .await
+    .map_err(|_| (StatusCode::NOT_FOUND, "Notification not found".to_string()))?;
+
+    Ok(Json(notification))
+}
@@ -0,0 +1,166 @@
+use axum::{
+    Extension, Json,
+    extract::{Path, Query, State},
+    http::StatusCode,
+};
+use serde::Serialize;
+use crate::{
+    auth::jwt::JwtClaims,
+    models::{
+        analytics::AnalyticsQuery,
+        power::{PowerPolicy, PowerSavings, SetPowerPolicyReq},
+    },
+    services::{analytics_service::AnalyticsRange, power_service},
+    state::AppState,
+};
+
+#[derive(Serialize)]
+pub struct PolicyResponse {
+    pub policy: PowerPolicy,
+    pub message: String,
+}
+
+#[derive(Serialize)]
+pub struct PoliciesResponse {
+    pub policies: Vec<PowerPolicy>,
+    pub message: String,
+}
+
+#[derive(Serialize)]
+pub struct SavingsResponse {
+    pub from: String,
+    pub to: String,
+    pub zones: Vec<PowerSavings>,
+    pub message: String,
+}
+
+const TIME_FORMAT: &str = "%Y-%m-%d %H:%M:%S";
+
+fn validate_clock(field: &str, value: &Option<String>) -> Result<(), (StatusCode, String)> {
+    match value {
+        Some(v) if chrono::NaiveTime::parse_from_str(v, "%H:%M").is_err() || v.len() != 5 => {
+            Err((StatusCode::BAD_REQUEST, format!("{} must be a UTC time in HH:MM format", field)))
+        }
+        _ => Ok(()),
+    }
+}
+
+pub async fn get_policies(
+    State(state): State<AppState>,
+) -> Result<Json<PoliciesResponse>, (StatusCode, String)> {
+    let policies = sqlx::query_as::<_, PowerPolicy>(&format!("SELECT {} FROM power_policies ORDER BY zone", power_service::POLICY_COLUMNS))
+        .fetch_all(&state.pool)
+        .await
+        .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, format!("Failed to fetch power policies: {}", e)))?;
+
+    let response = PoliciesResponse {
+        policies,
+        message: "Power policies retrieved successfully".to_string(),
+    };
+
+    Ok(Json(response))
+}
+
+pub async fn set_policy(
+    State(state): State<AppState>,
+    Extension(claims): Extension<JwtClaims>,
+    Path(zone): Path<String>,
+    Json(req): Json<SetPowerPolicyReq>,
+) -> Result<Json<PolicyResponse>, (StatusCode, String)> {
+    let staff_id = claims.user_id()
+        .ok_or((StatusCode::UNAUTHORIZED, "Invalid token subject".to_string()))?;
+
+    if req.idle_shutdown_minutes <= 0 {
+        return Err((StatusCode::BAD_REQUEST, "idle_shutdown_minutes must be positive".to_string()));
+    }
+    validate_clock("shutdown_after", &req.shutdown_after)?;
+    validate_clock("wake_at", &req.wake_at)?;
+
+    let idle_watts = req.idle_watts.unwrap_or(90);
+    let off_watts = req.off_watts.unwrap_or(2);
+    if off_watts < 0 || idle_watts <= off_watts {
+        return Err((StatusCode::BAD_REQUEST, "idle_watts must be greater than off_watts".to_string()));
+    }
+
+    let known: Option<i64> = sqlx::query_scalar("SELECT id FROM zones WHERE name = ?")
+        .bind(&zone)
+        .fetch_optional(&state.pool)
+        .await
+        .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, format!("Failed to look up zone: {}", e)))?;
+
+    if known.is_none() {
+        return Err((StatusCode::NOT_FOUND, format!("Zone {} not found", zone)));
+    }
+
+    let policy = sqlx::query_as::<_, PowerPolicy>(&format!(
+        "INSERT INTO power_policies (zone, enabled, idle_shutdown_minutes, shutdown_after, wake_at, idle_watts, off_watts, updated_by)
+         VALUES (?, ?, ?, ?, ?, ?, ?, ?)
+         ON CONFLICT(zone) DO UPDATE SET
+             enabled = excluded.enabled, idle_shutdown_minutes = excluded.idle_shutdown_minutes,
+             shutdown_after = excluded.shutdown_after, wake_at = excluded.wake_at,
+             idle_watts = excluded.idle_watts, off_watts = excluded.off_watts,
+             updated_by = excluded.updated_by, updated_at = datetime('now')
+         RETURNING {}",
+        power_service::POLICY_COLUMNS
+    ))
+    .bind(&zone)
+    .bind(req.enabled.unwrap_or(true))
+    .bind(req.idle_shutdown_minutes)
+    .bind(&req.shutdown_after)
+    .bind(&req.wake_at)
+    .bind(idle_watts)
+    .bind(off_watts)
+    .bind(staff_id)
+    .fetch_one(&state.pool)
+    .await
+    .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, format!("Failed to save power policy: {}", e)))?;
+
+    let response = PolicyResponse {
+        policy,
+        message: format!("Power policy for {} saved", zone),
+    };
+
+    Ok(Json(response))
+}
+
+pub async fn delete_policy(
+    State(state): State<AppState>,
+    Path(zone): Path<String>,
+) -> Result<Json<PolicyResponse>, (StatusCode, String)> {
+    let policy = sqlx::query_as::<_, PowerPolicy>(&format!(
+        "DELETE FROM power_policies WHERE zone = ? RETURNING {}",
+        power_service::POLICY_COLUMNS
+    ))
+    .bind(&zone)
+    .fetch_optional(&state.pool)
+    .await
+    .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, format!("Failed to delete power policy: {}", e)))?
+    .ok_or((StatusCode::NOT_FOUND, format!("No power policy for {}", zone)))?;
+
+    let response = PolicyResponse {
+        policy,
+        message: format!("Power policy for {} removed", zone),
+    };
+
+    Ok(Json(response))
+}
+
+pub async fn get_savings(
+    State(state): State<AppState>,
+    Query(query): Query<AnalyticsQuery>,
+) -> Result<Json<SavingsResponse>, (StatusCode, String)> {
+    let range = AnalyticsRange::from_query(&query)?;
+
+    let zones = power_service::savings(&state.pool, &range, &query.zone)
+        .await
+        .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, format!("Failed to build power savings: {}", e)))?;
+
+    let response = SavingsResponse {
+        from: range.from.format(TIME_FORMAT).to_string(),
+        to: range.to.format(TIME_FORMAT).to_string(),
+        zones,
+        message: "Power savings retrieved successfully".to_string(),
+    };
+
+    Ok(Json(response))
+}
\ No newline at end of file
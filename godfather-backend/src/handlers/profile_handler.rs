@@ -1,9 +1,14 @@
 use axum::{
-    Json, 
-    extract::{Request},
+    Extension, Json,
+    extract::State,
     http::StatusCode,
 };
-use crate::{models::user::User, auth::jwt::JwtClaims};
+use crate::{
+    auth::jwt::JwtClaims,
+    models::loyalty::LoyaltyTier,
+    services::loyalty_service,
+    state::AppState,
+};
 
 #[derive(serde::Serialize)]
 pub struct ProfileResponse {
@@ -11,20 +16,53 @@ pub struct ProfileResponse {
     pub user_id: String,
     pub username: String,
     pub role: String,
+    pub lifetime_hours: i64,
+    pub lifetime_minutes: i64,
+    pub tier: Option<LoyaltyTier>,
+    pub next_tier: Option<String>,
+    pub hours_to_next_tier: Option<i64>,
 }
 
 pub async fn get_profile(
-    request: Request,
-) -> Result<Json<ProfileResponse>, StatusCode> {
-    let claims = request.extensions().get::<JwtClaims>()
-        .ok_or(StatusCode::UNAUTHORIZED)?;
+    State(state): State<AppState>,
+    Extension(claims): Extension<JwtClaims>,
+) -> Result<Json<ProfileResponse>, (StatusCode, String)> {
+    let user_id = claims.user_id()
+        .ok_or((StatusCode::UNAUTHORIZED, "Invalid token subject".to_string()))?;
+
+    let mut conn = state.pool.acquire()
+        .await
+        .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, format!("Database error: {}", e)))?;
+
+    let (lifetime_hours, lifetime_minutes): (i64, i64) = sqlx::query_as("SELECT lifetime_hours, lifetime_minutes FROM users WHERE id = ?")
+        .bind(user_id)
+        .fetch_one(&mut *conn)
+        .await
+        .map_err(|_| (StatusCode::NOT_FOUND, "User not found".to_string()))?;
+
+    let tier = loyalty_service::current_tier(&mut conn, user_id)
+        .await
+        .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, format!("Failed to fetch tier: {}", e)))?;
+
+    let next: Option<(String, i64)> = sqlx::query_as(
+        "SELECT name, min_hours FROM loyalty_tiers WHERE min_hours > ? ORDER BY min_hours LIMIT 1",
+    )
+    .bind(lifetime_hours)
+    .fetch_optional(&mut *conn)
+    .await
+    .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, format!("Failed to fetch tier: {}", e)))?;
 
     let response = ProfileResponse {
         message: "Profile information retrieved successfully".to_string(),
         user_id: claims.sub.clone(),
         username: claims.username.clone(),
         role: claims.role.clone(),
+        lifetime_hours,
+        lifetime_minutes,
+        tier,
+        hours_to_next_tier: next.as_ref().map(|(_, min_hours)| min_hours - lifetime_hours),
+        next_tier: next.map(|(name, _)| name),
     };
 
     Ok(Json(response))
-}
\ No newline at end of file
+}
@@ -0,0 +1,190 @@
+use axum::{
+    Extension, Json,
+    extract::{Path, Query, State},
+    http::{StatusCode, header},
+    response::IntoResponse,
+};
+use serde::Serialize;
+use crate::{
+    auth::jwt::JwtClaims,
+    models::receipt::{Receipt, ReceiptDetail, ReceiptFilter, TopUpReq},
+    services::{billing_service, receipt_render, receipt_service::{self, LineItem}},
+    state::AppState,
+};
+
+#[derive(Serialize)]
+pub struct TopUpResponse {
+    pub user_id: i64,
+    pub balance: i64,
+    pub receipt: Receipt,
+    pub message: String,
+}
+
+async fn fetch_receipt(
+    state: &AppState,
+    receipt_id: i64,
+    owner_id: Option<i64>,
+) -> Result<ReceiptDetail, (StatusCode, String)> {
+    let mut conn = state.pool.acquire()
+        .await
+        .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, format!("Database error: {}", e)))?;
+
+    let detail = receipt_service::load_receipt(&mut conn, receipt_id)
+        .await
+        .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, format!("Failed to fetch receipt: {}", e)))?
+        .ok_or((StatusCode::NOT_FOUND, "Receipt not found".to_string()))?;
+
+    // customers only see their own receipts; a 404 avoids confirming other numbers exist
+    if owner_id.is_some() && detail.receipt.user_id != owner_id {
+        return Err((StatusCode::NOT_FOUND, "Receipt not found".to_string()));
+    }
+
+    Ok(detail)
+}
+
+fn claims_user_id(claims: &JwtClaims) -> Result<i64, (StatusCode, String)> {
+    claims.user_id()
+        .ok_or((StatusCode::UNAUTHORIZED, "Invalid token subject".to_string()))
+}
+
+pub async fn get_my_receipts(
+    State(state): State<AppState>,
+    Extension(claims): Extension<JwtClaims>,
+) -> Result<Json<Vec<Receipt>>, (StatusCode, String)> {
+    let user_id = claims_user_id(&claims)?;
+
+    let receipts = sqlx::query_as::<_, Receipt>(
+        "SELECT id, number, user_id, kind, reference_id, subtotal, tax, total, tax_rate_bp, currency, settled_by, created_at
+         FROM receipts WHERE user_id = ? ORDER BY id DESC",
+    )
+    .bind(user_id)
+    .fetch_all(&state.pool)
+    .await
+    .map_err(|_| (StatusCode::INTERNAL_SERVER_ERROR, "Failed to fetch receipts".to_string()))?;
+
+    Ok(Json(receipts))
+}
+
+pub async fn get_my_receipt(
+    Path(receipt_id): Path<i64>,
+    State(state): State<AppState>,
+    Extension(claims): Extension<JwtClaims>,
+) -> Result<Json<ReceiptDetail>, (StatusCode, String)> {
+    let user_id = claims_user_id(&claims)?;
+    Ok(Json(fetch_receipt(&state, receipt_id, Some(user_id)).await?))
+}
+
+pub async fn get_my_receipt_html(
+    Path(receipt_id): Path<i64>,
+    State(state): State<AppState>,
+    Extension(claims): Extension<JwtClaims>,
+) -> Result<impl IntoResponse, (StatusCode, String)> {
+    let user_id = claims_user_id(&claims)?;
+    let detail = fetch_receipt(&state, receipt_id, Some(user_id)).await?;
+
+    Ok(([(header::CONTENT_TYPE, "text/html; charset=utf-8")], receipt_render::render_html(&detail)))
+}
+
+pub async fn get_my_receipt_pdf(
+    Path(receipt_id): Path<i64>,
+    State(state): State<AppState>,
+    Extension(claims): Extension<JwtClaims>,
+) -> Result<impl IntoResponse, (StatusCode, String)> {
+    let user_id = claims_user_id(&claims)?;
+    let detail = fetch_receipt(&state, receipt_id, Some(user_id)).await?;
+    let disposition = format!("inline; filename=\"{}.pdf\"", detail.receipt.number);
+
+    Ok((
+        [(header::CONTENT_TYPE, "application/pdf".to_string()), (header::CONTENT_DISPOSITION, disposition)],
+        receipt_render::render_pdf(&detail),
+    ))
+}
+
+pub async fn get_receipts(
+    State(state): State<AppState>,
+    Query(filter): Query<ReceiptFilter>,
+) -> Result<Json<Vec<Receipt>>, (StatusCode, String)> {
+    let receipts = sqlx::query_as::<_, Receipt>(
+        "SELECT id, number, user_id, kind, reference_id, subtotal, tax, total, tax_rate_bp, currency, settled_by, created_at
+         FROM receipts
+         WHERE (? IS NULL OR user_id = ?) AND (? IS NULL OR kind = ?)
+         ORDER BY id DESC",
+    )
+    .bind(filter.user_id)
+    .bind(filter.user_id)
+    .bind(&filter.kind)
+    .bind(&filter.kind)
+    .fetch_all(&state.pool)
+    .await
+    .map_err(|_| (StatusCode::INTERNAL_SERVER_ERROR, "Failed to fetch receipts".to_string()))?;
+
+    Ok(Json(receipts))
+}
+
+pub async fn get_receipt_escpos(
+    Path(receipt_id): Path<i64>,
+    State(state): State<AppState>,
+) -> Result<impl IntoResponse, (StatusCode, String)> {
+    let detail = fetch_receipt(&state, receipt_id, None).await?;
+
+    Ok(([(header::CONTENT_TYPE, "application/octet-stream")], receipt_render::render_escpos(&detail)))
+}
+
+pub async fn top_up(
+    Path(user_id): Path<i64>,
+    State(state): State<AppState>,
+    Json(req): Json<TopUpReq>,
+) -> Result<Json<TopUpResponse>, (StatusCode, String)> {
+    if req.amount <= 0 {
+        return Err((StatusCode::BAD_REQUEST, "Amount must be positive".to_string()));
+    }
+
+    let mut tx = state.pool.begin()
+        .await
+        .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, format!("Database error: {}", e)))?;
+
+    sqlx::query_scalar::<_, i64>("UPDATE users SET balance = balance + ? WHERE id = ? RETURNING balance")
+        .bind(req.amount)
+        .bind(user_id)
+        .fetch_one(&mut *tx)
+        .await
+        .map_err(|_| (StatusCode::NOT_FOUND, "User not found".to_string()))?;
+
+    // minutes left owing by a session the user could not cover come out of the top-up first
+    let settled = billing_service::settle_minutes_debt(&mut tx, user_id)
+        .await
+        .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, format!("Failed to top up: {}", e)))?;
+
+    let balance: i64 = sqlx::query_scalar("SELECT balance FROM users WHERE id = ?")
+        .bind(user_id)
+        .fetch_one(&mut *tx)
+        .await
+        .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, format!("Failed to top up: {}", e)))?;
+
+    let receipt = receipt_service::issue_receipt(
+        &mut tx,
+        Some(user_id),
+        "top_up",
+        None,
+        vec![LineItem::new("Account top-up", 1, req.amount)],
+    )
+    .await
+    .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, format!("Failed to issue receipt: {}", e)))?;
+
+    tx.commit()
+        .await
+        .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, format!("Failed to top up: {}", e)))?;
+
+    let response = TopUpResponse {
+        user_id,
+        balance,
+        message: if settled > 0 {
+            format!("Balance topped up and {} owed minutes settled. Receipt {} issued", settled, receipt.number)
+        } else {
+            format!("Balance topped up. Receipt {} issued", receipt.number)
+        },
+        receipt,
+    };
+
+    Ok(Json(response))
+}
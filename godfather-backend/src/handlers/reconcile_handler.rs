@@ -0,0 +1,36 @@
+use axum::{
+    Json,
+    extract::State,
+    http::StatusCode,
+};
+use serde::Serialize;
+use crate::{
+    models::reconciliation::ReconciliationReport,
+    services::reconcile_service,
+    state::AppState,
+};
+
+#[derive(Serialize)]
+pub struct ReconciliationResponse {
+    pub report: ReconciliationReport,
+    pub message: String,
+}
+
+pub async fn reconcile(
+    State(state): State<AppState>,
+) -> Result<Json<ReconciliationResponse>, (StatusCode, String)> {
+    let report = reconcile_service::reconcile(&state.pool)
+        .await
+        .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, format!("Reconciliation failed: {}", e)))?;
+
+    let response = ReconciliationResponse {
+        message: format!(
+            "Closed {} orphaned session(s) and reset {} machine(s)",
+            report.sessions.iter().filter(|s| s.error.is_none()).count(),
+            report.machines.len()
+        ),
+        report,
+    };
+
+    Ok(Json(response))
+}
\ No newline at end of file
@@ -0,0 +1,342 @@
+use axum::{
+    Extension, Json,
+    extract::{Path, Query, State},
+    http::StatusCode,
+};
+use chrono::{NaiveDateTime, Utc};
+use serde::Serialize;
+use crate::{
+    auth::jwt::JwtClaims,
+    models::reservation::{
+        Reservation, MaintenanceWindow, CalendarSlot, CreateReservationReq, CreateMaintenanceWindowReq,
+        CalendarQuery, ReservationFilter,
+    },
+    services::{machine_service, reservation_service},
+    state::AppState,
+};
+
+#[derive(Serialize)]
+pub struct ReservationsResponse {
+    pub reservations: Vec<Reservation>,
+    pub message: String,
+}
+
+#[derive(Serialize)]
+pub struct MaintenanceWindowResponse {
+    pub window: MaintenanceWindow,
+    pub message: String,
+}
+
+pub async fn create_reservation(
+    State(state): State<AppState>,
+    Extension(claims): Extension<JwtClaims>,
+    Json(req): Json<CreateReservationReq>,
+) -> Result<Json<ReservationsResponse>, (StatusCode, String)> {
+    let user_id = claims.user_id()
+        .ok_or((StatusCode::UNAUTHORIZED, "Invalid token subject".to_string()))?;
+
+    reservation_service::validate_slot(&req.starts_at, &req.ends_at)?;
+
+    let starts = NaiveDateTime::parse_from_str(&req.starts_at, "%Y-%m-%d %H:%M:%S")
+        .map_err(|_| (StatusCode::BAD_REQUEST, "Invalid start time".to_string()))?;
+    if starts <= Utc::now().naive_utc() {
+        return Err((StatusCode::BAD_REQUEST, "Reservations must start in the future".to_string()));
+    }
+
+    let db_err = |e: sqlx::Error| (StatusCode::INTERNAL_SERVER_ERROR, format!("Database error: {}", e));
+
+    let mut tx = state.pool.begin().await.map_err(db_err)?;
+
+    let mut machine_ids = Vec::new();
+    match (&req.machine_ids, &req.machine_class) {
+        (Some(ids), _) if !ids.is_empty() => {
+            for &machine_id in ids {
+                if machine_ids.contains(&machine_id) {
+                    continue;
+                }
+
+                let name: String = sqlx::query_scalar("SELECT name FROM machines WHERE id = ? AND decommissioned_at IS NULL")
+                    .bind(machine_id)
+                    .fetch_optional(&mut *tx)
+                    .await
+                    .map_err(db_err)?
+                    .ok_or((StatusCode::NOT_FOUND, format!("Machine {} not found", machine_id)))?;
+
+                if !reservation_service::machine_is_free(&mut tx, machine_id, &req.starts_at, &req.ends_at).await.map_err(db_err)? {
+                    return Err((StatusCode::CONFLICT, format!("{} is already booked or under maintenance for that time", name)));
+                }
+
+                machine_ids.push(machine_id);
+            }
+        }
+        (_, Some(class)) => {
+            let wanted = req.count.unwrap_or(1);
+            if wanted <= 0 {
+                return Err((StatusCode::BAD_REQUEST, "Count must be positive".to_string()));
+            }
+
+            let candidates: Vec<i64> = sqlx::query_scalar("SELECT id FROM machines WHERE class = ? AND decommissioned_at IS NULL ORDER BY name")
+                .bind(class)
+                .fetch_all(&mut *tx)
+                .await
+                .map_err(db_err)?;
+
+            for machine_id in candidates {
+                if machine_ids.len() as i64 == wanted {
+                    break;
+                }
+                if reservation_service::machine_is_free(&mut tx, machine_id, &req.starts_at, &req.ends_at).await.map_err(db_err)? {
+                    machine_ids.push(machine_id);
+                }
+            }
+
+            if (machine_ids.len() as i64) < wanted {
+                return Err((StatusCode::CONFLICT, format!("Only {} {} machines are free for that time", machine_ids.len(), class)));
+            }
+        }
+        _ => {
+            return Err((StatusCode::BAD_REQUEST, "Specify machine_ids or a machine_class".to_string()));
+        }
+    }
+
+    let group_id: Option<i64> = if machine_ids.len() > 1 {
+        Some(
+            sqlx::query_scalar("SELECT COALESCE(MAX(group_id), 0) + 1 FROM reservations")
+                .fetch_one(&mut *tx)
+                .await
+                .map_err(db_err)?,
+        )
+    } else {
+        None
+    };
+
+    let mut reservations = Vec::with_capacity(machine_ids.len());
+    for machine_id in machine_ids {
+        let reservation = sqlx::query_as::<_, Reservation>(
+            "INSERT INTO reservations (user_id, machine_id, group_id, starts_at, ends_at)
+             VALUES (?, ?, ?, ?, ?)
+             RETURNING id, user_id, machine_id, group_id, starts_at, ends_at, status, created_at, checked_in_at, cancelled_at",
+        )
+        .bind(user_id)
+        .bind(machine_id)
+        .bind(group_id)
+        .bind(&req.starts_at)
+        .bind(&req.ends_at)
+        .fetch_one(&mut *tx)
+        .await
+        .map_err(db_err)?;
+
+        reservations.push(reservation);
+    }
+
+    tx.commit().await.map_err(db_err)?;
+
+    let response = ReservationsResponse {
+        message: format!("Reserved {} machine(s) from {} to {}", reservations.len(), req.starts_at, req.ends_at),
+        reservations,
+    };
+
+    Ok(Json(response))
+}
+
+pub async fn get_my_reservations(
+    State(state): State<AppState>,
+    Extension(claims): Extension<JwtClaims>,
+) -> Result<Json<ReservationsResponse>, (StatusCode, String)> {
+    let user_id = claims.user_id()
+        .ok_or((StatusCode::UNAUTHORIZED, "Invalid token subject".to_string()))?;
+
+    let reservations = sqlx::query_as::<_, Reservation>(
+        "SELECT id, user_id, machine_id, group_id, starts_at, ends_at, status, created_at, checked_in_at, cancelled_at
+         FROM reservations WHERE user_id = ? ORDER BY starts_at DESC",
+    )
+    .bind(user_id)
+    .fetch_all(&state.pool)
+    .await
+    .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, format!("Failed to fetch reservations: {}", e)))?;
+
+    let response = ReservationsResponse {
+        reservations,
+        message: "Reservations retrieved successfully".to_string(),
+    };
+
+    Ok(Json(response))
+}
+
+pub async fn cancel_reservation(
+    Path(reservation_id): Path<i64>,
+    State(state): State<AppState>,
+    Extension(claims): Extension<JwtClaims>,
+) -> Result<Json<ReservationsResponse>, (StatusCode, String)> {
+    let user_id = claims.user_id()
+        .ok_or((StatusCode::UNAUTHORIZED, "Invalid token subject".to_string()))?;
+
+    let db_err = |e: sqlx::Error| (StatusCode::INTERNAL_SERVER_ERROR, format!("Failed to cancel reservation: {}", e));
+
+    let mut tx = state.pool.begin().await.map_err(db_err)?;
+
+    let previous_status: String = sqlx::query_scalar("SELECT status FROM reservations WHERE id = ? AND user_id = ?")
+        .bind(reservation_id)
+        .bind(user_id)
+        .fetch_optional(&mut *tx)
+        .await
+        .map_err(db_err)?
+        .ok_or((StatusCode::NOT_FOUND, "Reservation not found".to_string()))?;
+
+    if previous_status != "booked" && previous_status != "held" {
+        return Err((StatusCode::CONFLICT, format!("Reservation is already {}", previous_status)));
+    }
+
+    let reservation = sqlx::query_as::<_, Reservation>(
+        "UPDATE reservations SET status = 'cancelled', cancelled_at = datetime('now') WHERE id = ?
+         RETURNING id, user_id, machine_id, group_id, starts_at, ends_at, status, created_at, checked_in_at, cancelled_at",
+    )
+    .bind(reservation_id)
+    .fetch_one(&mut *tx)
+    .await
+    .map_err(db_err)?;
+
+    if previous_status == "held" {
+        machine_service::release_machine(&mut tx, reservation.machine_id).await.map_err(db_err)?;
+    }
+
+    tx.commit().await.map_err(db_err)?;
+
+    let response = ReservationsResponse {
+        reservations: vec![reservation],
+        message: "Reservation cancelled".to_string(),
+    };
+
+    Ok(Json(response))
+}
+
+pub async fn get_calendar(
+    State(state): State<AppState>,
+    Query(query): Query<CalendarQuery>,
+) -> Result<Json<Vec<CalendarSlot>>, (StatusCode, String)> {
+    reservation_service::validate_slot(&query.from, &query.to)?;
+
+    let slots = sqlx::query_as::<_, CalendarSlot>(
+        "SELECT machine_id, 'reservation' AS kind, starts_at, ends_at FROM reservations
+         WHERE status IN ('booked', 'held', 'checked_in') AND starts_at < ? AND ends_at > ? AND (? IS NULL OR machine_id = ?)
+         UNION ALL
+         SELECT machine_id, 'maintenance' AS kind, starts_at, ends_at FROM maintenance_windows
+         WHERE starts_at < ? AND ends_at > ? AND (? IS NULL OR machine_id = ?)
+         ORDER BY machine_id, starts_at",
+    )
+    .bind(&query.to)
+    .bind(&query.from)
+    .bind(query.machine_id)
+    .bind(query.machine_id)
+    .bind(&query.to)
+    .bind(&query.from)
+    .bind(query.machine_id)
+    .bind(query.machine_id)
+    .fetch_all(&state.pool)
+    .await
+    .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, format!("Failed to fetch calendar: {}", e)))?;
+
+    Ok(Json(slots))
+}
+
+pub async fn get_reservations(
+    State(state): State<AppState>,
+    Query(filter): Query<ReservationFilter>,
+) -> Result<Json<ReservationsResponse>, (StatusCode, String)> {
+    let reservations = sqlx::query_as::<_, Reservation>(
+        "SELECT id, user_id, machine_id, group_id, starts_at, ends_at, status, created_at, checked_in_at, cancelled_at
+         FROM reservations
+         WHERE (? IS NULL OR ends_at > ?) AND (? IS NULL OR starts_at < ?) AND (? IS NULL OR status = ?)
+         ORDER BY starts_at",
+    )
+    .bind(&filter.from)
+    .bind(&filter.from)
+    .bind(&filter.to)
+    .bind(&filter.to)
+    .bind(&filter.status)
+    .bind(&filter.status)
+    .fetch_all(&state.pool)
+    .await
+    .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, format!("Failed to fetch reservations: {}", e)))?;
+
+    let response = ReservationsResponse {
+        reservations,
+        message: "Reservations retrieved successfully".to_string(),
+    };
+
+    Ok(Json(response))
+}
+
+pub async fn create_maintenance_window(
+    Path(machine_id): Path<i64>,
+    State(state): State<AppState>,
+    Json(req): Json<CreateMaintenanceWindowReq>,
+) -> Result<Json<MaintenanceWindowResponse>, (StatusCode, String)> {
+    reservation_service::validate_slot(&req.starts_at, &req.ends_at)?;
+
+    let window = sqlx::query_as::<_, MaintenanceWindow>(
+        "INSERT INTO maintenance_windows (machine_id, starts_at, ends_at, reason)
+         SELECT id, ?, ?, ? FROM machines WHERE id = ?
+         RETURNING id, machine_id, starts_at, ends_at, reason, created_at",
+    )
+    .bind(&req.starts_at)
+    .bind(&req.ends_at)
+    .bind(&req.reason)
+    .bind(machine_id)
+    .fetch_optional(&state.pool)
+    .await
+    .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, format!("Failed to create maintenance window: {}", e)))?
+    .ok_or((StatusCode::NOT_FOUND, "Machine not found".to_string()))?;
+
+    let overlapping: i64 = sqlx::query_scalar(
+        "SELECT COUNT(*) FROM reservations
+         WHERE machine_id = ? AND status IN ('booked', 'held') AND starts_at < ? AND ends_at > ?",
+    )
+    .bind(machine_id)
+    .bind(&window.ends_at)
+    .bind(&window.starts_at)
+    .fetch_one(&state.pool)
+    .await
+    .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, format!("Database error: {}", e)))?;
+
+    let message = if overlapping > 0 {
+        format!("Maintenance window created. {} existing reservation(s) overlap it and need rebooking", overlapping)
+    } else {
+        "Maintenance window created".to_string()
+    };
+
+    Ok(Json(MaintenanceWindowResponse { window, message }))
+}
+
+pub async fn get_maintenance_windows(
+    State(state): State<AppState>,
+) -> Result<Json<Vec<MaintenanceWindow>>, (StatusCode, String)> {
+    let windows = sqlx::query_as::<_, MaintenanceWindow>(
+        "SELECT id, machine_id, starts_at, ends_at, reason, created_at
+         FROM maintenance_windows WHERE ends_at > datetime('now') ORDER BY starts_at",
+    )
+    .fetch_all(&state.pool)
+    .await
+    .map_err(|_| (StatusCode::INTERNAL_SERVER_ERROR, "Failed to fetch maintenance windows".to_string()))?;
+
+    Ok(Json(windows))
+}
+
+pub async fn delete_maintenance_window(
+    Path(window_id): Path<i64>,
+    State(state): State<AppState>,
+) -> Result<Json<MaintenanceWindowResponse>, (StatusCode, String)> {
+    let window = sqlx::query_as::<_, MaintenanceWindow>(
+        "DELETE FROM maintenance_windows WHERE id = ?
+         RETURNING id, machine_id, starts_at, ends_at, reason, created_at",
+    )
+    .bind(window_id)
+    .fetch_one(&state.pool)
+    .await
+    .map_err(|_| (StatusCode::NOT_FOUND, "Maintenance window not found".to_string()))?;
+
+    Ok(Json(MaintenanceWindowResponse {
+        window,
+        message: "Maintenance window removed".to_string(),
+    }))
+}
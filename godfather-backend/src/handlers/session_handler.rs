@@ -1,15 +1,18 @@
 use axum::{
-    Json,
+    Extension, Json,
     extract::{Path, State},
     http::StatusCode,
 };
 use serde::Serialize;
 use crate::{
-    models::session::{Session, StartSessionReq, EndSessionReq},
+    auth::jwt::JwtClaims,
+    models::session::{Session, SessionPause, SessionSegment, StartSessionReq, EndSessionReq, ForceEndSessionReq, TransferSessionReq},
     state::AppState,
     models::machine::Machine,
+    services::{
+        assignment_service, license_service, machine_service, power_service, session_service::{self, SessionEnd},
+    },
 };
-use chrono::{DateTime, Utc, NaiveDateTime};
 
 #[derive(Serialize)]
 pub struct SessionResponse {
@@ -17,148 +20,202 @@ pub struct SessionResponse {
     pub message: String,
 }
 
-fn calculate_minutes_elapsed(started_at_str: &str) -> Result<i64, (StatusCode, String)> {
-    let naive_dt = NaiveDateTime::parse_from_str(started_at_str, "%Y-%m-%d %H:%M:%S")
-        .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, format!("Failed to parse datetime: {}", e)))?;
-
-    let started_at: DateTime<Utc> = DateTime::from_naive_utc_and_offset(naive_dt, Utc);
-    let now: DateTime<Utc> = Utc::now();
-
-    let duration = now.signed_duration_since(started_at);
-    let minutes_elapsed = (duration.num_seconds() / 60).max(1); // At least 1 minute
+#[derive(Serialize)]
+pub struct SessionPauseResponse {
+    pub pause: SessionPause,
+    pub message: String,
+}
 
-    Ok(minutes_elapsed as i64)
+#[derive(Serialize)]
+pub struct SessionTransferResponse {
+    pub session: Session,
+    pub segments: Vec<SessionSegment>,
+    pub message: String,
 }
 
 pub async fn start_session(
     State(state): State<AppState>,
     Json(req): Json<StartSessionReq>,
 ) -> Result<Json<SessionResponse>, (StatusCode, String)> {
-    // balance check
-    let user_minutes: i64 = sqlx::query_scalar!(
-        "SELECT minutes_balance FROM users WHERE id = ?",
-        req.user_id
-    )
-    .fetch_one(&state.pool)
-    .await
-    .map_err(|_| (StatusCode::NOT_FOUND, "User not found".to_string()))?;
+    let (session, machine) = match req.machine_id {
+        Some(machine_id) => session_service::open_session(&state.pool, req.user_id, machine_id).await?,
+        None => assignment_service::assign_and_open(&state.pool, &req).await?,
+    };
 
-    if user_minutes <= 0 {
-        return Err((StatusCode::FORBIDDEN, "Insufficient balance".to_string()));
-    }
+    let message = if req.machine_id.is_some() {
+        format!("Session started successfully on machine {}", machine.name)
+    } else {
+        format!("Assigned machine {}, session started successfully", machine.name)
+    };
 
-    // machine check
-    let machine: Machine = sqlx::query_as(
-        "SELECT id, name, status FROM machines WHERE id = ? AND status = 'available'",
-    )
-    .bind(req.machine_id)
-    .fetch_optional(&state.pool)
-    .await
-    .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, format!("Database error: {}", e)))?
-    .ok_or((StatusCode::NOT_FOUND, "Machine not found or not available".to_string()))?;
+    let response = SessionResponse {
+        session,
+        message,
+    };
 
-    let session: Session = sqlx::query_as(
-        "INSERT INTO sessions (user_id, machine_id, started_at) 
-         VALUES (?, ?, datetime('now')) 
-         RETURNING id, user_id, machine_id, started_at, ended_at, minutes_consumed",
-    )
-    .bind(req.user_id)
-    .bind(req.machine_id)
-    .fetch_one(&state.pool)
-    .await
-    .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, format!("Failed to start session: {}", e)))?;
+    Ok(Json(response))
+}
+
+pub async fn end_session(
+    State(state): State<AppState>,
+    Json(req): Json<EndSessionReq>,
+) -> Result<Json<SessionResponse>, (StatusCode, String)> {
+    // the other reasons are reserved for staff and the server itself
+    let reason = req.reason.as_deref().unwrap_or("user_logout");
+    if !["user_logout", "balance_exhausted", "crash"].contains(&reason) {
+        return Err((StatusCode::BAD_REQUEST, "End reason must be user_logout, balance_exhausted or crash".to_string()));
+    }
 
-    // machine used
-    let _ = sqlx::query("UPDATE machines SET status = 'in_use' WHERE id = ?")
-        .bind(req.machine_id)
-        .execute(&state.pool)
-        .await;
+    let closed = session_service::close_session(&state.pool, req.session_id, SessionEnd::new(reason)).await?;
+
+    let session = closed.session;
+    let message = if session.included_minutes > 0 {
+        format!("Session ended successfully. {} minutes consumed ({} from your plan, {} charged). {} minutes added to lifetime total.",
+                session.minutes_consumed, session.included_minutes, session.charged_minutes, closed.minutes_played)
+    } else {
+        format!("Session ended successfully. {} minutes consumed. {} minutes added to lifetime total.", 
+                session.minutes_consumed, closed.minutes_played)
+    };
 
     let response = SessionResponse {
         session,
-        message: format!("Session started successfully on machine {}", machine.name),
+        message,
     };
 
     Ok(Json(response))
 }
 
-pub async fn end_session(
+pub async fn force_end_session(
+    Path(session_id): Path<i64>,
     State(state): State<AppState>,
-    Json(req): Json<EndSessionReq>,
+    Extension(claims): Extension<JwtClaims>,
+    Json(req): Json<ForceEndSessionReq>,
 ) -> Result<Json<SessionResponse>, (StatusCode, String)> {
-    let session = sqlx::query_as::<_, Session>(
-        "SELECT id, user_id, machine_id, started_at, ended_at, minutes_consumed FROM sessions WHERE id = ?",
-    )
-    .bind(req.session_id)
-    .fetch_one(&state.pool)
-    .await
-    .map_err(|_| (StatusCode::NOT_FOUND, "Session not found".to_string()))?;
+    let staff_id = claims.user_id()
+        .ok_or((StatusCode::UNAUTHORIZED, "Invalid token subject".to_string()))?;
 
-    if session.ended_at.is_some() {
-        return Err((StatusCode::BAD_REQUEST, "Session already ended".to_string()));
+    let reason = req.reason.as_deref().unwrap_or("staff_forced");
+    session_service::validate_end_reason(reason)?;
+
+    if req.charged_minutes.is_some_and(|m| m < 0) {
+        return Err((StatusCode::BAD_REQUEST, "Charged minutes cannot be negative".to_string()));
     }
 
-    // calculate elapsed time
-    let started_at_naive = chrono::NaiveDateTime::parse_from_str(&session.started_at, "%Y-%m-%d %H:%M:%S")
-        .map_err(|_| (StatusCode::INTERNAL_SERVER_ERROR, "Invalid timestamp format".to_string()))?;
+    let end = SessionEnd {
+        ended_by: Some(staff_id),
+        charged_minutes: req.charged_minutes,
+        lock_machine: true,
+        ..SessionEnd::new(reason)
+    };
+    let closed = session_service::close_session(&state.pool, session_id, end).await?;
+
+    let session = closed.session;
+    let response = SessionResponse {
+        message: format!(
+            "Session ended by staff ({}). {} minutes consumed, {} charged.",
+            reason, session.minutes_consumed, session.charged_minutes
+        ),
+        session,
+    };
+
+    Ok(Json(response))
+}
+
+pub async fn pause_session(
+    Path(session_id): Path<i64>,
+    State(state): State<AppState>,
+    Extension(claims): Extension<JwtClaims>,
+) -> Result<Json<SessionPauseResponse>, (StatusCode, String)> {
+    let user_id = claims.user_id()
+        .ok_or((StatusCode::UNAUTHORIZED, "Invalid token subject".to_string()))?;
+
+    let db_err = |e: sqlx::Error| (StatusCode::INTERNAL_SERVER_ERROR, format!("Failed to pause session: {}", e));
 
-    let started_at: DateTime<Utc> = DateTime::from_naive_utc_and_offset(started_at_naive, Utc);
-    let now: DateTime<Utc> = Utc::now();
+    let mut tx = state.pool.begin().await.map_err(db_err)?;
 
-    let duration = now.signed_duration_since(started_at);
-    let minutes_consumed = (duration.num_seconds() / 60).max(1) as i64; 
+    let machine_id: i64 = sqlx::query_scalar("SELECT machine_id FROM sessions WHERE id = ? AND user_id = ? AND ended_at IS NULL")
+        .bind(session_id)
+        .bind(user_id)
+        .fetch_optional(&mut *tx)
+        .await
+        .map_err(db_err)?
+        .ok_or((StatusCode::NOT_FOUND, "No running session found".to_string()))?;
 
-    // deduct from user balance
-    let user_minutes: i64 = sqlx::query_scalar!(
-        "SELECT minutes_balance FROM users WHERE id = ?",
-        session.user_id
+    let (pauses, open): (i64, i64) = sqlx::query_as(
+        "SELECT COUNT(*), COUNT(*) - COUNT(resumed_at) FROM session_pauses WHERE session_id = ?",
     )
-    .fetch_one(&state.pool)
+    .bind(session_id)
+    .fetch_one(&mut *tx)
     .await
-    .map_err(|_| (StatusCode::NOT_FOUND, "User not found".to_string()))?;
+    .map_err(db_err)?;
 
-    if user_minutes < minutes_consumed {
-        return Err((StatusCode::FORBIDDEN, "Insufficient balance to cover session time".to_string()));
+    if open > 0 {
+        return Err((StatusCode::CONFLICT, "Session is already paused".to_string()));
     }
 
-    let hours_consumed = ((minutes_consumed as f64) / 60.0).ceil() as i64;
+    let max_pauses = session_service::max_pauses();
+    if pauses >= max_pauses {
+        return Err((StatusCode::CONFLICT, format!("Sessions can be paused at most {} times", max_pauses)));
+    }
 
-    // update user's lifetime hours
-    let _ = sqlx::query(
-        "UPDATE users SET lifetime_hours = lifetime_hours + ? WHERE id = ?"
-    )
-    .bind(hours_consumed)
-    .bind(session.user_id)
-    .execute(&state.pool)
-    .await;
-
-    let updated_session = sqlx::query_as::<_, Session>(
-        "UPDATE sessions SET ended_at = datetime('now'), minutes_consumed = ? WHERE id = ? 
-         RETURNING id, user_id, machine_id, started_at, ended_at, minutes_consumed",
+    let pause = sqlx::query_as::<_, SessionPause>(
+        "INSERT INTO session_pauses (session_id) VALUES (?) RETURNING id, session_id, paused_at, resumed_at",
     )
-    .bind(minutes_consumed)
-    .bind(req.session_id)
-    .fetch_one(&state.pool)
+    .bind(session_id)
+    .fetch_one(&mut *tx)
     .await
-    .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, format!("Failed to end session: {}", e)))?;
+    .map_err(db_err)?;
+
+    machine_service::queue_command(&mut tx, machine_id, "lock").await.map_err(db_err)?;
+
+    tx.commit().await.map_err(db_err)?;
 
-    let _ = sqlx::query(
-        "UPDATE users SET minutes_balance = minutes_balance - ? WHERE id = ?",
+    let response = SessionPauseResponse {
+        pause,
+        message: format!("Session paused. Resume within {} minutes or it will be ended", session_service::max_pause_minutes()),
+    };
+
+    Ok(Json(response))
+}
+
+pub async fn resume_session(
+    Path(session_id): Path<i64>,
+    State(state): State<AppState>,
+    Extension(claims): Extension<JwtClaims>,
+) -> Result<Json<SessionPauseResponse>, (StatusCode, String)> {
+    let user_id = claims.user_id()
+        .ok_or((StatusCode::UNAUTHORIZED, "Invalid token subject".to_string()))?;
+
+    let db_err = |e: sqlx::Error| (StatusCode::INTERNAL_SERVER_ERROR, format!("Failed to resume session: {}", e));
+
+    let mut tx = state.pool.begin().await.map_err(db_err)?;
+
+    let machine_id: i64 = sqlx::query_scalar("SELECT machine_id FROM sessions WHERE id = ? AND user_id = ? AND ended_at IS NULL")
+        .bind(session_id)
+        .bind(user_id)
+        .fetch_optional(&mut *tx)
+        .await
+        .map_err(db_err)?
+        .ok_or((StatusCode::NOT_FOUND, "No running session found".to_string()))?;
+
+    let pause = sqlx::query_as::<_, SessionPause>(
+        "UPDATE session_pauses SET resumed_at = datetime('now')
+         WHERE session_id = ? AND resumed_at IS NULL
+         RETURNING id, session_id, paused_at, resumed_at",
     )
-    .bind(minutes_consumed)
-    .bind(session.user_id)
-    .execute(&state.pool)
-    .await;
+    .bind(session_id)
+    .fetch_optional(&mut *tx)
+    .await
+    .map_err(db_err)?
+    .ok_or((StatusCode::CONFLICT, "Session is not paused".to_string()))?;
 
-    let _ = sqlx::query("UPDATE machines SET status = 'available' WHERE id = ?")
-        .bind(session.machine_id)
-        .execute(&state.pool)
-        .await;
+    machine_service::queue_command(&mut tx, machine_id, "unlock").await.map_err(db_err)?;
 
-    let response = SessionResponse {
-        session: updated_session,
-        message: format!("Session ended successfully. {} minutes consumed. {} hours added to lifetime total.", 
-                        minutes_consumed, hours_consumed),
+    tx.commit().await.map_err(db_err)?;
+
+    let response = SessionPauseResponse {
+        pause,
+        message: "Session resumed".to_string(),
     };
 
     Ok(Json(response))
@@ -169,7 +226,7 @@ pub async fn get_session(
     State(state): State<AppState>,
 ) -> Result<Json<SessionResponse>, (StatusCode, String)> {
     let session: Session = sqlx::query_as(
-        "SELECT id, user_id, machine_id, started_at, ended_at, minutes_consumed FROM sessions WHERE id = ?",
+        "SELECT id, user_id, machine_id, started_at, ended_at, minutes_consumed, included_minutes, charged_minutes, paused_minutes, end_reason FROM sessions WHERE id = ?",
     )
     .bind(session_id)
     .fetch_one(&state.pool)
@@ -181,5 +238,137 @@ pub async fn get_session(
         message: "Session details retrieved successfully".to_string(),
     };
 
+    Ok(Json(response))
+}
+
+pub async fn transfer_session(
+    Path(session_id): Path<i64>,
+    State(state): State<AppState>,
+    Extension(claims): Extension<JwtClaims>,
+    Json(req): Json<TransferSessionReq>,
+) -> Result<Json<SessionTransferResponse>, (StatusCode, String)> {
+    let staff_id = claims.user_id()
+        .ok_or((StatusCode::UNAUTHORIZED, "Invalid token subject".to_string()))?;
+
+    let db_err = |e: sqlx::Error| (StatusCode::INTERNAL_SERVER_ERROR, format!("Failed to transfer session: {}", e));
+
+    let mut tx = state.pool.begin().await.map_err(db_err)?;
+
+    let session: Session = sqlx::query_as(
+        "SELECT id, user_id, machine_id, started_at, ended_at, minutes_consumed, included_minutes, charged_minutes, paused_minutes, end_reason FROM sessions WHERE id = ?",
+    )
+    .bind(session_id)
+    .fetch_optional(&mut *tx)
+    .await
+    .map_err(db_err)?
+    .ok_or((StatusCode::NOT_FOUND, "Session not found".to_string()))?;
+
+    if session.ended_at.is_some() {
+        return Err((StatusCode::BAD_REQUEST, "Session already ended".to_string()));
+    }
+
+    if session.machine_id == req.machine_id {
+        return Err((StatusCode::BAD_REQUEST, "Session is already on that machine".to_string()));
+    }
+
+    let target: Machine = sqlx::query_as(
+        "SELECT id, name, status, class, zone FROM machines WHERE id = ? AND status = 'available' AND decommissioned_at IS NULL",
+    )
+    .bind(req.machine_id)
+    .fetch_optional(&mut *tx)
+    .await
+    .map_err(db_err)?
+    .ok_or((StatusCode::CONFLICT, "Target machine not found or not available".to_string()))?;
+
+    session_service::check_machine_usable(&mut tx, &target, session.user_id).await?;
+
+    sqlx::query("UPDATE session_segments SET ended_at = datetime('now'), end_reason = 'transfer' WHERE session_id = ? AND ended_at IS NULL")
+        .bind(session.id)
+        .execute(&mut *tx)
+        .await
+        .map_err(db_err)?;
+
+    // whatever was running stays behind on the old machine
+    sqlx::query("UPDATE app_usage SET ended_at = datetime('now') WHERE session_id = ? AND ended_at IS NULL")
+        .bind(session.id)
+        .execute(&mut *tx)
+        .await
+        .map_err(db_err)?;
+
+    sqlx::query("INSERT INTO session_segments (session_id, machine_id) VALUES (?, ?)")
+        .bind(session.id)
+        .bind(target.id)
+        .execute(&mut *tx)
+        .await
+        .map_err(db_err)?;
+
+    license_service::move_to_machine(&mut tx, session.id, target.id).await.map_err(db_err)?;
+
+    let old_machine_id = session.machine_id;
+
+    let session: Session = sqlx::query_as(
+        "UPDATE sessions SET machine_id = ? WHERE id = ?
+         RETURNING id, user_id, machine_id, started_at, ended_at, minutes_consumed, included_minutes, charged_minutes, paused_minutes, end_reason",
+    )
+    .bind(target.id)
+    .bind(session.id)
+    .fetch_one(&mut *tx)
+    .await
+    .map_err(db_err)?;
+
+    sqlx::query("UPDATE machines SET status = 'in_use' WHERE id = ?")
+        .bind(target.id)
+        .execute(&mut *tx)
+        .await
+        .map_err(db_err)?;
+
+    power_service::keep_awake(&mut tx, target.id).await.map_err(db_err)?;
+
+    // the old machine is locked either way, then parked or handed back to the pool
+    machine_service::queue_command(&mut tx, old_machine_id, "lock").await.map_err(db_err)?;
+
+    // a blocking ticket parks the old machine until staff resolve it
+    if req.maintenance.unwrap_or(false) {
+        sqlx::query(
+            "INSERT INTO maintenance_tickets (machine_id, title, description, blocking, opened_by) VALUES (?, ?, ?, 1, ?)",
+        )
+        .bind(old_machine_id)
+        .bind("Taken out of service when a session was moved off it")
+        .bind(&req.note)
+        .bind(staff_id)
+        .execute(&mut *tx)
+        .await
+        .map_err(db_err)?;
+    }
+
+    machine_service::release_machine(&mut tx, old_machine_id).await.map_err(db_err)?;
+
+    // a paused session stays locked on the new machine until resumed
+    let paused = sqlx::query("SELECT 1 FROM session_pauses WHERE session_id = ? AND resumed_at IS NULL")
+        .bind(session.id)
+        .fetch_optional(&mut *tx)
+        .await
+        .map_err(db_err)?;
+
+    if paused.is_none() {
+        machine_service::queue_command(&mut tx, target.id, "unlock").await.map_err(db_err)?;
+    }
+
+    let segments = sqlx::query_as::<_, SessionSegment>(
+        "SELECT id, session_id, machine_id, started_at, ended_at, end_reason FROM session_segments WHERE session_id = ? ORDER BY id",
+    )
+    .bind(session.id)
+    .fetch_all(&mut *tx)
+    .await
+    .map_err(db_err)?;
+
+    tx.commit().await.map_err(db_err)?;
+
+    let response = SessionTransferResponse {
+        session,
+        segments,
+        message: format!("Session moved to machine {}", target.name),
+    };
+
     Ok(Json(response))
 }
\ No newline at end of file
@@ -0,0 +1,210 @@
+use axum::{
+    Extension, Json,
+    extract::{Query, State},
+    http::{StatusCode, header},
+    response::{IntoResponse, Response},
+};
+use serde::Serialize;
+use sqlx::{Sqlite, sqlite::SqliteArguments, query::QueryAs};
+use crate::{
+    auth::jwt::JwtClaims,
+    models::session::{EndReasonBreakdown, ReportRange, Session, SessionFilter, SessionTotals},
+    state::AppState,
+};
+
+const DEFAULT_PER_PAGE: i64 = 50;
+const MAX_PER_PAGE: i64 = 200;
+// far past any real history, keeps the offset well inside an i64
+const MAX_PAGE: i64 = 1_000_000;
+
+const SESSION_COLUMNS: &str =
+    "id, user_id, machine_id, started_at, ended_at, minutes_consumed, included_minutes, charged_minutes, paused_minutes, end_reason";
+
+const SESSION_WHERE: &str =
+    "WHERE (? IS NULL OR user_id = ?)
+       AND (? IS NULL OR machine_id = ?)
+       AND (? IS NULL OR started_at >= ?)
+       AND (? IS NULL OR started_at < ?)
+       AND (? IS NULL OR (? = 'active' AND ended_at IS NULL) OR (? = 'ended' AND ended_at IS NOT NULL))
+       AND (? IS NULL OR minutes_consumed >= ?)
+       AND (? IS NULL OR minutes_consumed <= ?)
+       AND (? IS NULL OR end_reason = ?)";
+
+#[derive(Serialize)]
+pub struct SessionPageResponse {
+    pub sessions: Vec<Session>,
+    pub totals: SessionTotals,
+    pub page: i64,
+    pub per_page: i64,
+    pub message: String,
+}
+
+fn bind_filter<'q, O>(
+    query: QueryAs<'q, Sqlite, O, SqliteArguments<'q>>,
+    filter: &'q SessionFilter,
+) -> QueryAs<'q, Sqlite, O, SqliteArguments<'q>> {
+    query
+        .bind(filter.user_id)
+        .bind(filter.user_id)
+        .bind(filter.machine_id)
+        .bind(filter.machine_id)
+        .bind(&filter.from)
+        .bind(&filter.from)
+        .bind(&filter.to)
+        .bind(&filter.to)
+        .bind(&filter.status)
+        .bind(&filter.status)
+        .bind(&filter.status)
+        .bind(filter.min_minutes)
+        .bind(filter.min_minutes)
+        .bind(filter.max_minutes)
+        .bind(filter.max_minutes)
+        .bind(&filter.end_reason)
+        .bind(&filter.end_reason)
+}
+
+fn validate_filter(filter: &SessionFilter) -> Result<(), (StatusCode, String)> {
+    if filter.status.as_deref().is_some_and(|status| status != "active" && status != "ended") {
+        return Err((StatusCode::BAD_REQUEST, "Status must be active or ended".to_string()));
+    }
+
+    Ok(())
+}
+
+async fn search(state: &AppState, filter: &SessionFilter) -> Result<SessionPageResponse, (StatusCode, String)> {
+    validate_filter(filter)?;
+
+    let page = filter.page.unwrap_or(1).clamp(1, MAX_PAGE);
+    let per_page = filter.per_page.unwrap_or(DEFAULT_PER_PAGE).clamp(1, MAX_PER_PAGE);
+
+    let sql = format!(
+        "SELECT {} FROM sessions {} ORDER BY started_at DESC, id DESC LIMIT ? OFFSET ?",
+        SESSION_COLUMNS, SESSION_WHERE
+    );
+    let sessions = bind_filter(sqlx::query_as::<_, Session>(&sql), filter)
+        .bind(per_page)
+        .bind((page - 1).saturating_mul(per_page))
+        .fetch_all(&state.pool)
+        .await
+        .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, format!("Failed to fetch sessions: {}", e)))?;
+
+    let sql = format!(
+        "SELECT COUNT(*) AS sessions,
+                COALESCE(SUM(minutes_consumed), 0) AS minutes_consumed,
+                COALESCE(SUM(included_minutes), 0) AS included_minutes,
+                COALESCE(SUM(charged_minutes), 0) AS charged_minutes,
+                COALESCE(SUM(paused_minutes), 0) AS paused_minutes
+         FROM sessions {}",
+        SESSION_WHERE
+    );
+    let totals = bind_filter(sqlx::query_as::<_, SessionTotals>(&sql), filter)
+        .fetch_one(&state.pool)
+        .await
+        .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, format!("Failed to total sessions: {}", e)))?;
+
+    Ok(SessionPageResponse {
+        sessions,
+        totals,
+        page,
+        per_page,
+        message: "Sessions retrieved successfully".to_string(),
+    })
+}
+
+pub async fn search_sessions(
+    State(state): State<AppState>,
+    Query(filter): Query<SessionFilter>,
+) -> Result<Json<SessionPageResponse>, (StatusCode, String)> {
+    Ok(Json(search(&state, &filter).await?))
+}
+
+pub async fn get_my_sessions(
+    State(state): State<AppState>,
+    Extension(claims): Extension<JwtClaims>,
+    Query(filter): Query<SessionFilter>,
+) -> Result<Json<SessionPageResponse>, (StatusCode, String)> {
+    let user_id = claims.user_id()
+        .ok_or((StatusCode::UNAUTHORIZED, "Invalid token subject".to_string()))?;
+
+    let filter = SessionFilter { user_id: Some(user_id), ..filter };
+
+    Ok(Json(search(&state, &filter).await?))
+}
+
+/// Every matching session, unpaged, for accounting.
+pub async fn export_sessions(
+    State(state): State<AppState>,
+    Query(filter): Query<SessionFilter>,
+) -> Result<Response, (StatusCode, String)> {
+    validate_filter(&filter)?;
+
+    let sql = format!("SELECT {} FROM sessions {} ORDER BY started_at, id", SESSION_COLUMNS, SESSION_WHERE);
+    let sessions = bind_filter(sqlx::query_as::<_, Session>(&sql), &filter)
+        .fetch_all(&state.pool)
+        .await
+        .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, format!("Failed to export sessions: {}", e)))?;
+
+    match filter.format.as_deref().unwrap_or("csv") {
+        "csv" => {
+            let mut csv = String::from(
+                "id,user_id,machine_id,started_at,ended_at,minutes_consumed,included_minutes,charged_minutes,paused_minutes,end_reason\n",
+            );
+            for s in &sessions {
+                csv.push_str(&format!(
+                    "{},{},{},{},{},{},{},{},{},{}\n",
+                    s.id,
+                    s.user_id,
+                    s.machine_id,
+                    s.started_at,
+                    s.ended_at.as_deref().unwrap_or(""),
+                    s.minutes_consumed,
+                    s.included_minutes,
+                    s.charged_minutes,
+                    s.paused_minutes,
+                    s.end_reason.as_deref().unwrap_or(""),
+                ));
+            }
+
+            Ok((
+                [
+                    (header::CONTENT_TYPE, "text/csv; charset=utf-8"),
+                    (header::CONTENT_DISPOSITION, "attachment; filename=\"sessions.csv\""),
+                ],
+                csv,
+            )
+                .into_response())
+        }
+        "json" => Ok((
+            [(header::CONTENT_DISPOSITION, "attachment; filename=\"sessions.json\"")],
+            Json(sessions),
+        )
+            .into_response()),
+        _ => Err((StatusCode::BAD_REQUEST, "Format must be csv or json".to_string())),
+    }
+}
+
+/// How sessions ended over the range, by start time.
+pub async fn get_end_reason_report(
+    State(state): State<AppState>,
+    Query(range): Query<ReportRange>,
+) -> Result<Json<Vec<EndReasonBreakdown>>, (StatusCode, String)> {
+    let breakdown = sqlx::query_as::<_, EndReasonBreakdown>(
+        "SELECT COALESCE(end_reason, 'unknown') AS end_reason,
+                COUNT(*) AS sessions,
+                COALESCE(SUM(minutes_consumed), 0) AS minutes_consumed,
+                COALESCE(SUM(charged_minutes), 0) AS charged_minutes
+         FROM sessions
+         WHERE ended_at IS NOT NULL AND (? IS NULL OR started_at >= ?) AND (? IS NULL OR started_at < ?)
+         GROUP BY COALESCE(end_reason, 'unknown')
+         ORDER BY sessions DESC",
+    )
+    .bind(&range.from)
+    .bind(&range.from)
+    .bind(&range.to)
+    .bind(&range.to)
+    .fetch_all(&state.pool)
+    .await
+    .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, format!("Failed to build report: {}", e)))?;
+
+    Ok(Json(breakdown))
+}
\ No newline at end of file
@@ -0,0 +1,222 @@
+use axum::{
+    Extension, Json,
+    extract::{Path, Query, State},
+    http::StatusCode,
+};
+use serde::Serialize;
+use crate::{
+    auth::jwt::JwtClaims,
+    models::subscription::{
+        Subscription, SubscriptionPlan, CreatePlanReq, UpdatePlanReq, SubscribeReq, SubscriptionFilter,
+    },
+    services::subscription_service,
+    state::AppState,
+};
+
+#[derive(Serialize)]
+pub struct PlanResponse {
+    pub plan: SubscriptionPlan,
+    pub message: String,
+}
+
+#[derive(Serialize)]
+pub struct SubscriptionResponse {
+    pub subscription: Subscription,
+    pub message: String,
+}
+
+#[derive(Serialize)]
+pub struct SubscriptionsResponse {
+    pub subscriptions: Vec<Subscription>,
+    pub message: String,
+}
+
+fn validate_discount(percent: i64) -> Result<(), (StatusCode, String)> {
+    if !(0..=100).contains(&percent) {
+        return Err((StatusCode::BAD_REQUEST, "Discount must be between 0 and 100 percent".to_string()));
+    }
+    Ok(())
+}
+
+pub async fn get_plans(
+    State(state): State<AppState>,
+) -> Result<Json<Vec<SubscriptionPlan>>, (StatusCode, String)> {
+    let plans = sqlx::query_as::<_, SubscriptionPlan>(
+        "SELECT id, name, price, period_days, included_minutes, extra_discount_percent, machine_classes, active, created_at
+         FROM subscription_plans WHERE active = 1 ORDER BY price",
+    )
+    .fetch_all(&state.pool)
+    .await
+    .map_err(|_| (StatusCode::INTERNAL_SERVER_ERROR, "Failed to fetch plans".to_string()))?;
+
+    Ok(Json(plans))
+}
+
+pub async fn create_plan(
+    State(state): State<AppState>,
+    Json(req): Json<CreatePlanReq>,
+) -> Result<Json<PlanResponse>, (StatusCode, String)> {
+    let period_days = req.period_days.unwrap_or(30);
+    let discount = req.extra_discount_percent.unwrap_or(0);
+
+    if req.price < 0 || req.included_minutes < 0 || period_days <= 0 {
+        return Err((StatusCode::BAD_REQUEST, "Price, included minutes and period must not be negative".to_string()));
+    }
+    validate_discount(discount)?;
+
+    let plan = sqlx::query_as::<_, SubscriptionPlan>(
+        "INSERT INTO subscription_plans (name, price, period_days, included_minutes, extra_discount_percent, machine_classes)
+         VALUES (?, ?, ?, ?, ?, ?)
+         RETURNING id, name, price, period_days, included_minutes, extra_discount_percent, machine_classes, active, created_at",
+    )
+    .bind(&req.name)
+    .bind(req.price)
+    .bind(period_days)
+    .bind(req.included_minutes)
+    .bind(discount)
+    .bind(&req.machine_classes)
+    .fetch_one(&state.pool)
+    .await
+    .map_err(|_| (StatusCode::CONFLICT, "Plan with this name already exists".to_string()))?;
+
+    let response = PlanResponse {
+        message: format!("Plan {} created successfully", plan.name),
+        plan,
+    };
+
+    Ok(Json(response))
+}
+
+pub async fn update_plan(
+    Path(plan_id): Path<i64>,
+    State(state): State<AppState>,
+    Json(req): Json<UpdatePlanReq>,
+) -> Result<Json<PlanResponse>, (StatusCode, String)> {
+    if let Some(discount) = req.extra_discount_percent {
+        validate_discount(discount)?;
+    }
+
+    let plan = sqlx::query_as::<_, SubscriptionPlan>(
+        "UPDATE subscription_plans SET
+             price = COALESCE(?, price),
+             included_minutes = COALESCE(?, included_minutes),
+             extra_discount_percent = COALESCE(?, extra_discount_percent),
+             machine_classes = COALESCE(?, machine_classes),
+             active = COALESCE(?, active)
+         WHERE id = ?
+         RETURNING id, name, price, period_days, included_minutes, extra_discount_percent, machine_classes, active, created_at",
+    )
+    .bind(req.price)
+    .bind(req.included_minutes)
+    .bind(req.extra_discount_percent)
+    .bind(&req.machine_classes)
+    .bind(req.active.map(|a| a as i64))
+    .bind(plan_id)
+    .fetch_one(&state.pool)
+    .await
+    .map_err(|_| (StatusCode::NOT_FOUND, "Plan not found".to_string()))?;
+
+    let response = PlanResponse {
+        message: format!("Plan {} updated successfully", plan.name),
+        plan,
+    };
+
+    Ok(Json(response))
+}
+
+pub async fn get_subscriptions(
+    State(state): State<AppState>,
+    Query(filter): Query<SubscriptionFilter>,
+) -> Result<Json<SubscriptionsResponse>, (StatusCode, String)> {
+    let subscriptions = sqlx::query_as::<_, Subscription>(
+        "SELECT id, user_id, plan_id, status, auto_renew, started_at, current_period_start, current_period_end, minutes_used, cancelled_at, ended_at
+         FROM subscriptions
+         WHERE ? IS NULL OR status = ?
+         ORDER BY started_at DESC",
+    )
+    .bind(&filter.status)
+    .bind(&filter.status)
+    .fetch_all(&state.pool)
+    .await
+    .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, format!("Failed to fetch subscriptions: {}", e)))?;
+
+    let response = SubscriptionsResponse {
+        subscriptions,
+        message: "Subscriptions retrieved successfully".to_string(),
+    };
+
+    Ok(Json(response))
+}
+
+pub async fn get_my_subscription(
+    State(state): State<AppState>,
+    Extension(claims): Extension<JwtClaims>,
+) -> Result<Json<SubscriptionResponse>, (StatusCode, String)> {
+    let user_id = claims.user_id()
+        .ok_or((StatusCode::UNAUTHORIZED, "Invalid token subject".to_string()))?;
+
+    let subscription = sqlx::query_as::<_, Subscription>(
+        "SELECT id, user_id, plan_id, status, auto_renew, started_at, current_period_start, current_period_end, minutes_used, cancelled_at, ended_at
+         FROM subscriptions
+         WHERE user_id = ?
+         ORDER BY started_at DESC, id DESC
+         LIMIT 1",
+    )
+    .bind(user_id)
+    .fetch_optional(&state.pool)
+    .await
+    .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, format!("Database error: {}", e)))?
+    .ok_or((StatusCode::NOT_FOUND, "No subscription found".to_string()))?;
+
+    let response = SubscriptionResponse {
+        subscription,
+        message: "Subscription retrieved successfully".to_string(),
+    };
+
+    Ok(Json(response))
+}
+
+pub async fn subscribe(
+    State(state): State<AppState>,
+    Extension(claims): Extension<JwtClaims>,
+    Json(req): Json<SubscribeReq>,
+) -> Result<Json<SubscriptionResponse>, (StatusCode, String)> {
+    let user_id = claims.user_id()
+        .ok_or((StatusCode::UNAUTHORIZED, "Invalid token subject".to_string()))?;
+
+    let subscription = subscription_service::subscribe(&state.pool, user_id, req.plan_id).await?;
+
+    let response = SubscriptionResponse {
+        message: format!("Subscription active until {}", subscription.current_period_end),
+        subscription,
+    };
+
+    Ok(Json(response))
+}
+
+pub async fn cancel_subscription(
+    State(state): State<AppState>,
+    Extension(claims): Extension<JwtClaims>,
+) -> Result<Json<SubscriptionResponse>, (StatusCode, String)> {
+    let user_id = claims.user_id()
+        .ok_or((StatusCode::UNAUTHORIZED, "Invalid token subject".to_string()))?;
+
+    // the plan stays usable until the paid period ends; the scheduler closes it out
+    let subscription = sqlx::query_as::<_, Subscription>(
+        "UPDATE subscriptions SET auto_renew = 0, cancelled_at = datetime('now')
+         WHERE user_id = ? AND status = 'active' AND auto_renew = 1
+         RETURNING id, user_id, plan_id, status, auto_renew, started_at, current_period_start, current_period_end, minutes_used, cancelled_at, ended_at",
+    )
+    .bind(user_id)
+    .fetch_optional(&state.pool)
+    .await
+    .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, format!("Database error: {}", e)))?
+    .ok_or((StatusCode::NOT_FOUND, "No renewing subscription to cancel".to_string()))?;
+
+    let response = SubscriptionResponse {
+        message: format!("Subscription cancelled. Your plan remains active until {}", subscription.current_period_end),
+        subscription,
+    };
+
+    Ok(Json(response))
+}
@@ -0,0 +1,169 @@
+use axum::{
+    Extension, Json,
+    extract::{Path, Query, State},
+    http::StatusCode,
+};
+use serde::Serialize;
+use crate::{
+    auth::jwt::JwtClaims,
+    models::telemetry::{AlertFilter, MachineAlert, TelemetryQuery, TelemetryRollup, TelemetrySample},
+    state::AppState,
+};
+
+const ALERT_COLUMNS: &str =
+    "id, machine_id, kind, value, threshold, raised_at, acknowledged_at, acknowledged_by, resolved_at";
+
+#[derive(Serialize)]
+pub struct TelemetryResponse {
+    pub machine_id: i64,
+    pub agent_version: Option<String>,
+    pub logged_in_user: Option<String>,
+    pub resolution: String,
+    pub samples: Vec<TelemetrySample>,
+    pub hourly: Vec<TelemetryRollup>,
+    pub message: String,
+}
+
+#[derive(Serialize)]
+pub struct AlertResponse {
+    pub alert: MachineAlert,
+    pub message: String,
+}
+
+#[derive(Serialize)]
+pub struct AlertsResponse {
+    pub alerts: Vec<MachineAlert>,
+    pub message: String,
+}
+
+pub async fn get_machine_telemetry(
+    State(state): State<AppState>,
+    Path(machine_id): Path<i64>,
+    Query(query): Query<TelemetryQuery>,
+) -> Result<Json<TelemetryResponse>, (StatusCode, String)> {
+    let resolution = query.resolution.unwrap_or_else(|| "raw".to_string());
+    if resolution != "raw" && resolution != "hourly" {
+        return Err((StatusCode::BAD_REQUEST, "Resolution must be raw or hourly".to_string()));
+    }
+
+    let db_err = |e: sqlx::Error| (StatusCode::INTERNAL_SERVER_ERROR, format!("Failed to fetch telemetry: {}", e));
+
+    let (agent_version, logged_in_user): (Option<String>, Option<String>) =
+        sqlx::query_as("SELECT agent_version, logged_in_user FROM machines WHERE id = ?")
+            .bind(machine_id)
+            .fetch_optional(&state.pool)
+            .await
+            .map_err(db_err)?
+            .ok_or((StatusCode::NOT_FOUND, "Machine not found".to_string()))?;
+
+    let mut samples = Vec::new();
+    let mut hourly = Vec::new();
+
+    if resolution == "raw" {
+        samples = sqlx::query_as::<_, TelemetrySample>(
+            "SELECT recorded_at, cpu_temp_c, gpu_temp_c, cpu_load_percent, gpu_load_percent,
+                    ram_used_mb, ram_total_mb, disk_free_mb, uptime_seconds
+             FROM telemetry_samples
+             WHERE machine_id = ? AND (? IS NULL OR recorded_at >= ?) AND (? IS NULL OR recorded_at < ?)
+             ORDER BY recorded_at",
+        )
+        .bind(machine_id)
+        .bind(&query.from)
+        .bind(&query.from)
+        .bind(&query.to)
+        .bind(&query.to)
+        .fetch_all(&state.pool)
+        .await
+        .map_err(db_err)?;
+    } else {
+        hourly = sqlx::query_as::<_, TelemetryRollup>(
+            "SELECT hour, samples, avg_cpu_temp_c, max_cpu_temp_c, avg_gpu_temp_c, max_gpu_temp_c,
+                    avg_cpu_load_percent, avg_gpu_load_percent, avg_ram_used_mb, min_disk_free_mb, max_uptime_seconds
+             FROM telemetry_hourly
+             WHERE machine_id = ? AND (? IS NULL OR hour >= ?) AND (? IS NULL OR hour < ?)
+             ORDER BY hour",
+        )
+        .bind(machine_id)
+        .bind(&query.from)
+        .bind(&query.from)
+        .bind(&query.to)
+        .bind(&query.to)
+        .fetch_all(&state.pool)
+        .await
+        .map_err(db_err)?;
+    }
+
+    let response = TelemetryResponse {
+        machine_id,
+        agent_version,
+        logged_in_user,
+        resolution,
+        samples,
+        hourly,
+        message: "Telemetry retrieved successfully".to_string(),
+    };
+
+    Ok(Json(response))
+}
+
+pub async fn get_alerts(
+    State(state): State<AppState>,
+    Query(filter): Query<AlertFilter>,
+) -> Result<Json<AlertsResponse>, (StatusCode, String)> {
+    let status = filter.status.as_deref();
+    if status.is_some_and(|s| s != "open" && s != "resolved") {
+        return Err((StatusCode::BAD_REQUEST, "Status must be open or resolved".to_string()));
+    }
+
+    let alerts = sqlx::query_as::<_, MachineAlert>(&format!(
+        "SELECT {} FROM machine_alerts
+         WHERE (? IS NULL OR machine_id = ?)
+           AND (? IS NULL OR (? = 'open' AND resolved_at IS NULL) OR (? = 'resolved' AND resolved_at IS NOT NULL))
+         ORDER BY raised_at DESC, id DESC",
+        ALERT_COLUMNS
+    ))
+    .bind(filter.machine_id)
+    .bind(filter.machine_id)
+    .bind(status)
+    .bind(status)
+    .bind(status)
+    .fetch_all(&state.pool)
+    .await
+    .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, format!("Failed to fetch alerts: {}", e)))?;
+
+    let response = AlertsResponse {
+        alerts,
+        message: "Alerts retrieved successfully".to_string(),
+    };
+
+    Ok(Json(response))
+}
+
+pub async fn acknowledge_alert(
+    State(state): State<AppState>,
+    Extension(claims): Extension<JwtClaims>,
+    Path(alert_id): Path<i64>,
+) -> Result<Json<AlertResponse>, (StatusCode, String)> {
+    let staff_id = claims.user_id()
+        .ok_or((StatusCode::UNAUTHORIZED, "Invalid token subject".to_string()))?;
+
+    let alert = sqlx::query_as::<_, MachineAlert>(&format!(
+        "UPDATE machine_alerts SET acknowledged_at = datetime('now'), acknowledged_by = ?
+         WHERE id = ? AND acknowledged_at IS NULL
+         RETURNING {}",
+        ALERT_COLUMNS
+    ))
+    .bind(staff_id)
+    .bind(alert_id)
+    .fetch_optional(&state.pool)
+    .await
+    .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, format!("Failed to acknowledge alert: {}", e)))?
+    .ok_or((StatusCode::NOT_FOUND, "Alert not found or already acknowledged".to_string()))?;
+
+    let response = AlertResponse {
+        alert,
+        message: "Alert acknowledged".to_string(),
+    };
+
+    Ok(Json(response))
+}
\ No newline at end of file
@@ -0,0 +1,296 @@
+use axum::{
+    Extension, Json,
+    extract::{Path, Query, State},
+    http::StatusCode,
+};
+use serde::Serialize;
+use crate::{
+    auth::jwt::JwtClaims,
+    models::voucher::{Voucher, VoucherRedemption, GenerateVouchersReq, VoucherFilter, RedeemReq},
+    services::{billing_service, code_service::{generate_code, normalize_code}},
+    state::AppState,
+};
+
+const MAX_BATCH_SIZE: i64 = 1000;
+
+// failed redemptions allowed per user inside the window before we lock them out
+const MAX_FAILED_ATTEMPTS: i64 = 5;
+const ATTEMPT_WINDOW_MINUTES: i64 = 15;
+
+#[derive(Serialize)]
+pub struct VouchersResponse {
+    pub vouchers: Vec<Voucher>,
+    pub message: String,
+}
+
+#[derive(Serialize)]
+pub struct RedemptionsResponse {
+    pub redemptions: Vec<VoucherRedemption>,
+    pub message: String,
+}
+
+#[derive(Serialize)]
+pub struct RedeemResponse {
+    pub user_id: i64,
+    pub kind: String,
+    pub value: i64,
+    pub balance: i64,
+    pub minutes_balance: i64,
+    pub message: String,
+}
+
+pub async fn generate_vouchers(
+    State(state): State<AppState>,
+    Extension(claims): Extension<JwtClaims>,
+    Json(req): Json<GenerateVouchersReq>,
+) -> Result<Json<VouchersResponse>, (StatusCode, String)> {
+    if req.kind != "minutes" && req.kind != "money" {
+        return Err((StatusCode::BAD_REQUEST, "Kind must be 'minutes' or 'money'".to_string()));
+    }
+    if req.value <= 0 {
+        return Err((StatusCode::BAD_REQUEST, "Value must be positive".to_string()));
+    }
+    if req.count <= 0 || req.count > MAX_BATCH_SIZE {
+        return Err((StatusCode::BAD_REQUEST, format!("Count must be between 1 and {}", MAX_BATCH_SIZE)));
+    }
+
+    let max_uses = req.max_uses.unwrap_or(1);
+    if max_uses <= 0 {
+        return Err((StatusCode::BAD_REQUEST, "Max uses must be positive".to_string()));
+    }
+
+    if let Some(expires_at) = &req.expires_at {
+        chrono::NaiveDateTime::parse_from_str(expires_at, "%Y-%m-%d %H:%M:%S")
+            .map_err(|_| (StatusCode::BAD_REQUEST, "expires_at must be formatted as YYYY-MM-DD HH:MM:SS".to_string()))?;
+    }
+
+    let mut tx = state.pool.begin()
+        .await
+        .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, format!("Database error: {}", e)))?;
+
+    let mut vouchers = Vec::with_capacity(req.count as usize);
+    while (vouchers.len() as i64) < req.count {
+        // a code collision is skipped by the unique index and simply retried
+        let voucher = sqlx::query_as::<_, Voucher>(
+            "INSERT INTO vouchers (code, kind, value, max_uses, campaign, expires_at, created_by)
+             VALUES (?, ?, ?, ?, ?, ?, ?)
+             ON CONFLICT(code) DO NOTHING
+             RETURNING id, code, kind, value, max_uses, uses, campaign, expires_at, disabled, created_by, created_at",
+        )
+        .bind(generate_code())
+        .bind(&req.kind)
+        .bind(req.value)
+        .bind(max_uses)
+        .bind(&req.campaign)
+        .bind(&req.expires_at)
+        .bind(claims.user_id())
+        .fetch_optional(&mut *tx)
+        .await
+        .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, format!("Failed to create voucher: {}", e)))?;
+
+        if let Some(voucher) = voucher {
+            vouchers.push(voucher);
+        }
+    }
+
+    tx.commit()
+        .await
+        .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, format!("Failed to create vouchers: {}", e)))?;
+
+    let response = VouchersResponse {
+        message: format!("Generated {} vouchers", vouchers.len()),
+        vouchers,
+    };
+
+    Ok(Json(response))
+}
+
+pub async fn get_vouchers(
+    State(state): State<AppState>,
+    Query(filter): Query<VoucherFilter>,
+) -> Result<Json<VouchersResponse>, (StatusCode, String)> {
+    let vouchers = sqlx::query_as::<_, Voucher>(
+        "SELECT id, code, kind, value, max_uses, uses, campaign, expires_at, disabled, created_by, created_at
+         FROM vouchers
+         WHERE ? IS NULL OR campaign = ?
+         ORDER BY created_at DESC, id DESC",
+    )
+    .bind(&filter.campaign)
+    .bind(&filter.campaign)
+    .fetch_all(&state.pool)
+    .await
+    .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, format!("Failed to fetch vouchers: {}", e)))?;
+
+    let response = VouchersResponse {
+        vouchers,
+        message: "Vouchers retrieved successfully".to_string(),
+    };
+
+    Ok(Json(response))
+}
+
+pub async fn get_voucher_redemptions(
+    Path(voucher_id): Path<i64>,
+    State(state): State<AppState>,
+) -> Result<Json<RedemptionsResponse>, (StatusCode, String)> {
+    let redemptions = sqlx::query_as::<_, VoucherRedemption>(
+        "SELECT id, voucher_id, user_id, redeemed_at FROM voucher_redemptions WHERE voucher_id = ? ORDER BY redeemed_at",
+    )
+    .bind(voucher_id)
+    .fetch_all(&state.pool)
+    .await
+    .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, format!("Failed to fetch redemptions: {}", e)))?;
+
+    let response = RedemptionsResponse {
+        redemptions,
+        message: "Redemptions retrieved successfully".to_string(),
+    };
+
+    Ok(Json(response))
+}
+
+pub async fn disable_voucher(
+    Path(voucher_id): Path<i64>,
+    State(state): State<AppState>,
+) -> Result<Json<VouchersResponse>, (StatusCode, String)> {
+    let voucher = sqlx::query_as::<_, Voucher>(
+        "UPDATE vouchers SET disabled = 1 WHERE id = ?
+         RETURNING id, code, kind, value, max_uses, uses, campaign, expires_at, disabled, created_by, created_at",
+    )
+    .bind(voucher_id)
+    .fetch_one(&state.pool)
+    .await
+    .map_err(|_| (StatusCode::NOT_FOUND, "Voucher not found".to_string()))?;
+
+    let response = VouchersResponse {
+        message: format!("Voucher {} has been disabled", voucher.code),
+        vouchers: vec![voucher],
+    };
+
+    Ok(Json(response))
+}
+
+pub async fn redeem_voucher(
+    State(state): State<AppState>,
+    Extension(claims): Extension<JwtClaims>,
+    Json(req): Json<RedeemReq>,
+) -> Result<Json<RedeemResponse>, (StatusCode, String)> {
+    let user_id = claims.user_id()
+        .ok_or((StatusCode::UNAUTHORIZED, "Invalid token subject".to_string()))?;
+    let code = normalize_code(&req.code);
+
+    let mut tx = state.pool.begin()
+        .await
+        .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, format!("Database error: {}", e)))?;
+
+    // the attempt is written first so the transaction holds the write lock while
+    // counting, otherwise parallel requests could all pass the limit together
+    let attempt_id: i64 = sqlx::query_scalar(
+        "INSERT INTO voucher_redeem_attempts (user_id, code, success) VALUES (?, ?, 0) RETURNING id",
+    )
+    .bind(user_id)
+    .bind(&code)
+    .fetch_one(&mut *tx)
+    .await
+    .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, format!("Database error: {}", e)))?;
+
+    let failed_attempts: i64 = sqlx::query_scalar(
+        "SELECT COUNT(*) FROM voucher_redeem_attempts
+         WHERE user_id = ? AND success = 0 AND attempted_at > datetime('now', ?) AND id <> ?",
+    )
+    .bind(user_id)
+    .bind(format!("-{} minutes", ATTEMPT_WINDOW_MINUTES))
+    .bind(attempt_id)
+    .fetch_one(&mut *tx)
+    .await
+    .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, format!("Database error: {}", e)))?;
+
+    if failed_attempts >= MAX_FAILED_ATTEMPTS {
+        return Err((StatusCode::TOO_MANY_REQUESTS, "Too many failed attempts, try again later".to_string()));
+    }
+
+    // unknown, expired, disabled, used up and already redeemed codes share one
+    // error so codes can't be probed
+    let voucher = sqlx::query_as::<_, Voucher>(
+        "SELECT id, code, kind, value, max_uses, uses, campaign, expires_at, disabled, created_by, created_at
+         FROM vouchers
+         WHERE code = ? AND disabled = 0 AND (expires_at IS NULL OR expires_at > datetime('now')) AND uses < max_uses
+           AND NOT EXISTS (SELECT 1 FROM voucher_redemptions WHERE voucher_id = vouchers.id AND user_id = ?)",
+    )
+    .bind(&code)
+    .bind(user_id)
+    .fetch_optional(&mut *tx)
+    .await
+    .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, format!("Database error: {}", e)))?;
+
+    let Some(voucher) = voucher else {
+        // keep the failed attempt so it counts towards the limit
+        tx.commit()
+            .await
+            .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, format!("Database error: {}", e)))?;
+        return Err((StatusCode::NOT_FOUND, "Invalid or expired voucher code".to_string()));
+    };
+
+    sqlx::query("UPDATE vouchers SET uses = uses + 1 WHERE id = ?")
+        .bind(voucher.id)
+        .execute(&mut *tx)
+        .await
+        .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, format!("Failed to redeem voucher: {}", e)))?;
+
+    sqlx::query("INSERT INTO voucher_redemptions (voucher_id, user_id) VALUES (?, ?)")
+        .bind(voucher.id)
+        .bind(user_id)
+        .execute(&mut *tx)
+        .await
+        .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, format!("Failed to redeem voucher: {}", e)))?;
+
+    sqlx::query("UPDATE voucher_redeem_attempts SET success = 1 WHERE id = ?")
+        .bind(attempt_id)
+        .execute(&mut *tx)
+        .await
+        .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, format!("Failed to redeem voucher: {}", e)))?;
+
+    let credit_query = if voucher.kind == "money" {
+        "UPDATE users SET balance = balance + ? WHERE id = ? RETURNING balance, minutes_balance"
+    } else {
+        "UPDATE users SET minutes_balance = minutes_balance + ? WHERE id = ? RETURNING balance, minutes_balance"
+    };
+
+    let (mut balance, mut minutes_balance): (i64, i64) = sqlx::query_as(credit_query)
+        .bind(voucher.value)
+        .bind(user_id)
+        .fetch_one(&mut *tx)
+        .await
+        .map_err(|_| (StatusCode::NOT_FOUND, "User not found".to_string()))?;
+
+    // credit pays off minutes still owed from an uncovered session first
+    if voucher.kind == "money" {
+        let settled = billing_service::settle_minutes_debt(&mut tx, user_id)
+            .await
+            .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, format!("Failed to redeem voucher: {}", e)))?;
+
+        if settled > 0 {
+            (balance, minutes_balance) = sqlx::query_as("SELECT balance, minutes_balance FROM users WHERE id = ?")
+                .bind(user_id)
+                .fetch_one(&mut *tx)
+                .await
+                .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, format!("Failed to redeem voucher: {}", e)))?;
+        }
+    }
+
+    tx.commit()
+        .await
+        .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, format!("Failed to redeem voucher: {}", e)))?;
+
+    let unit = if voucher.kind == "money" { "credit" } else { "minutes" };
+    let response = RedeemResponse {
+        user_id,
+        kind: voucher.kind.clone(),
+        value: voucher.value,
+        balance,
+        minutes_balance,
+        message: format!("Voucher redeemed: {} {} added to your account", voucher.value, unit),
+    };
+
+    Ok(Json(response))
+}
@@ -0,0 +1,210 @@
+use axum::{
+    Extension, Json,
+    extract::State,
+    http::StatusCode,
+};
+use serde::Serialize;
+use crate::{
+    auth::jwt::JwtClaims,
+    models::waitlist::{WaitlistEntry, WaitlistStatus, JoinWaitlistReq},
+    services::{machine_service, reservation_service, waitlist_service},
+    state::AppState,
+};
+
+const ENTRY_COLUMNS: &str =
+    "id, user_id, machine_class, status, created_at, offered_machine_id, offered_at, offer_expires_at, seated_at";
+
+#[derive(Serialize)]
+pub struct WaitlistStatusResponse {
+    pub waitlist: WaitlistStatus,
+    pub message: String,
+}
+
+#[derive(Serialize)]
+pub struct WaitlistResponse {
+    pub waitlist: Vec<WaitlistStatus>,
+    pub message: String,
+}
+
+pub async fn join_waitlist(
+    State(state): State<AppState>,
+    Extension(claims): Extension<JwtClaims>,
+    Json(req): Json<JoinWaitlistReq>,
+) -> Result<Json<WaitlistStatusResponse>, (StatusCode, String)> {
+    let user_id = claims.user_id()
+        .ok_or((StatusCode::UNAUTHORIZED, "Invalid token subject".to_string()))?;
+
+    let db_err = |e: sqlx::Error| (StatusCode::INTERNAL_SERVER_ERROR, format!("Failed to join waitlist: {}", e));
+
+    let mut tx = state.pool.begin().await.map_err(db_err)?;
+
+    let playing: Option<String> = sqlx::query_scalar(
+        "SELECT m.name FROM sessions s JOIN machines m ON m.id = s.machine_id WHERE s.user_id = ? AND s.ended_at IS NULL",
+    )
+    .bind(user_id)
+    .fetch_optional(&mut *tx)
+    .await
+    .map_err(db_err)?;
+
+    if let Some(name) = playing {
+        return Err((StatusCode::CONFLICT, format!("You already have an active session on {}", name)));
+    }
+
+    if let Some(class) = &req.machine_class {
+        let known: Option<i64> = sqlx::query_scalar("SELECT 1 FROM machines WHERE class = ? AND decommissioned_at IS NULL LIMIT 1")
+            .bind(class)
+            .fetch_optional(&mut *tx)
+            .await
+            .map_err(db_err)?;
+
+        if known.is_none() {
+            return Err((StatusCode::BAD_REQUEST, format!("No machines of class {}", class)));
+        }
+    }
+
+    let entry_id: i64 = sqlx::query_scalar("INSERT INTO waitlist_entries (user_id, machine_class) VALUES (?, ?) RETURNING id")
+        .bind(user_id)
+        .bind(&req.machine_class)
+        .fetch_one(&mut *tx)
+        .await
+        .map_err(|e| match e {
+            sqlx::Error::Database(ref d) if d.is_unique_violation() => {
+                (StatusCode::CONFLICT, "You are already on the waitlist".to_string())
+            }
+            e => db_err(e),
+        })?;
+
+    // a machine may already be free, in which case the new entry is offered it straight away
+    let idle: Vec<i64> = sqlx::query_scalar(
+        "SELECT id FROM machines WHERE status = 'available' AND (? IS NULL OR class = ?) ORDER BY name",
+    )
+    .bind(&req.machine_class)
+    .bind(&req.machine_class)
+    .fetch_all(&mut *tx)
+    .await
+    .map_err(db_err)?;
+
+    for machine_id in idle {
+        if reservation_service::has_upcoming_booking(&mut tx, machine_id).await.map_err(db_err)? {
+            continue;
+        }
+        if waitlist_service::offer_machine(&mut tx, machine_id).await.map_err(db_err)? {
+            break;
+        }
+    }
+
+    let entry = sqlx::query_as::<_, WaitlistEntry>(&format!("SELECT {} FROM waitlist_entries WHERE id = ?", ENTRY_COLUMNS))
+        .bind(entry_id)
+        .fetch_one(&mut *tx)
+        .await
+        .map_err(db_err)?;
+
+    let waitlist = waitlist_service::status_for(&mut tx, entry).await.map_err(db_err)?;
+
+    tx.commit().await.map_err(db_err)?;
+
+    let message = if waitlist.entry.status == "offered" {
+        "A machine is ready for you".to_string()
+    } else {
+        format!("You are number {} on the waitlist", waitlist.position)
+    };
+
+    Ok(Json(WaitlistStatusResponse { waitlist, message }))
+}
+
+pub async fn get_my_waitlist(
+    State(state): State<AppState>,
+    Extension(claims): Extension<JwtClaims>,
+) -> Result<Json<WaitlistStatusResponse>, (StatusCode, String)> {
+    let user_id = claims.user_id()
+        .ok_or((StatusCode::UNAUTHORIZED, "Invalid token subject".to_string()))?;
+
+    let db_err = |e: sqlx::Error| (StatusCode::INTERNAL_SERVER_ERROR, format!("Failed to fetch waitlist: {}", e));
+
+    let mut conn = state.pool.acquire().await.map_err(db_err)?;
+
+    let entry = sqlx::query_as::<_, WaitlistEntry>(&format!(
+        "SELECT {} FROM waitlist_entries WHERE user_id = ? AND status IN ('waiting', 'offered')",
+        ENTRY_COLUMNS
+    ))
+    .bind(user_id)
+    .fetch_optional(&mut *conn)
+    .await
+    .map_err(db_err)?
+    .ok_or((StatusCode::NOT_FOUND, "You are not on the waitlist".to_string()))?;
+
+    let waitlist = waitlist_service::status_for(&mut conn, entry).await.map_err(db_err)?;
+
+    let response = WaitlistStatusResponse {
+        waitlist,
+        message: "Waitlist status retrieved successfully".to_string(),
+    };
+
+    Ok(Json(response))
+}
+
+pub async fn leave_waitlist(
+    State(state): State<AppState>,
+    Extension(claims): Extension<JwtClaims>,
+) -> Result<Json<WaitlistStatusResponse>, (StatusCode, String)> {
+    let user_id = claims.user_id()
+        .ok_or((StatusCode::UNAUTHORIZED, "Invalid token subject".to_string()))?;
+
+    let db_err = |e: sqlx::Error| (StatusCode::INTERNAL_SERVER_ERROR, format!("Failed to leave waitlist: {}", e));
+
+    let mut tx = state.pool.begin().await.map_err(db_err)?;
+
+    let entry = sqlx::query_as::<_, WaitlistEntry>(&format!(
+        "UPDATE waitlist_entries SET status = 'cancelled'
+         WHERE user_id = ? AND status IN ('waiting', 'offered')
+         RETURNING {}",
+        ENTRY_COLUMNS
+    ))
+    .bind(user_id)
+    .fetch_optional(&mut *tx)
+    .await
+    .map_err(db_err)?
+    .ok_or((StatusCode::NOT_FOUND, "You are not on the waitlist".to_string()))?;
+
+    // an offered machine goes on to the next person in line
+    if let Some(machine_id) = entry.offered_machine_id {
+        machine_service::release_machine(&mut tx, machine_id).await.map_err(db_err)?;
+    }
+
+    tx.commit().await.map_err(db_err)?;
+
+    let response = WaitlistStatusResponse {
+        waitlist: WaitlistStatus { entry, position: 0, estimated_wait_minutes: None },
+        message: "You have left the waitlist".to_string(),
+    };
+
+    Ok(Json(response))
+}
+
+pub async fn get_waitlist(
+    State(state): State<AppState>,
+) -> Result<Json<WaitlistResponse>, (StatusCode, String)> {
+    let db_err = |e: sqlx::Error| (StatusCode::INTERNAL_SERVER_ERROR, format!("Failed to fetch waitlist: {}", e));
+
+    let mut conn = state.pool.acquire().await.map_err(db_err)?;
+
+    let entries = sqlx::query_as::<_, WaitlistEntry>(&format!(
+        "SELECT {} FROM waitlist_entries WHERE status IN ('waiting', 'offered') ORDER BY created_at, id",
+        ENTRY_COLUMNS
+    ))
+    .fetch_all(&mut *conn)
+    .await
+    .map_err(db_err)?;
+
+    let mut waitlist = Vec::with_capacity(entries.len());
+    for entry in entries {
+        waitlist.push(waitlist_service::status_for(&mut conn, entry).await.map_err(db_err)?);
+    }
+
+    let response = WaitlistResponse {
+        waitlist,
+        message: "Waitlist retrieved successfully".to_string(),
+    };
+
+    Ok(Json(response))
+}
\ No newline at end of file
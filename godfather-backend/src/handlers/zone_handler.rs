@@ -0,0 +1,267 @@
+use std::collections::HashMap;
+use axum::{
+    Json,
+    extract::{Path, Query, State},
+    http::StatusCode,
+};
+use serde::Serialize;
+use crate::{
+    models::{
+        reservation::CalendarSlot,
+        zone::{CreateZoneReq, FloorQuery, FloorSeat, FloorSession, FloorZone, UpdateZoneReq, Zone},
+    },
+    services::subscription_service,
+    state::AppState,
+};
+
+// how far ahead the floor map shows bookings and maintenance
+const DEFAULT_FLOOR_LOOKAHEAD_HOURS: i64 = 12;
+
+fn floor_lookahead_hours() -> i64 {
+    std::env::var("FLOOR_LOOKAHEAD_HOURS")
+        .ok()
+        .and_then(|v| v.parse().ok())
+        .unwrap_or(DEFAULT_FLOOR_LOOKAHEAD_HOURS)
+}
+
+#[derive(Serialize)]
+pub struct ZoneResponse {
+    pub zone: Zone,
+    pub message: String,
+}
+
+#[derive(Serialize)]
+pub struct ZonesResponse {
+    pub zones: Vec<Zone>,
+    pub message: String,
+}
+
+#[derive(Serialize)]
+pub struct FloorResponse {
+    pub zones: Vec<FloorZone>,
+    pub message: String,
+}
+
+pub async fn get_zones(
+    State(state): State<AppState>,
+) -> Result<Json<ZonesResponse>, (StatusCode, String)> {
+    let zones = sqlx::query_as::<_, Zone>("SELECT id, name, label, sort_order, created_at FROM zones ORDER BY sort_order, name")
+        .fetch_all(&state.pool)
+        .await
+        .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, format!("Failed to fetch zones: {}", e)))?;
+
+    let response = ZonesResponse {
+        zones,
+        message: "Zones retrieved successfully".to_string(),
+    };
+
+    Ok(Json(response))
+}
+
+pub async fn create_zone(
+    State(state): State<AppState>,
+    Json(req): Json<CreateZoneReq>,
+) -> Result<Json<ZoneResponse>, (StatusCode, String)> {
+    let name = req.name.trim().to_lowercase();
+    if name.is_empty() || !name.chars().all(|c| c.is_ascii_alphanumeric() || c == '_') {
+        return Err((StatusCode::BAD_REQUEST, "Zone name may only contain letters, digits and underscores".to_string()));
+    }
+
+    let label = req.label.trim();
+    if label.is_empty() {
+        return Err((StatusCode::BAD_REQUEST, "Zone label is required".to_string()));
+    }
+
+    let zone = sqlx::query_as::<_, Zone>(
+        "INSERT INTO zones (name, label, sort_order) VALUES (?, ?, ?)
+         RETURNING id, name, label, sort_order, created_at",
+    )
+    .bind(&name)
+    .bind(label)
+    .bind(req.sort_order.unwrap_or(0))
+    .fetch_one(&state.pool)
+    .await
+    .map_err(|e| match e {
+        sqlx::Error::Database(ref d) if d.is_unique_violation() => {
+            (StatusCode::CONFLICT, format!("Zone {} already exists", name))
+        }
+        e => (StatusCode::INTERNAL_SERVER_ERROR, format!("Failed to create zone: {}", e)),
+    })?;
+
+    let response = ZoneResponse {
+        zone,
+        message: "Zone created".to_string(),
+    };
+
+    Ok(Json(response))
+}
+
+pub async fn update_zone(
+    State(state): State<AppState>,
+    Path(zone_id): Path<i64>,
+    Json(req): Json<UpdateZoneReq>,
+) -> Result<Json<ZoneResponse>, (StatusCode, String)> {
+    if req.label.as_deref().is_some_and(|l| l.trim().is_empty()) {
+        return Err((StatusCode::BAD_REQUEST, "Zone label cannot be empty".to_string()));
+    }
+
+    let zone = sqlx::query_as::<_, Zone>(
+        "UPDATE zones SET label = COALESCE(?, label), sort_order = COALESCE(?, sort_order)
+         WHERE id = ?
+         RETURNING id, name, label, sort_order, created_at",
+    )
+    .bind(req.label.as_deref().map(str::trim))
+    .bind(req.sort_order)
+    .bind(zone_id)
+    .fetch_optional(&state.pool)
+    .await
+    .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, format!("Failed to update zone: {}", e)))?
+    .ok_or((StatusCode::NOT_FOUND, "Zone not found".to_string()))?;
+
+    let response = ZoneResponse {
+        zone,
+        message: "Zone updated".to_string(),
+    };
+
+    Ok(Json(response))
+}
+
+pub async fn delete_zone(
+    State(state): State<AppState>,
+    Path(zone_id): Path<i64>,
+) -> Result<Json<ZoneResponse>, (StatusCode, String)> {
+    let zone = sqlx::query_as::<_, Zone>(
+        "DELETE FROM zones
+         WHERE id = ? AND NOT EXISTS (
+             SELECT 1 FROM machines WHERE machines.zone = zones.name AND decommissioned_at IS NULL
+         )
+         RETURNING id, name, label, sort_order, created_at",
+    )
+    .bind(zone_id)
+    .fetch_optional(&state.pool)
+    .await
+    .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, format!("Failed to delete zone: {}", e)))?
+    .ok_or((StatusCode::CONFLICT, "Zone not found or still has machines".to_string()))?;
+
+    let response = ZoneResponse {
+        zone,
+        message: "Zone deleted".to_string(),
+    };
+
+    Ok(Json(response))
+}
+
+/// Seat map of the floor with live machine status, time left on running
+/// sessions and upcoming bookings. Leaves out who is sitting where.
+pub async fn get_floor(
+    State(state): State<AppState>,
+    Query(query): Query<FloorQuery>,
+) -> Result<Json<FloorResponse>, (StatusCode, String)> {
+    let db_err = |e: sqlx::Error| (StatusCode::INTERNAL_SERVER_ERROR, format!("Failed to fetch floor: {}", e));
+
+    let mut conn = state.pool.acquire().await.map_err(db_err)?;
+
+    let zones: Vec<(String, String)> = sqlx::query_as(
+        "SELECT name, label FROM zones WHERE (? IS NULL OR name = ?) ORDER BY sort_order, name",
+    )
+    .bind(&query.zone)
+    .bind(&query.zone)
+    .fetch_all(&mut *conn)
+    .await
+    .map_err(db_err)?;
+
+    if query.zone.is_some() && zones.is_empty() {
+        return Err((StatusCode::NOT_FOUND, "Zone not found".to_string()));
+    }
+
+    let seats = sqlx::query_as::<_, FloorSeat>(
+        "SELECT id AS machine_id, name, class, status, zone, seat_position, pos_x, pos_y FROM machines
+         WHERE decommissioned_at IS NULL AND (? IS NULL OR zone = ?)
+         ORDER BY seat_position IS NULL, seat_position, name",
+    )
+    .bind(&query.zone)
+    .bind(&query.zone)
+    .fetch_all(&mut *conn)
+    .await
+    .map_err(db_err)?;
+
+    // playtime so far excludes pauses, including one still running
+    let sessions: Vec<(i64, i64, String, i64, bool, i64)> = sqlx::query_as(
+        "SELECT s.machine_id, s.user_id, s.started_at,
+                CAST((julianday('now') - julianday(s.started_at)) * 1440 AS INTEGER) - s.paused_minutes
+                    - COALESCE((SELECT CAST((julianday('now') - julianday(p.paused_at)) * 1440 AS INTEGER)
+                                FROM session_pauses p WHERE p.session_id = s.id AND p.resumed_at IS NULL), 0),
+                EXISTS (SELECT 1 FROM session_pauses p WHERE p.session_id = s.id AND p.resumed_at IS NULL),
+                u.minutes_balance + u.bonus_minutes
+         FROM sessions s
+         JOIN users u ON u.id = s.user_id
+         WHERE s.ended_at IS NULL",
+    )
+    .fetch_all(&mut *conn)
+    .await
+    .map_err(db_err)?;
+
+    let upcoming = sqlx::query_as::<_, CalendarSlot>(
+        "SELECT machine_id, 'reservation' AS kind, starts_at, ends_at FROM reservations
+         WHERE status IN ('booked', 'held', 'checked_in') AND starts_at < datetime('now', ?) AND ends_at > datetime('now')
+         UNION ALL
+         SELECT machine_id, 'maintenance' AS kind, starts_at, ends_at FROM maintenance_windows
+         WHERE starts_at < datetime('now', ?) AND ends_at > datetime('now')
+         ORDER BY machine_id, starts_at",
+    )
+    .bind(format!("+{} hours", floor_lookahead_hours()))
+    .bind(format!("+{} hours", floor_lookahead_hours()))
+    .fetch_all(&mut *conn)
+    .await
+    .map_err(db_err)?;
+
+    let mut slots_by_machine: HashMap<i64, Vec<CalendarSlot>> = HashMap::new();
+    for slot in upcoming {
+        slots_by_machine.entry(slot.machine_id).or_default().push(slot);
+    }
+
+    let sessions_by_machine: HashMap<i64, (i64, String, i64, bool, i64)> = sessions
+        .into_iter()
+        .map(|(machine_id, user_id, started_at, played, paused, prepaid)| (machine_id, (user_id, started_at, played, paused, prepaid)))
+        .collect();
+
+    let mut floor: Vec<FloorZone> = zones
+        .into_iter()
+        .map(|(name, label)| FloorZone { name: Some(name), label, seats: Vec::new() })
+        .collect();
+    let mut unplaced = FloorZone { name: None, label: "Unassigned".to_string(), seats: Vec::new() };
+
+    for mut seat in seats {
+        if let Some((user_id, started_at, played, paused, prepaid)) = sessions_by_machine.get(&seat.machine_id) {
+            // an estimate, member and loyalty discounts stretch it a little further
+            let allowance = subscription_service::active_subscription(&mut conn, *user_id)
+                .await?
+                .filter(|sub| sub.covers(&seat.class))
+                .map_or(0, |sub| sub.remaining_minutes());
+
+            seat.session = Some(FloorSession {
+                started_at: started_at.clone(),
+                paused: *paused,
+                remaining_minutes: (prepaid + allowance - (*played).max(0)).max(0),
+            });
+        }
+
+        seat.upcoming = slots_by_machine.remove(&seat.machine_id).unwrap_or_default();
+
+        match floor.iter_mut().find(|z| z.name == seat.zone) {
+            Some(floor_zone) => floor_zone.seats.push(seat),
+            None => unplaced.seats.push(seat),
+        }
+    }
+
+    if !unplaced.seats.is_empty() {
+        floor.push(unplaced);
+    }
+
+    let response = FloorResponse {
+        zones: floor,
+        message: "Floor layout retrieved successfully".to_string(),
+    };
+
+    Ok(Json(response))
+}
\ No newline at end of file
@@ -2,10 +2,13 @@ mod models;
 mod handlers;
 mod services;
 mod auth;
+mod middleware;
+mod scheduler;
 mod state;
 
 use axum::{
-    routing::{get, post},
+    middleware::from_fn,
+    routing::{delete, get, post, put},
     Router,
 };
 use sqlx::SqlitePool;
@@ -28,11 +31,104 @@ async fn main() {
     
     sqlx::migrate!().run(&pool).await.expect("Failed to run migrations");
     
+    scheduler::spawn(pool.clone());
+    
     let app_state = AppState {
         pool,
         jwt_secret,
     };
     
+    let me_routes = Router::new()
+        .route("/me/redeem", post(handlers::voucher_handler::redeem_voucher))
+        .route("/me/subscription", get(handlers::subscription_handler::get_my_subscription).post(handlers::subscription_handler::subscribe))
+        .route("/me/subscription/cancel", post(handlers::subscription_handler::cancel_subscription))
+        .route("/me/profile", get(handlers::profile_handler::get_profile))
+        .route("/me/receipts", get(handlers::receipt_handler::get_my_receipts))
+        .route("/me/receipts/:id", get(handlers::receipt_handler::get_my_receipt))
+        .route("/me/receipts/:id/html", get(handlers::receipt_handler::get_my_receipt_html))
+        .route("/me/receipts/:id/pdf", get(handlers::receipt_handler::get_my_receipt_pdf))
+        .route("/me/reservations", get(handlers::reservation_handler::get_my_reservations).post(handlers::reservation_handler::create_reservation))
+        .route("/me/reservations/:id/cancel", post(handlers::reservation_handler::cancel_reservation))
+        .route("/me/sessions", get(handlers::session_history_handler::get_my_sessions))
+        .route("/me/sessions/:id/pause", post(handlers::session_handler::pause_session))
+        .route("/me/sessions/:id/resume", post(handlers::session_handler::resume_session))
+        .route("/me/apps", get(handlers::app_usage_handler::get_my_app_history))
+        .route("/me/licenses", get(handlers::license_handler::get_my_licenses))
+        .route("/me/licenses/:title_id/checkout", post(handlers::license_handler::checkout_license))
+        .route("/me/licenses/:title_id/waitlist", delete(handlers::license_handler::leave_license_waitlist))
+        .route("/me/license_checkouts/:id/return", post(handlers::license_handler::return_license))
+        .route("/me/favorites", get(handlers::favorite_handler::get_my_favorites))
+        .route("/me/favorites/:machine_id", post(handlers::favorite_handler::add_favorite).delete(handlers::favorite_handler::remove_favorite))
+        .route("/me/waitlist", get(handlers::waitlist_handler::get_my_waitlist).post(handlers::waitlist_handler::join_waitlist).delete(handlers::waitlist_handler::leave_waitlist))
+        .route("/me/notifications", get(handlers::notification_handler::get_my_notifications))
+        .route("/me/notifications/:id/read", post(handlers::notification_handler::mark_notification_read))
+        .route_layer(from_fn(middleware::auth::auth_middleware));
+
+    let admin_routes = Router::new()
+        .route("/admin/vouchers", get(handlers::voucher_handler::get_vouchers).post(handlers::voucher_handler::generate_vouchers))
+        .route("/admin/vouchers/:id/redemptions", get(handlers::voucher_handler::get_voucher_redemptions))
+        .route("/admin/vouchers/:id/disable", post(handlers::voucher_handler::disable_voucher))
+        .route("/admin/subscription_plans", post(handlers::subscription_handler::create_plan))
+        .route("/admin/subscription_plans/:id", put(handlers::subscription_handler::update_plan))
+        .route("/admin/subscriptions", get(handlers::subscription_handler::get_subscriptions))
+        .route("/admin/loyalty_tiers", post(handlers::loyalty_handler::create_tier))
+        .route("/admin/loyalty_tiers/:id", put(handlers::loyalty_handler::update_tier).delete(handlers::loyalty_handler::delete_tier))
+        .route("/admin/users/:id/adjustments", post(handlers::adjustment_handler::create_adjustment))
+        .route("/admin/adjustments", get(handlers::adjustment_handler::get_adjustments))
+        .route("/admin/adjustments/:id/approve", post(handlers::adjustment_handler::approve_adjustment))
+        .route("/admin/adjustments/:id/reject", post(handlers::adjustment_handler::reject_adjustment))
+        .route("/admin/users/:id/top_up", post(handlers::receipt_handler::top_up))
+        .route("/admin/receipts", get(handlers::receipt_handler::get_receipts))
+        .route("/admin/receipts/:id/escpos", get(handlers::receipt_handler::get_receipt_escpos))
+        .route("/admin/reservations", get(handlers::reservation_handler::get_reservations))
+        .route("/admin/machines/:id/maintenance_windows", post(handlers::reservation_handler::create_maintenance_window))
+        .route("/admin/maintenance_windows", get(handlers::reservation_handler::get_maintenance_windows))
+        .route("/admin/maintenance_windows/:id", delete(handlers::reservation_handler::delete_maintenance_window))
+        .route("/admin/sessions/search", get(handlers::session_history_handler::search_sessions))
+        .route("/admin/sessions/export", get(handlers::session_history_handler::export_sessions))
+        .route("/admin/sessions/:id/force_end", post(handlers::session_handler::force_end_session))
+        .route("/admin/sessions/:id/transfer", post(handlers::session_handler::transfer_session))
+        .route("/admin/guest_tickets", get(handlers::guest_ticket_handler::get_tickets).post(handlers::guest_ticket_handler::sell_ticket))
+        .route("/admin/billing_policy", put(handlers::billing_handler::update_billing_policy))
+        .route("/admin/analytics/machines", get(handlers::analytics_handler::get_machine_analytics))
+        .route("/admin/analytics/zones", get(handlers::analytics_handler::get_zone_analytics))
+        .route("/admin/analytics/heatmap", get(handlers::analytics_handler::get_heatmap))
+        .route("/admin/power/policies", get(handlers::power_handler::get_policies))
+        .route("/admin/power/policies/:zone", put(handlers::power_handler::set_policy).delete(handlers::power_handler::delete_policy))
+        .route("/admin/power/savings", get(handlers::power_handler::get_savings))
+        .route("/admin/reports/apps", get(handlers::app_usage_handler::get_app_report))
+        .route("/admin/users/:id/apps", get(handlers::app_usage_handler::get_user_app_history))
+        .route("/admin/reports/end_reasons", get(handlers::session_history_handler::get_end_reason_report))
+        .route("/admin/machines/:id", get(handlers::inventory_handler::get_machine_inventory).put(handlers::inventory_handler::update_machine))
+        .route("/admin/machines/:id/decommission", post(handlers::inventory_handler::decommission_machine))
+        .route("/admin/machines/:id/reenroll", post(handlers::inventory_handler::reenroll_machine))
+        .route("/admin/machines/:id/peripherals", post(handlers::inventory_handler::attach_peripheral))
+        .route("/admin/peripherals/:id/detach", post(handlers::inventory_handler::detach_peripheral))
+        .route("/admin/machines/:id/tickets", post(handlers::inventory_handler::open_ticket))
+        .route("/admin/tickets", get(handlers::inventory_handler::get_tickets))
+        .route("/admin/tickets/:id/assign", post(handlers::inventory_handler::assign_ticket))
+        .route("/admin/tickets/:id/resolve", post(handlers::inventory_handler::resolve_ticket))
+        .route("/admin/zones", get(handlers::zone_handler::get_zones).post(handlers::zone_handler::create_zone))
+        .route("/admin/zones/:id", put(handlers::zone_handler::update_zone).delete(handlers::zone_handler::delete_zone))
+        .route("/admin/agent/releases", get(handlers::agent_handler::get_releases).post(handlers::agent_handler::publish_release))
+        .route("/admin/agent/releases/:id", put(handlers::agent_handler::update_release))
+        .route("/admin/agent/blocked_versions", get(handlers::agent_handler::get_blocked_versions).post(handlers::agent_handler::block_version))
+        .route("/admin/agent/blocked_versions/:version", delete(handlers::agent_handler::unblock_version))
+        .route("/admin/agent/versions", get(handlers::agent_handler::get_fleet_versions))
+        .route("/machines/:id/telemetry", get(handlers::telemetry_handler::get_machine_telemetry))
+        .route("/machines/register", post(handlers::machine_handler::register_machine))
+        .route("/admin/alerts", get(handlers::telemetry_handler::get_alerts))
+        .route("/admin/alerts/:id/acknowledge", post(handlers::telemetry_handler::acknowledge_alert))
+        .route("/admin/licenses", get(handlers::license_handler::get_titles).post(handlers::license_handler::create_title))
+        .route("/admin/licenses/:id", get(handlers::license_handler::get_title).put(handlers::license_handler::update_title))
+        .route("/admin/licenses/:id/accounts", post(handlers::license_handler::add_account))
+        .route("/admin/license_accounts/:id/disable", post(handlers::license_handler::disable_account))
+        .route("/admin/reconcile", post(handlers::reconcile_handler::reconcile))
+        .route("/admin/waitlist", get(handlers::waitlist_handler::get_waitlist))
+        .route("/admin/notifications", get(handlers::notification_handler::get_staff_notifications))
+        .route("/admin/notifications/:id/read", post(handlers::notification_handler::mark_staff_notification_read))
+        .route_layer(from_fn(middleware::auth::require_admin));
+    
     let app = Router::new()
         .route("/health", get(handlers::user_handler::health))
         .route("/register", post(handlers::auth_handler::register))
@@ -43,13 +139,23 @@ async fn main() {
         .route("/sessions/start", post(handlers::session_handler::start_session))
         .route("/sessions/end", post(handlers::session_handler::end_session))
         .route("/sessions/:id", get(handlers::session_handler::get_session))
-        .route("/machines/register", post(handlers::machine_handler::register_machine))
         .route("/machines/heartbeat", post(handlers::machine_handler::heartbeat))
+        .route("/machines/app_events", post(handlers::app_usage_handler::record_app_events))
         .route("/machines", get(handlers::machine_handler::get_machines))
+        .route("/floor", get(handlers::zone_handler::get_floor))
         .route("/users/:id/ban", post(handlers::admin_handler::ban_user))
         .route("/users/:id/unban", post(handlers::admin_handler::unban_user))
         .route("/admin/users", get(handlers::admin_handler::get_all_users))
         .route("/admin/sessions", get(handlers::admin_handler::get_active_sessions))
+        .route("/subscription_plans", get(handlers::subscription_handler::get_plans))
+        .route("/loyalty_tiers", get(handlers::loyalty_handler::get_tiers))
+        .route("/billing_policy", get(handlers::billing_handler::get_billing_policy))
+        .route("/guest/login", post(handlers::guest_ticket_handler::guest_login))
+        .route("/guest_tickets/convert", post(handlers::guest_ticket_handler::convert_ticket))
+        .route("/guest_tickets/:code", get(handlers::guest_ticket_handler::get_ticket))
+        .route("/reservations/calendar", get(handlers::reservation_handler::get_calendar))
+        .merge(me_routes)
+        .merge(admin_routes)
         .with_state(app_state);
     
     let addr = SocketAddr::from(([127, 0, 0, 1], 3001));
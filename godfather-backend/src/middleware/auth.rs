@@ -4,7 +4,7 @@ use axum::{
     response::Response,
     http::{StatusCode, HeaderMap},
 };
-use crate::auth::jwt::{verify_token, JwtClaims};
+use crate::auth::jwt::verify_token;
 
 pub async fn auth_middleware(
     headers: HeaderMap,
@@ -0,0 +1,37 @@
+use serde::{Deserialize, Serialize};
+use sqlx::FromRow;
+
+#[derive(Serialize, FromRow)]
+pub struct BalanceAdjustment {
+    pub id: i64,
+    pub user_id: i64,
+    pub session_id: Option<i64>,
+    pub direction: String,
+    pub minutes: i64,
+    pub reason: String,
+    pub status: String,
+    pub requested_by: i64,
+    pub reviewed_by: Option<i64>,
+    pub review_note: Option<String>,
+    pub created_at: String,
+    pub reviewed_at: Option<String>,
+}
+
+#[derive(Deserialize)]
+pub struct CreateAdjustmentReq {
+    pub direction: String,
+    pub minutes: i64,
+    pub reason: String,
+    pub session_id: Option<i64>,
+}
+
+#[derive(Deserialize)]
+pub struct ReviewAdjustmentReq {
+    pub note: Option<String>,
+}
+
+#[derive(Deserialize)]
+pub struct AdjustmentFilter {
+    pub status: Option<String>,
+    pub user_id: Option<i64>,
+}
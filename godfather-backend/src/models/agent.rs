@@ -0,0 +1,66 @@
+use serde::{Deserialize, Serialize};
+use sqlx::FromRow;
+
+#[derive(Serialize, FromRow)]
+pub struct AgentRelease {
+    pub id: i64,
+    pub version: String,
+    pub checksum: String,
+    pub download_path: String,
+    pub rollout_percent: i64,
+    pub zone: Option<String>,
+    pub notes: Option<String>,
+    pub status: String,
+    pub published_by: Option<i64>,
+    pub published_at: String,
+}
+
+#[derive(Serialize, FromRow)]
+pub struct BlockedAgentVersion {
+    pub version: String,
+    pub reason: Option<String>,
+    pub blocked_by: Option<i64>,
+    pub blocked_at: String,
+}
+
+/// Update advertised to an agent in the heartbeat response.
+#[derive(Serialize)]
+pub struct AgentUpdate {
+    pub version: String,
+    pub checksum: String,
+    pub download_path: String,
+    // the running version is blocked, so the update should be applied right away
+    pub required: bool,
+}
+
+#[derive(Serialize, FromRow)]
+pub struct AgentVersionCount {
+    pub version: Option<String>,
+    pub machines: i64,
+    pub blocked: bool,
+}
+
+#[derive(Deserialize)]
+pub struct PublishReleaseReq {
+    pub version: String,
+    pub checksum: String,
+    pub download_path: String,
+    pub rollout_percent: Option<i64>,
+    pub zone: Option<String>,
+    pub notes: Option<String>,
+}
+
+#[derive(Deserialize)]
+pub struct UpdateReleaseReq {
+    pub rollout_percent: Option<i64>,
+    // an empty string rolls out to every zone
+    pub zone: Option<String>,
+    // active / withdrawn
+    pub status: Option<String>,
+}
+
+#[derive(Deserialize)]
+pub struct BlockVersionReq {
+    pub version: String,
+    pub reason: Option<String>,
+}
\ No newline at end of file
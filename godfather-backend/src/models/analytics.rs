@@ -0,0 +1,44 @@
+use serde::{Deserialize, Serialize};
+
+#[derive(Deserialize)]
+pub struct AnalyticsQuery {
+    // defaults to the last 7 days
+    pub from: Option<String>,
+    pub to: Option<String>,
+    pub zone: Option<String>,
+}
+
+#[derive(Serialize)]
+pub struct MachineUtilization {
+    pub machine_id: i64,
+    pub name: String,
+    pub zone: Option<String>,
+    pub open_minutes: i64,
+    pub occupied_minutes: i64,
+    pub utilization_percent: f64,
+    pub uptime_minutes: i64,
+    pub uptime_percent: f64,
+    pub revenue: i64,
+}
+
+#[derive(Serialize)]
+pub struct ZoneUtilization {
+    pub zone: Option<String>,
+    pub machines: i64,
+    pub open_minutes: i64,
+    pub occupied_minutes: i64,
+    pub utilization_percent: f64,
+    pub uptime_minutes: i64,
+    pub uptime_percent: f64,
+    pub revenue: i64,
+}
+
+/// One weekday and hour slot. Weekdays run 0 = Monday to 6 = Sunday.
+#[derive(Serialize)]
+pub struct HeatmapCell {
+    pub weekday: u32,
+    pub hour: u32,
+    pub occupied_minutes: i64,
+    // machines in use on average during this slot
+    pub average_occupancy: f64,
+}
\ No newline at end of file
@@ -0,0 +1,39 @@
+use serde::{Deserialize, Serialize};
+use sqlx::FromRow;
+
+#[derive(Deserialize)]
+pub struct AppEvent {
+    pub app: String,
+    pub event: String, // start / stop
+}
+
+#[derive(Deserialize)]
+pub struct AppEventsReq {
+    pub machine_id: i64,
+    pub events: Vec<AppEvent>,
+}
+
+#[derive(Serialize, FromRow)]
+pub struct AppUsage {
+    pub id: i64,
+    pub session_id: i64,
+    pub machine_id: i64,
+    pub machine_name: String,
+    pub user_id: i64,
+    pub app: String,
+    pub started_at: String,
+    pub ended_at: Option<String>,
+}
+
+/// Play time for one title over a range. The peak is the most sessions
+/// running it at the same moment, which is what seat licenses are counted by.
+#[derive(Serialize)]
+pub struct AppUsageSummary {
+    pub app: String,
+    pub minutes: i64,
+    pub hours: f64,
+    pub sessions: i64,
+    pub users: i64,
+    pub peak_concurrent: i64,
+    pub peak_at: Option<String>,
+}
\ No newline at end of file
@@ -0,0 +1,19 @@
+use serde::{Deserialize, Serialize};
+use sqlx::FromRow;
+
+#[derive(Serialize, FromRow)]
+pub struct BillingPolicy {
+    pub granularity_seconds: i64,
+    pub rounding: String,
+    pub minimum_seconds: i64,
+    pub grace_seconds: i64,
+    pub updated_at: String,
+}
+
+#[derive(Deserialize)]
+pub struct UpdateBillingPolicyReq {
+    pub granularity_seconds: Option<i64>,
+    pub rounding: Option<String>,
+    pub minimum_seconds: Option<i64>,
+    pub grace_seconds: Option<i64>,
+}
\ No newline at end of file
@@ -0,0 +1,44 @@
+use serde::{Deserialize, Serialize};
+use sqlx::FromRow;
+
+#[derive(Serialize, FromRow)]
+pub struct GuestTicket {
+    pub id: i64,
+    pub code: String,
+    pub user_id: i64,
+    pub minutes_sold: i64,
+    pub minutes_remaining: i64,
+    pub price: i64,
+    pub status: String,
+    pub expires_at: String,
+    pub sold_by: Option<i64>,
+    pub created_at: String,
+    pub converted_at: Option<String>,
+}
+
+#[derive(Deserialize)]
+pub struct SellTicketReq {
+    pub minutes: i64,
+    // defaults to the minutes at the minute rate
+    pub price: Option<i64>,
+    pub valid_days: Option<i64>,
+}
+
+#[derive(Deserialize)]
+pub struct TicketFilter {
+    pub status: Option<String>,
+}
+
+#[derive(Deserialize)]
+pub struct GuestLoginReq {
+    pub code: String,
+    pub machine_id: i64,
+}
+
+#[derive(Deserialize)]
+pub struct ConvertTicketReq {
+    pub code: String,
+    pub username: String,
+    pub email: String,
+    pub password: String,
+}
\ No newline at end of file
@@ -0,0 +1,113 @@
+use serde::{Deserialize, Serialize};
+use sqlx::FromRow;
+use crate::models::machine::Machine;
+
+#[derive(Serialize, FromRow)]
+pub struct MachineSpecs {
+    pub cpu: Option<String>,
+    pub gpu: Option<String>,
+    pub ram_gb: Option<i64>,
+    pub monitor_hz: Option<i64>,
+}
+
+#[derive(Serialize, FromRow)]
+pub struct MachineEnrollment {
+    pub id: i64,
+    pub machine_id: i64,
+    pub requested_by: Option<i64>,
+    pub requested_at: String,
+    pub note: Option<String>,
+    pub enrolled_at: Option<String>,
+}
+
+#[derive(Serialize, FromRow)]
+pub struct Peripheral {
+    pub id: i64,
+    pub machine_id: i64,
+    pub kind: String,
+    pub model: Option<String>,
+    pub serial: String,
+    pub attached_at: String,
+    pub detached_at: Option<String>,
+}
+
+#[derive(Serialize, FromRow)]
+pub struct MaintenanceTicket {
+    pub id: i64,
+    pub machine_id: i64,
+    pub title: String,
+    pub description: Option<String>,
+    pub blocking: bool,
+    pub status: String,
+    pub opened_by: Option<i64>,
+    pub opened_at: String,
+    pub assigned_to: Option<i64>,
+    pub assigned_at: Option<String>,
+    pub resolved_by: Option<i64>,
+    pub resolved_at: Option<String>,
+    pub resolution: Option<String>,
+}
+
+/// Everything known about one machine, including detached peripherals,
+/// resolved tickets and past re-enrollments.
+#[derive(Serialize)]
+pub struct MachineInventory {
+    pub machine: Machine,
+    pub specs: MachineSpecs,
+    pub decommissioned_at: Option<String>,
+    pub reenroll_requested_at: Option<String>,
+    pub peripherals: Vec<Peripheral>,
+    pub tickets: Vec<MaintenanceTicket>,
+    pub enrollments: Vec<MachineEnrollment>,
+}
+
+#[derive(Deserialize)]
+pub struct UpdateMachineReq {
+    pub name: Option<String>,
+    // an empty string clears the zone
+    pub zone: Option<String>,
+    pub class: Option<String>,
+    pub seat_position: Option<i64>,
+    pub pos_x: Option<f64>,
+    pub pos_y: Option<f64>,
+    pub cpu: Option<String>,
+    pub gpu: Option<String>,
+    pub ram_gb: Option<i64>,
+    pub monitor_hz: Option<i64>,
+}
+
+#[derive(Deserialize)]
+pub struct ReenrollMachineReq {
+    pub note: Option<String>,
+}
+
+#[derive(Deserialize)]
+pub struct AttachPeripheralReq {
+    pub kind: String,
+    pub model: Option<String>,
+    pub serial: String,
+}
+
+#[derive(Deserialize)]
+pub struct OpenTicketReq {
+    pub title: String,
+    pub description: Option<String>,
+    pub blocking: Option<bool>,
+}
+
+#[derive(Deserialize)]
+pub struct AssignTicketReq {
+    pub assignee_id: i64,
+}
+
+#[derive(Deserialize)]
+pub struct ResolveTicketReq {
+    pub resolution: Option<String>,
+}
+
+#[derive(Deserialize)]
+pub struct TicketFilter {
+    pub machine_id: Option<i64>,
+    // open / assigned / resolved
+    pub status: Option<String>,
+}
\ No newline at end of file
@@ -0,0 +1,82 @@
+use serde::{Deserialize, Serialize};
+use sqlx::FromRow;
+
+#[derive(Serialize, FromRow)]
+pub struct LicenseTitle {
+    pub id: i64,
+    pub title: String,
+    pub max_concurrent: Option<i64>,
+    pub accounts: i64,
+    pub in_use: i64,
+    pub waiting: i64,
+    pub created_at: String,
+}
+
+#[derive(Deserialize)]
+pub struct CreateLicenseTitleReq {
+    pub title: String,
+    pub max_concurrent: Option<i64>,
+}
+
+#[derive(Deserialize)]
+pub struct UpdateLicenseTitleReq {
+    pub title: Option<String>,
+    pub max_concurrent: Option<i64>, // 0 removes the limit
+}
+
+/// A pooled account as staff see it. The password never leaves the database
+/// except sealed, or decrypted for the agent it is lent to.
+#[derive(Serialize, FromRow)]
+pub struct LicenseAccount {
+    pub id: i64,
+    pub title_id: i64,
+    pub label: Option<String>,
+    pub username: String,
+    pub checked_out: bool,
+    pub disabled_at: Option<String>,
+    pub created_at: String,
+}
+
+#[derive(Deserialize)]
+pub struct AddLicenseAccountReq {
+    pub username: String,
+    pub password: String,
+    pub label: Option<String>,
+}
+
+#[derive(Serialize, FromRow)]
+pub struct LicenseCheckout {
+    pub id: i64,
+    pub title_id: i64,
+    pub title: String,
+    pub account_id: i64,
+    pub session_id: i64,
+    pub user_id: i64,
+    pub machine_id: i64,
+    pub checked_out_at: String,
+    pub delivered_at: Option<String>,
+    pub returned_at: Option<String>,
+    pub return_reason: Option<String>,
+}
+
+#[derive(Serialize, FromRow)]
+pub struct LicenseWaitlistEntry {
+    pub id: i64,
+    pub title_id: i64,
+    pub title: String,
+    pub user_id: i64,
+    pub session_id: i64,
+    pub status: String,
+    pub position: i64,
+    pub created_at: String,
+    pub resolved_at: Option<String>,
+}
+
+/// Credentials handed to the machine agent so it can sign the launcher in.
+#[derive(Serialize)]
+pub struct LicenseDelivery {
+    pub checkout_id: i64,
+    pub title: String,
+    pub username: String,
+    pub password: String,
+}
\ No newline at end of file
@@ -0,0 +1,31 @@
+use serde::{Deserialize, Serialize};
+use sqlx::FromRow;
+
+#[derive(Serialize, FromRow, Clone)]
+pub struct LoyaltyTier {
+    pub id: i64,
+    pub name: String,
+    pub min_hours: i64,
+    pub rate_discount_percent: i64,
+    pub bonus_minutes: i64,
+    pub bonus_period_days: i64,
+    pub created_at: String,
+}
+
+#[derive(Deserialize)]
+pub struct CreateTierReq {
+    pub name: String,
+    pub min_hours: i64,
+    pub rate_discount_percent: Option<i64>,
+    pub bonus_minutes: Option<i64>,
+    pub bonus_period_days: Option<i64>,
+}
+
+#[derive(Deserialize)]
+pub struct UpdateTierReq {
+    pub name: Option<String>,
+    pub min_hours: Option<i64>,
+    pub rate_discount_percent: Option<i64>,
+    pub bonus_minutes: Option<i64>,
+    pub bonus_period_days: Option<i64>,
+}
@@ -7,14 +7,45 @@ pub struct Machine {
     pub id: i64,
     pub name: String,
     pub status: String,
+    pub class: String,
+    pub zone: Option<String>,
 }
 
 #[derive(Deserialize)]
 pub struct RegisterMachineReq {
     pub name: String,
+    pub class: Option<String>,
+}
+
+#[derive(Deserialize)]
+pub struct MachineFilter {
+    pub status: Option<String>,
+    pub zone: Option<String>,
+    pub include_decommissioned: Option<bool>,
 }
 
 #[derive(Deserialize)]
 pub struct HeartbeatReq {
     pub machine_id: i64,
+    pub cpu_temp_c: Option<f64>,
+    pub gpu_temp_c: Option<f64>,
+    pub cpu_load_percent: Option<f64>,
+    pub gpu_load_percent: Option<f64>,
+    pub ram_used_mb: Option<i64>,
+    pub ram_total_mb: Option<i64>,
+    pub disk_free_mb: Option<i64>,
+    pub uptime_seconds: Option<i64>,
+    pub agent_version: Option<String>,
+    pub logged_in_user: Option<String>,
+    pub mac_address: Option<String>,
+    // license checkouts the agent has signed in with since its last heartbeat
+    pub delivered_licenses: Option<Vec<i64>>,
+}
+
+#[derive(Serialize, FromRow)]
+pub struct MachineCommand {
+    pub id: i64,
+    pub machine_id: i64,
+    pub command: String,
+    pub created_at: String,
 }
\ No newline at end of file
@@ -1,4 +1,23 @@
 pub mod user;
 pub mod session;
 pub mod machine;
-pub mod balance;
\ No newline at end of file
+pub mod balance;
+pub mod voucher;
+pub mod subscription;
+pub mod loyalty;
+pub mod notification;
+pub mod adjustment;
+pub mod receipt;
+pub mod reservation;
+pub mod waitlist;
+pub mod reconciliation;
+pub mod billing;
+pub mod guest_ticket;
+pub mod telemetry;
+pub mod inventory;
+pub mod zone;
+pub mod analytics;
+pub mod agent;
+pub mod power;
+pub mod app_usage;
+pub mod license;
\ No newline at end of file
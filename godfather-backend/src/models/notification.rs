@@ -0,0 +1,13 @@
+use serde::Serialize;
+use sqlx::FromRow;
+
+#[derive(Serialize, FromRow)]
+pub struct Notification {
+    pub id: i64,
+    pub user_id: Option<i64>,
+    pub audience: String,
+    pub kind: String,
+    pub message: String,
+    pub created_at: String,
+    pub read_at: Option<String>,
+}
@@ -0,0 +1,41 @@
+use serde::{Deserialize, Serialize};
+use sqlx::FromRow;
+
+#[derive(Serialize, FromRow)]
+pub struct PowerPolicy {
+    pub zone: String,
+    pub enabled: bool,
+    pub idle_shutdown_minutes: i64,
+    pub shutdown_after: Option<String>,
+    pub wake_at: Option<String>,
+    pub idle_watts: i64,
+    pub off_watts: i64,
+    pub last_wake_on: Option<String>,
+    pub updated_by: Option<i64>,
+    pub updated_at: String,
+}
+
+#[derive(Deserialize)]
+pub struct SetPowerPolicyReq {
+    pub enabled: Option<bool>,
+    pub idle_shutdown_minutes: i64,
+    // HH:MM in UTC
+    pub shutdown_after: Option<String>,
+    pub wake_at: Option<String>,
+    pub idle_watts: Option<i64>,
+    pub off_watts: Option<i64>,
+}
+
+/// Energy saved by policy shutdowns in a zone, next to what idle machines
+/// that stayed on still used.
+#[derive(Serialize)]
+pub struct PowerSavings {
+    pub zone: Option<String>,
+    pub machines: i64,
+    pub off_minutes: i64,
+    pub saved_kwh: f64,
+    pub saved_cost: i64,
+    pub idle_on_minutes: i64,
+    pub wasted_kwh: f64,
+    pub wasted_cost: i64,
+}
\ No newline at end of file
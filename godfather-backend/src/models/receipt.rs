@@ -0,0 +1,45 @@
+use serde::{Deserialize, Serialize};
+use sqlx::FromRow;
+
+#[derive(Serialize, FromRow)]
+pub struct Receipt {
+    pub id: i64,
+    pub number: String,
+    pub user_id: Option<i64>,
+    pub kind: String,
+    pub reference_id: Option<i64>,
+    pub subtotal: i64,
+    pub tax: i64,
+    pub total: i64,
+    pub tax_rate_bp: i64,
+    pub currency: String,
+    pub settled_by: Option<String>,
+    pub created_at: String,
+}
+
+#[derive(Serialize, FromRow)]
+pub struct ReceiptLine {
+    pub id: i64,
+    pub receipt_id: i64,
+    pub description: String,
+    pub quantity: i64,
+    pub unit_price: i64,
+    pub amount: i64,
+}
+
+#[derive(Serialize)]
+pub struct ReceiptDetail {
+    pub receipt: Receipt,
+    pub lines: Vec<ReceiptLine>,
+}
+
+#[derive(Deserialize)]
+pub struct ReceiptFilter {
+    pub user_id: Option<i64>,
+    pub kind: Option<String>,
+}
+
+#[derive(Deserialize)]
+pub struct TopUpReq {
+    pub amount: i64,
+}
@@ -0,0 +1,27 @@
+use serde::Serialize;
+
+#[derive(Serialize)]
+pub struct ReconciledSession {
+    pub session_id: i64,
+    pub user_id: i64,
+    pub machine_id: i64,
+    pub ended_at: Option<String>,
+    pub minutes_consumed: i64,
+    pub charged_minutes: i64,
+    // set when the session could not be closed, e.g. it was ended elsewhere meanwhile
+    pub error: Option<String>,
+}
+
+#[derive(Serialize)]
+pub struct ReconciledMachine {
+    pub machine_id: i64,
+    pub name: String,
+    pub previous_status: String,
+    pub status: String,
+}
+
+#[derive(Serialize)]
+pub struct ReconciliationReport {
+    pub sessions: Vec<ReconciledSession>,
+    pub machines: Vec<ReconciledMachine>,
+}
\ No newline at end of file
@@ -0,0 +1,65 @@
+use serde::{Deserialize, Serialize};
+use sqlx::FromRow;
+
+#[derive(Serialize, FromRow)]
+pub struct Reservation {
+    pub id: i64,
+    pub user_id: i64,
+    pub machine_id: i64,
+    pub group_id: Option<i64>,
+    pub starts_at: String,
+    pub ends_at: String,
+    pub status: String,
+    pub created_at: String,
+    pub checked_in_at: Option<String>,
+    pub cancelled_at: Option<String>,
+}
+
+#[derive(Serialize, FromRow)]
+pub struct MaintenanceWindow {
+    pub id: i64,
+    pub machine_id: i64,
+    pub starts_at: String,
+    pub ends_at: String,
+    pub reason: Option<String>,
+    pub created_at: String,
+}
+
+/// A booked slot on the calendar, without any customer details.
+#[derive(Serialize, FromRow)]
+pub struct CalendarSlot {
+    pub machine_id: i64,
+    pub kind: String,
+    pub starts_at: String,
+    pub ends_at: String,
+}
+
+#[derive(Deserialize)]
+pub struct CreateReservationReq {
+    pub machine_ids: Option<Vec<i64>>,
+    pub machine_class: Option<String>,
+    pub count: Option<i64>,
+    pub starts_at: String,
+    pub ends_at: String,
+}
+
+#[derive(Deserialize)]
+pub struct CreateMaintenanceWindowReq {
+    pub starts_at: String,
+    pub ends_at: String,
+    pub reason: Option<String>,
+}
+
+#[derive(Deserialize)]
+pub struct CalendarQuery {
+    pub from: String,
+    pub to: String,
+    pub machine_id: Option<i64>,
+}
+
+#[derive(Deserialize)]
+pub struct ReservationFilter {
+    pub from: Option<String>,
+    pub to: Option<String>,
+    pub status: Option<String>,
+}
@@ -9,14 +9,101 @@ pub struct Session {
     pub started_at: String, 
     pub ended_at: Option<String>,
     pub minutes_consumed: i64,
+    pub included_minutes: i64,
+    pub charged_minutes: i64,
+    pub paused_minutes: i64,
+    pub end_reason: Option<String>,
 }
+
+#[derive(Serialize, FromRow)]
+pub struct SessionPause {
+    pub id: i64,
+    pub session_id: i64,
+    pub paused_at: String,
+    pub resumed_at: Option<String>,
+}
+#[derive(Serialize, FromRow)]
+pub struct SessionSegment {
+    pub id: i64,
+    pub session_id: i64,
+    pub machine_id: i64,
+    pub started_at: String,
+    pub ended_at: Option<String>,
+    pub end_reason: Option<String>,
+}
+
 #[derive(Deserialize)]
 pub struct StartSessionReq {
     pub user_id: i64,
-    pub machine_id: i64,
+    // without a machine the server picks one matching the criteria below
+    pub machine_id: Option<i64>,
+    pub zone: Option<String>,
+    pub class: Option<String>,
+    pub gpu: Option<String>,
+    pub min_ram_gb: Option<i64>,
+    pub min_monitor_hz: Option<i64>,
 }
 
 #[derive(Deserialize)]
 pub struct EndSessionReq {
     pub session_id: i64,
+    // defaults to user_logout, agents report balance_exhausted or crash
+    pub reason: Option<String>,
+}
+
+#[derive(Deserialize)]
+pub struct ForceEndSessionReq {
+    pub reason: Option<String>,
+    // bill this many minutes instead of the normal charge, 0 waives it
+    pub charged_minutes: Option<i64>,
+}
+
+#[derive(Deserialize)]
+pub struct TransferSessionReq {
+    pub machine_id: i64,
+    // open a blocking ticket on the old machine instead of handing it back to the pool
+    pub maintenance: Option<bool>,
+    // ticket description
+    pub note: Option<String>,
+}
+
+#[derive(Deserialize)]
+pub struct SessionFilter {
+    pub user_id: Option<i64>,
+    pub machine_id: Option<i64>,
+    // start time range, from inclusive and to exclusive
+    pub from: Option<String>,
+    pub to: Option<String>,
+    // active / ended
+    pub status: Option<String>,
+    pub min_minutes: Option<i64>,
+    pub max_minutes: Option<i64>,
+    pub end_reason: Option<String>,
+    pub page: Option<i64>,
+    pub per_page: Option<i64>,
+    // csv / json, export only
+    pub format: Option<String>,
+}
+
+#[derive(Serialize, FromRow)]
+pub struct SessionTotals {
+    pub sessions: i64,
+    pub minutes_consumed: i64,
+    pub included_minutes: i64,
+    pub charged_minutes: i64,
+    pub paused_minutes: i64,
+}
+
+#[derive(Deserialize)]
+pub struct ReportRange {
+    pub from: Option<String>,
+    pub to: Option<String>,
+}
+
+#[derive(Serialize, FromRow)]
+pub struct EndReasonBreakdown {
+    pub end_reason: String,
+    pub sessions: i64,
+    pub minutes_consumed: i64,
+    pub charged_minutes: i64,
 }
\ No newline at end of file
@@ -0,0 +1,82 @@
+use serde::{Deserialize, Serialize};
+use sqlx::FromRow;
+
+#[derive(Serialize, FromRow)]
+pub struct SubscriptionPlan {
+    pub id: i64,
+    pub name: String,
+    pub price: i64,
+    pub period_days: i64,
+    pub included_minutes: i64,
+    pub extra_discount_percent: i64,
+    pub machine_classes: Option<String>,
+    pub active: i64,
+    pub created_at: String,
+}
+
+#[derive(Serialize, FromRow)]
+pub struct Subscription {
+    pub id: i64,
+    pub user_id: i64,
+    pub plan_id: i64,
+    pub status: String,
+    pub auto_renew: i64,
+    pub started_at: String,
+    pub current_period_start: String,
+    pub current_period_end: String,
+    pub minutes_used: i64,
+    pub cancelled_at: Option<String>,
+    pub ended_at: Option<String>,
+}
+
+/// An active subscription joined with the plan terms billing needs.
+#[derive(FromRow)]
+pub struct ActiveSubscription {
+    pub id: i64,
+    pub included_minutes: i64,
+    pub minutes_used: i64,
+    pub extra_discount_percent: i64,
+    pub machine_classes: Option<String>,
+}
+
+impl ActiveSubscription {
+    pub fn covers(&self, machine_class: &str) -> bool {
+        match &self.machine_classes {
+            Some(classes) => classes.split(',').any(|c| c.trim() == machine_class),
+            None => true,
+        }
+    }
+
+    pub fn remaining_minutes(&self) -> i64 {
+        (self.included_minutes - self.minutes_used).max(0)
+    }
+}
+
+#[derive(Deserialize)]
+pub struct CreatePlanReq {
+    pub name: String,
+    pub price: i64,
+    pub period_days: Option<i64>,
+    pub included_minutes: i64,
+    pub extra_discount_percent: Option<i64>,
+    pub machine_classes: Option<String>,
+}
+
+#[derive(Deserialize)]
+pub struct UpdatePlanReq {
+    pub price: Option<i64>,
+    pub included_minutes: Option<i64>,
+    pub extra_discount_percent: Option<i64>,
+    pub machine_classes: Option<String>,
+    pub active: Option<bool>,
+}
+
+#[derive(Deserialize)]
+pub struct SubscribeReq {
+    pub plan_id: i64,
+}
+
+#[derive(Deserialize)]
+pub struct SubscriptionFilter {
+    pub status: Option<String>,
+}
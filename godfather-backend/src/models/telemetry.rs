@@ -0,0 +1,58 @@
+use serde::{Deserialize, Serialize};
+use sqlx::FromRow;
+
+#[derive(Serialize, FromRow)]
+pub struct TelemetrySample {
+    pub recorded_at: String,
+    pub cpu_temp_c: Option<f64>,
+    pub gpu_temp_c: Option<f64>,
+    pub cpu_load_percent: Option<f64>,
+    pub gpu_load_percent: Option<f64>,
+    pub ram_used_mb: Option<i64>,
+    pub ram_total_mb: Option<i64>,
+    pub disk_free_mb: Option<i64>,
+    pub uptime_seconds: Option<i64>,
+}
+
+#[derive(Serialize, FromRow)]
+pub struct TelemetryRollup {
+    pub hour: String,
+    pub samples: i64,
+    pub avg_cpu_temp_c: Option<f64>,
+    pub max_cpu_temp_c: Option<f64>,
+    pub avg_gpu_temp_c: Option<f64>,
+    pub max_gpu_temp_c: Option<f64>,
+    pub avg_cpu_load_percent: Option<f64>,
+    pub avg_gpu_load_percent: Option<f64>,
+    pub avg_ram_used_mb: Option<f64>,
+    pub min_disk_free_mb: Option<i64>,
+    pub max_uptime_seconds: Option<i64>,
+}
+
+#[derive(Serialize, FromRow)]
+pub struct MachineAlert {
+    pub id: i64,
+    pub machine_id: i64,
+    pub kind: String,
+    pub value: f64,
+    pub threshold: f64,
+    pub raised_at: String,
+    pub acknowledged_at: Option<String>,
+    pub acknowledged_by: Option<i64>,
+    pub resolved_at: Option<String>,
+}
+
+#[derive(Deserialize)]
+pub struct TelemetryQuery {
+    pub from: Option<String>,
+    pub to: Option<String>,
+    // raw / hourly
+    pub resolution: Option<String>,
+}
+
+#[derive(Deserialize)]
+pub struct AlertFilter {
+    pub machine_id: Option<i64>,
+    // open / resolved
+    pub status: Option<String>,
+}
\ No newline at end of file
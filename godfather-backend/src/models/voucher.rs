@@ -0,0 +1,45 @@
+use serde::{Deserialize, Serialize};
+use sqlx::FromRow;
+
+#[derive(Serialize, FromRow)]
+pub struct Voucher {
+    pub id: i64,
+    pub code: String,
+    pub kind: String,
+    pub value: i64,
+    pub max_uses: i64,
+    pub uses: i64,
+    pub campaign: Option<String>,
+    pub expires_at: Option<String>,
+    pub disabled: i64,
+    pub created_by: Option<i64>,
+    pub created_at: String,
+}
+
+#[derive(Serialize, FromRow)]
+pub struct VoucherRedemption {
+    pub id: i64,
+    pub voucher_id: i64,
+    pub user_id: i64,
+    pub redeemed_at: String,
+}
+
+#[derive(Deserialize)]
+pub struct GenerateVouchersReq {
+    pub count: i64,
+    pub kind: String,
+    pub value: i64,
+    pub max_uses: Option<i64>,
+    pub campaign: Option<String>,
+    pub expires_at: Option<String>,
+}
+
+#[derive(Deserialize)]
+pub struct VoucherFilter {
+    pub campaign: Option<String>,
+}
+
+#[derive(Deserialize)]
+pub struct RedeemReq {
+    pub code: String,
+}
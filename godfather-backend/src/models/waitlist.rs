@@ -0,0 +1,27 @@
+use serde::{Deserialize, Serialize};
+use sqlx::FromRow;
+
+#[derive(Serialize, FromRow)]
+pub struct WaitlistEntry {
+    pub id: i64,
+    pub user_id: i64,
+    pub machine_class: Option<String>,
+    pub status: String,
+    pub created_at: String,
+    pub offered_machine_id: Option<i64>,
+    pub offered_at: Option<String>,
+    pub offer_expires_at: Option<String>,
+    pub seated_at: Option<String>,
+}
+
+#[derive(Serialize)]
+pub struct WaitlistStatus {
+    pub entry: WaitlistEntry,
+    pub position: i64,
+    pub estimated_wait_minutes: Option<i64>,
+}
+
+#[derive(Deserialize)]
+pub struct JoinWaitlistReq {
+    pub machine_class: Option<String>,
+}
@@ -0,0 +1,65 @@
+use serde::{Deserialize, Serialize};
+use sqlx::FromRow;
+use crate::models::reservation::CalendarSlot;
+
+#[derive(Serialize, FromRow)]
+pub struct Zone {
+    pub id: i64,
+    pub name: String,
+    pub label: String,
+    pub sort_order: i64,
+    pub created_at: String,
+}
+
+/// What a seat on the floor map shows about a running session. Carries no
+/// customer details so the layout can go to customer apps as well.
+#[derive(Serialize)]
+pub struct FloorSession {
+    pub started_at: String,
+    pub paused: bool,
+    pub remaining_minutes: i64,
+}
+
+#[derive(Serialize, FromRow)]
+pub struct FloorSeat {
+    pub machine_id: i64,
+    pub name: String,
+    pub class: String,
+    pub status: String,
+    // already given by the enclosing FloorZone
+    #[serde(skip)]
+    pub zone: Option<String>,
+    pub seat_position: Option<i64>,
+    pub pos_x: Option<f64>,
+    pub pos_y: Option<f64>,
+    #[sqlx(skip)]
+    pub session: Option<FloorSession>,
+    #[sqlx(skip)]
+    pub upcoming: Vec<CalendarSlot>,
+}
+
+#[derive(Serialize)]
+pub struct FloorZone {
+    // None for machines not placed in any zone yet
+    pub name: Option<String>,
+    pub label: String,
+    pub seats: Vec<FloorSeat>,
+}
+
+#[derive(Deserialize)]
+pub struct CreateZoneReq {
+    pub name: String,
+    pub label: String,
+    pub sort_order: Option<i64>,
+}
+
+#[derive(Deserialize)]
+pub struct UpdateZoneReq {
+    pub label: Option<String>,
+    pub sort_order: Option<i64>,
+}
+
+#[derive(Deserialize)]
+pub struct FloorQuery {
+    pub zone: Option<String>,
+}
\ No newline at end of file
@@ -0,0 +1,68 @@
+use std::time::Duration;
+use sqlx::SqlitePool;
+use crate::services::{
+    guest_ticket_service, loyalty_service, power_service, reconcile_service, reservation_service, session_service,
+    subscription_service, telemetry_service, waitlist_service,
+};
+
+const TICK_SECONDS: u64 = 60;
+
+/// Runs periodic background jobs for the lifetime of the server.
+pub fn spawn(pool: SqlitePool) {
+    // no heartbeats arrive while the server is down, so agents get a full
+    // silence window to check back in before their sessions are judged orphaned
+    let startup_pool = pool.clone();
+    tokio::spawn(async move {
+        let grace_minutes = reconcile_service::silent_minutes().max(0) as u64;
+        tokio::time::sleep(Duration::from_secs(grace_minutes * 60)).await;
+
+        match reconcile_service::reconcile(&startup_pool).await {
+            Ok(report) => println!(
+                "Reconciled {} orphaned session(s) and {} machine(s)",
+                report.sessions.len(),
+                report.machines.len()
+            ),
+            Err(e) => eprintln!("Startup reconciliation failed: {}", e),
+        }
+    });
+
+    tokio::spawn(async move {
+        let mut interval = tokio::time::interval(Duration::from_secs(TICK_SECONDS));
+
+        loop {
+            interval.tick().await;
+
+            if let Err(e) = subscription_service::run_lifecycle(&pool).await {
+                eprintln!("Subscription lifecycle job failed: {}", e);
+            }
+
+            if let Err(e) = loyalty_service::grant_tier_bonuses(&pool).await {
+                eprintln!("Tier bonus job failed: {}", e);
+            }
+
+            if let Err(e) = reservation_service::run_holds(&pool).await {
+                eprintln!("Reservation hold job failed: {}", e);
+            }
+
+            if let Err(e) = waitlist_service::run_offers(&pool).await {
+                eprintln!("Waitlist offer job failed: {}", e);
+            }
+
+            if let Err((_, e)) = session_service::end_overlong_pauses(&pool).await {
+                eprintln!("Session pause job failed: {}", e);
+            }
+
+            if let Err(e) = guest_ticket_service::expire_tickets(&pool).await {
+                eprintln!("Guest ticket expiry job failed: {}", e);
+            }
+
+            if let Err(e) = telemetry_service::run_rollups(&pool).await {
+                eprintln!("Telemetry rollup job failed: {}", e);
+            }
+
+            if let Err(e) = power_service::run_policies(&pool).await {
+                eprintln!("Power policy job failed: {}", e);
+            }
+        }
+    });
+}
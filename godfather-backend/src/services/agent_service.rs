@@ -0,0 +1,119 @@
+use std::cmp::Ordering;
+use sqlx::SqliteConnection;
+use crate::models::agent::{AgentRelease, AgentUpdate};
+
+pub const RELEASE_COLUMNS: &str =
+    "id, version, checksum, download_path, rollout_percent, zone, notes, status, published_by, published_at";
+
+/// Orders dotted versions numerically part by part, so 1.10.0 is newer than
+/// 1.9.3. Parts that are not numbers compare as text.
+pub fn compare_versions(a: &str, b: &str) -> Ordering {
+    let mut left = a.split(['.', '-', '+']);
+    let mut right = b.split(['.', '-', '+']);
+
+    loop {
+        match (left.next(), right.next()) {
+            (None, None) => return Ordering::Equal,
+            (Some(_), None) => return Ordering::Greater,
+            (None, Some(_)) => return Ordering::Less,
+            (Some(l), Some(r)) => {
+                let ordering = match (l.parse::<u64>(), r.parse::<u64>()) {
+                    (Ok(l), Ok(r)) => l.cmp(&r),
+                    _ => l.cmp(r),
+                };
+                if ordering != Ordering::Equal {
+                    return ordering;
+                }
+            }
+        }
+    }
+}
+
+/// Stable 0-99 slot for staged rollouts; 37 is coprime with 100 so
+/// consecutive machine ids spread across the range.
+fn rollout_bucket(machine_id: i64) -> i64 {
+    (machine_id * 37).rem_euclid(100)
+}
+
+pub async fn is_blocked(conn: &mut SqliteConnection, version: &str) -> Result<bool, sqlx::Error> {
+    let blocked = sqlx::query("SELECT 1 FROM agent_blocked_versions WHERE version = ?")
+        .bind(version)
+        .fetch_optional(conn)
+        .await?;
+
+    Ok(blocked.is_some())
+}
+
+/// The newest active release the machine should move to, if any. Machines on a
+/// blocked version get one whatever the rollout percentage says.
+pub async fn pending_update(
+    conn: &mut SqliteConnection,
+    machine_id: i64,
+    zone: Option<&str>,
+    current_version: Option<&str>,
+) -> Result<Option<AgentUpdate>, sqlx::Error> {
+    let required = match current_version {
+        Some(version) => is_blocked(conn, version).await?,
+        None => false,
+    };
+
+    let releases = sqlx::query_as::<_, AgentRelease>(&format!(
+        "SELECT {} FROM agent_releases
+         WHERE status = 'active' AND version NOT IN (SELECT version FROM agent_blocked_versions)",
+        RELEASE_COLUMNS
+    ))
+    .fetch_all(conn)
+    .await?;
+
+    let newest = releases
+        .into_iter()
+        .filter(|r| r.zone.is_none() || r.zone.as_deref() == zone)
+        .filter(|r| required || rollout_bucket(machine_id) < r.rollout_percent)
+        // a blocked version may have to roll back to an older release
+        .filter(|r| current_version.is_none_or(|v| {
+            let ordering = compare_versions(&r.version, v);
+            ordering == Ordering::Greater || (required && ordering == Ordering::Less)
+        }))
+        .max_by(|a, b| compare_versions(&a.version, &b.version));
+
+    Ok(newest.map(|r| AgentUpdate {
+        version: r.version,
+        checksum: r.checksum,
+        download_path: r.download_path,
+        required,
+    }))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn compares_version_parts_numerically() {
+        assert_eq!(compare_versions("1.10.0", "1.9.3"), Ordering::Greater);
+        assert_eq!(compare_versions("2.0.0", "10.0.0"), Ordering::Less);
+        assert_eq!(compare_versions("1.2.3", "1.2.3"), Ordering::Equal);
+    }
+
+    #[test]
+    fn longer_version_is_newer_when_prefix_matches() {
+        assert_eq!(compare_versions("1.2.1", "1.2"), Ordering::Greater);
+        assert_eq!(compare_versions("1.2", "1.2.0"), Ordering::Less);
+    }
+
+    #[test]
+    fn non_numeric_parts_compare_as_text() {
+        assert_eq!(compare_versions("1.0.0-beta", "1.0.0-alpha"), Ordering::Greater);
+        assert_eq!(compare_versions("1.0.0-rc1", "1.0.0-rc1"), Ordering::Equal);
+    }
+
+    #[test]
+    fn rollout_buckets_stay_in_range_and_spread_consecutive_ids() {
+        let buckets: std::collections::HashSet<i64> = (1..=100).map(rollout_bucket).collect();
+        assert_eq!(buckets.len(), 100);
+        assert!(buckets.iter().all(|b| (0..100).contains(b)));
+        assert_eq!(rollout_bucket(1), 37);
+        assert_eq!(rollout_bucket(3), 11);
+        assert!((0..100).contains(&rollout_bucket(-7)));
+    }
+}
\ No newline at end of file
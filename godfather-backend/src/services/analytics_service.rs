@@ -0,0 +1,368 @@
+use std::collections::HashMap;
+use axum::http::StatusCode;
+use chrono::{Datelike, Duration, NaiveDateTime, NaiveTime, Timelike, Utc};
+use sqlx::SqlitePool;
+use crate::{
+    models::analytics::{AnalyticsQuery, HeatmapCell, MachineUtilization, ZoneUtilization},
+    services::receipt_service,
+};
+
+const DEFAULT_RANGE_DAYS: i64 = 7;
+// the venue is open around the clock unless told otherwise
+const DEFAULT_OPEN_HOUR: u32 = 0;
+const DEFAULT_CLOSE_HOUR: u32 = 24;
+
+const TIME_FORMAT: &str = "%Y-%m-%d %H:%M:%S";
+
+fn hour_env(name: &str, default: u32) -> u32 {
+    std::env::var(name)
+        .ok()
+        .and_then(|v| v.parse().ok())
+        .filter(|h| *h <= 24)
+        .unwrap_or(default)
+}
+
+/// Opening and closing hour from the environment. A closing hour at or before
+/// the opening hour means the venue closes the next day; equal hours would be
+/// either closed or open all day, so they are refused rather than guessed.
+fn venue_hours() -> Result<(u32, u32), (StatusCode, String)> {
+    let open = hour_env("VENUE_OPEN_HOUR", DEFAULT_OPEN_HOUR);
+    let close = hour_env("VENUE_CLOSE_HOUR", DEFAULT_CLOSE_HOUR);
+
+    if open % 24 == close % 24 {
+        return Err((
+            StatusCode::INTERNAL_SERVER_ERROR,
+            "VENUE_OPEN_HOUR and VENUE_CLOSE_HOUR must differ, use 0 and 24 for a venue open around the clock".to_string(),
+        ));
+    }
+
+    Ok((open, close))
+}
+
+fn parse_time(value: &str) -> Option<NaiveDateTime> {
+    NaiveDateTime::parse_from_str(value, TIME_FORMAT).ok()
+}
+
+pub struct AnalyticsRange {
+    pub from: NaiveDateTime,
+    pub to: NaiveDateTime,
+    open_hour: u32,
+    close_hour: u32,
+}
+
+impl AnalyticsRange {
+    pub fn from_query(query: &AnalyticsQuery) -> Result<Self, (StatusCode, String)> {
+        let parse = |value: &Option<String>| match value {
+            Some(v) => parse_time(v)
+                .map(Some)
+                .ok_or((StatusCode::BAD_REQUEST, "Times must be formatted as YYYY-MM-DD HH:MM:SS".to_string())),
+            None => Ok(None),
+        };
+
+        let to = parse(&query.to)?.unwrap_or_else(|| Utc::now().naive_utc().with_nanosecond(0).unwrap_or_default());
+        let from = parse(&query.from)?.unwrap_or(to - Duration::days(DEFAULT_RANGE_DAYS));
+
+        if to <= from {
+            return Err((StatusCode::BAD_REQUEST, "End time must be after start time".to_string()));
+        }
+
+        let (open_hour, close_hour) = venue_hours()?;
+
+        Ok(Self { from, to, open_hour, close_hour })
+    }
+
+    pub fn clip_seconds(&self, start: NaiveDateTime, end: NaiveDateTime) -> i64 {
+        let start = start.max(self.from);
+        let end = end.min(self.to);
+        if end > start { (end - start).num_seconds() } else { 0 }
+    }
+
+    /// Seconds of `start..end` that fall within the range while the venue is open.
+    fn clip_open_seconds(&self, start: NaiveDateTime, end: NaiveDateTime) -> i64 {
+        let (open, close) = (self.open_hour, self.close_hour);
+        let at = |day: chrono::NaiveDate, hour: u32| day.and_time(NaiveTime::MIN) + Duration::hours(hour as i64);
+
+        let start = start.max(self.from);
+        let end = end.min(self.to);
+
+        let mut total = 0;
+        // start a day early so a window running past midnight is counted
+        let mut day = start.date() - Duration::days(1);
+        while day <= end.date() {
+            let starts = at(day, open).max(start);
+            // closing before opening means the venue closes the next day
+            let ends = if close > open { at(day, close) } else { at(day + Duration::days(1), close) }.min(end);
+            if ends > starts {
+                total += (ends - starts).num_seconds();
+            }
+            day += Duration::days(1);
+        }
+
+        total
+    }
+
+    /// Minutes the venue is open within the range, per machine.
+    fn open_minutes(&self) -> i64 {
+        self.clip_open_seconds(self.from, self.to) / 60
+    }
+}
+
+fn percent(part: i64, whole: i64) -> f64 {
+    if whole <= 0 {
+        return 0.0;
+    }
+    (part as f64 * 1000.0 / whole as f64).round() / 10.0
+}
+
+type Segment = (i64, NaiveDateTime, NaiveDateTime);
+
+/// Session segments overlapping the range, with open ones running up to now.
+async fn load_segments(pool: &SqlitePool, range: &AnalyticsRange, zone: &Option<String>) -> Result<Vec<Segment>, sqlx::Error> {
+    let rows: Vec<(i64, String, String)> = sqlx::query_as(
+        "SELECT sg.machine_id, sg.started_at, COALESCE(sg.ended_at, datetime('now'))
+         FROM session_segments sg
+         JOIN machines m ON m.id = sg.machine_id
+         WHERE sg.started_at < ? AND COALESCE(sg.ended_at, datetime('now')) > ? AND (? IS NULL OR m.zone = ?)",
+    )
+    .bind(range.to.format(TIME_FORMAT).to_string())
+    .bind(range.from.format(TIME_FORMAT).to_string())
+    .bind(zone)
+    .bind(zone)
+    .fetch_all(pool)
+    .await?;
+
+    Ok(rows
+        .into_iter()
+        .filter_map(|(machine_id, start, end)| Some((machine_id, parse_time(&start)?, parse_time(&end)?)))
+        .collect())
+}
+
+/// Occupancy, uptime and revenue per machine over the range. Revenue is the
+/// prepaid minutes sessions ending in the range used up, valued at the minute
+/// rate and split over the machines they ran on by time spent on each. Top-up
+/// receipts are where the money came in, so session receipts are not counted.
+pub async fn machine_utilization(
+    pool: &SqlitePool,
+    range: &AnalyticsRange,
+    zone: &Option<String>,
+) -> Result<Vec<MachineUtilization>, sqlx::Error> {
+    let from = range.from.format(TIME_FORMAT).to_string();
+    let to = range.to.format(TIME_FORMAT).to_string();
+
+    // machines retired before the range started have nothing to report
+    let machines: Vec<(i64, String, Option<String>)> = sqlx::query_as(
+        "SELECT id, name, zone FROM machines
+         WHERE (decommissioned_at IS NULL OR decommissioned_at > ?) AND (? IS NULL OR zone = ?)
+         ORDER BY zone IS NULL, zone, name",
+    )
+    .bind(&from)
+    .bind(zone)
+    .bind(zone)
+    .fetch_all(pool)
+    .await?;
+
+    // play after hours does not count, so occupancy never exceeds the open time
+    let mut occupied: HashMap<i64, i64> = HashMap::new();
+    for (machine_id, start, end) in load_segments(pool, range, zone).await? {
+        *occupied.entry(machine_id).or_default() += range.clip_open_seconds(start, end);
+    }
+
+    let spans: Vec<(i64, String, String)> = sqlx::query_as(
+        "SELECT machine_id, started_at, last_seen_at FROM machine_uptime_spans WHERE started_at < ? AND last_seen_at > ?",
+    )
+    .bind(&to)
+    .bind(&from)
+    .fetch_all(pool)
+    .await?;
+
+    let mut uptime: HashMap<i64, i64> = HashMap::new();
+    for (machine_id, start, end) in spans {
+        if let (Some(start), Some(end)) = (parse_time(&start), parse_time(&end)) {
+            *uptime.entry(machine_id).or_default() += range.clip_seconds(start, end);
+        }
+    }
+
+    let billed: Vec<(i64, i64, f64, i64)> = sqlx::query_as(
+        "SELECT sg.session_id, sg.machine_id,
+                (julianday(COALESCE(sg.ended_at, s.ended_at)) - julianday(sg.started_at)) * 86400,
+                s.charged_minutes * ?
+         FROM session_segments sg
+         JOIN sessions s ON s.id = sg.session_id
+         WHERE s.ended_at >= ? AND s.ended_at < ?",
+    )
+    .bind(receipt_service::minute_rate())
+    .bind(&from)
+    .bind(&to)
+    .fetch_all(pool)
+    .await?;
+
+    let mut session_seconds: HashMap<i64, f64> = HashMap::new();
+    for (session_id, _, seconds, _) in &billed {
+        *session_seconds.entry(*session_id).or_default() += seconds.max(0.0);
+    }
+
+    let mut revenue: HashMap<i64, f64> = HashMap::new();
+    for (session_id, machine_id, seconds, total) in &billed {
+        let share = match session_seconds[session_id] {
+            s if s > 0.0 => seconds.max(0.0) / s,
+            // an instant session has one segment that takes it all
+            _ => 1.0,
+        };
+        *revenue.entry(*machine_id).or_default() += *total as f64 * share;
+    }
+
+    let open_minutes = range.open_minutes();
+
+    Ok(machines
+        .into_iter()
+        .map(|(machine_id, name, zone)| {
+            let occupied_minutes = occupied.get(&machine_id).copied().unwrap_or(0) / 60;
+            let uptime_minutes = uptime.get(&machine_id).copied().unwrap_or(0) / 60;
+            let range_minutes = (range.to - range.from).num_minutes();
+
+            MachineUtilization {
+                machine_id,
+                name,
+                zone,
+                open_minutes,
+                occupied_minutes,
+                utilization_percent: percent(occupied_minutes, open_minutes),
+                uptime_minutes,
+                uptime_percent: percent(uptime_minutes, range_minutes),
+                revenue: revenue.get(&machine_id).copied().unwrap_or(0.0).round() as i64,
+            }
+        })
+        .collect())
+}
+
+/// Rolls per-machine figures up to their zones, machines without a zone last.
+pub fn zone_utilization(machines: &[MachineUtilization], range: &AnalyticsRange) -> Vec<ZoneUtilization> {
+    let range_minutes = (range.to - range.from).num_minutes();
+    let mut zones: Vec<ZoneUtilization> = Vec::new();
+
+    for machine in machines {
+        let index = match zones.iter().position(|z| z.zone == machine.zone) {
+            Some(index) => index,
+            None => {
+                zones.push(ZoneUtilization {
+                    zone: machine.zone.clone(),
+                    machines: 0,
+                    open_minutes: 0,
+                    occupied_minutes: 0,
+                    utilization_percent: 0.0,
+                    uptime_minutes: 0,
+                    uptime_percent: 0.0,
+                    revenue: 0,
+                });
+                zones.len() - 1
+            }
+        };
+
+        let zone = &mut zones[index];
+        zone.machines += 1;
+        zone.open_minutes += machine.open_minutes;
+        zone.occupied_minutes += machine.occupied_minutes;
+        zone.uptime_minutes += machine.uptime_minutes;
+        zone.revenue += machine.revenue;
+    }
+
+    for zone in &mut zones {
+        zone.utilization_percent = percent(zone.occupied_minutes, zone.open_minutes);
+        zone.uptime_percent = percent(zone.uptime_minutes, range_minutes * zone.machines);
+    }
+
+    zones
+}
+
+/// Occupied minutes for every weekday and hour of the range, with the average
+/// number of machines in use during that slot.
+pub async fn heatmap(pool: &SqlitePool, range: &AnalyticsRange, zone: &Option<String>) -> Result<Vec<HeatmapCell>, sqlx::Error> {
+    let mut occupied = [[0i64; 24]; 7];
+
+    for (_, start, end) in load_segments(pool, range, zone).await? {
+        let mut cursor = start.max(range.from);
+        let end = end.min(range.to);
+
+        while cursor < end {
+            let next_hour = cursor.date().and_hms_opt(cursor.hour(), 0, 0).unwrap_or(cursor) + Duration::hours(1);
+            let slice_end = next_hour.min(end);
+            occupied[cursor.weekday().num_days_from_monday() as usize][cursor.hour() as usize] +=
+                (slice_end - cursor).num_seconds();
+            cursor = slice_end;
+        }
+    }
+
+    // how many minutes of each slot the range itself covers
+    let mut covered = [[0i64; 24]; 7];
+    let mut cursor = range.from;
+    while cursor < range.to {
+        let next_hour = cursor.date().and_hms_opt(cursor.hour(), 0, 0).unwrap_or(cursor) + Duration::hours(1);
+        let slice_end = next_hour.min(range.to);
+        covered[cursor.weekday().num_days_from_monday() as usize][cursor.hour() as usize] += (slice_end - cursor).num_seconds();
+        cursor = slice_end;
+    }
+
+    let mut cells = Vec::with_capacity(7 * 24);
+    for weekday in 0..7 {
+        for hour in 0..24 {
+            let seconds = occupied[weekday][hour];
+            let average_occupancy = match covered[weekday][hour] {
+                0 => 0.0,
+                c => (seconds as f64 * 100.0 / c as f64).round() / 100.0,
+            };
+
+            cells.push(HeatmapCell {
+                weekday: weekday as u32,
+                hour: hour as u32,
+                occupied_minutes: seconds / 60,
+                average_occupancy,
+            });
+        }
+    }
+
+    Ok(cells)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn time(value: &str) -> NaiveDateTime {
+        parse_time(value).unwrap()
+    }
+
+    fn range(from: &str, to: &str, open_hour: u32, close_hour: u32) -> AnalyticsRange {
+        AnalyticsRange { from: time(from), to: time(to), open_hour, close_hour }
+    }
+
+    #[test]
+    fn overnight_hours_span_midnight() {
+        let range = range("2025-11-03 00:00:00", "2025-11-04 00:00:00", 18, 4);
+        // 00:00-04:00 from the night before plus 18:00-24:00
+        assert_eq!(range.open_minutes(), 10 * 60);
+        assert_eq!(
+            range.clip_open_seconds(time("2025-11-03 23:00:00"), time("2025-11-04 02:00:00")),
+            3600,
+        );
+    }
+
+    #[test]
+    fn play_after_closing_is_not_counted() {
+        let range = range("2025-11-03 00:00:00", "2025-11-05 00:00:00", 18, 4);
+        assert_eq!(range.clip_open_seconds(time("2025-11-04 03:00:00"), time("2025-11-04 06:00:00")), 3600);
+        assert_eq!(range.clip_open_seconds(time("2025-11-04 04:30:00"), time("2025-11-04 17:00:00")), 0);
+    }
+
+    #[test]
+    fn daytime_hours_stay_within_the_day() {
+        let range = range("2025-11-03 00:00:00", "2025-11-04 00:00:00", 10, 22);
+        assert_eq!(range.open_minutes(), 12 * 60);
+        assert_eq!(range.clip_open_seconds(time("2025-11-03 21:00:00"), time("2025-11-03 23:30:00")), 3600);
+    }
+
+    #[test]
+    fn default_hours_are_open_around_the_clock() {
+        let range = range("2025-11-03 00:00:00", "2025-11-04 00:00:00", DEFAULT_OPEN_HOUR, DEFAULT_CLOSE_HOUR);
+        assert_eq!(range.open_minutes(), 24 * 60);
+    }
+}
\ No newline at end of file
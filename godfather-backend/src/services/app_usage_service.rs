@@ -0,0 +1,129 @@
+use std::collections::{HashMap, HashSet};
+use chrono::NaiveDateTime;
+use sqlx::{SqliteConnection, SqlitePool};
+use crate::{
+    models::app_usage::{AppUsage, AppUsageSummary},
+    services::analytics_service::AnalyticsRange,
+};
+
+const TIME_FORMAT: &str = "%Y-%m-%d %H:%M:%S";
+
+pub const USAGE_COLUMNS: &str =
+    "u.id, u.session_id, u.machine_id, m.name AS machine_name, u.user_id, u.app, u.started_at, u.ended_at";
+
+/// Apps still running when their session leaves the machine stop with it.
+pub async fn close_running(conn: &mut SqliteConnection, session_id: i64, ended_at: &str) -> Result<(), sqlx::Error> {
+    sqlx::query("UPDATE app_usage SET ended_at = MAX(started_at, ?) WHERE session_id = ? AND ended_at IS NULL")
+        .bind(ended_at)
+        .bind(session_id)
+        .execute(&mut *conn)
+        .await?;
+
+    Ok(())
+}
+
+pub async fn running(conn: &mut SqliteConnection, session_id: i64) -> Result<Vec<AppUsage>, sqlx::Error> {
+    sqlx::query_as::<_, AppUsage>(&format!(
+        "SELECT {} FROM app_usage u JOIN machines m ON m.id = u.machine_id
+         WHERE u.session_id = ? AND u.ended_at IS NULL
+         ORDER BY u.started_at",
+        USAGE_COLUMNS
+    ))
+    .bind(session_id)
+    .fetch_all(&mut *conn)
+    .await
+}
+
+/// A user's play history over the range, newest first.
+pub async fn history(pool: &SqlitePool, user_id: i64, range: &AnalyticsRange) -> Result<Vec<AppUsage>, sqlx::Error> {
+    sqlx::query_as::<_, AppUsage>(&format!(
+        "SELECT {} FROM app_usage u JOIN machines m ON m.id = u.machine_id
+         WHERE u.user_id = ? AND u.started_at < ? AND COALESCE(u.ended_at, datetime('now')) > ?
+         ORDER BY u.started_at DESC",
+        USAGE_COLUMNS
+    ))
+    .bind(user_id)
+    .bind(range.to.format(TIME_FORMAT).to_string())
+    .bind(range.from.format(TIME_FORMAT).to_string())
+    .fetch_all(pool)
+    .await
+}
+
+#[derive(Default)]
+struct Tally {
+    seconds: i64,
+    sessions: HashSet<i64>,
+    users: HashSet<i64>,
+    // +1 when a run starts, -1 when it stops
+    changes: Vec<(NaiveDateTime, i64)>,
+}
+
+/// Titles ranked by time played within the range.
+pub async fn top_apps(pool: &SqlitePool, range: &AnalyticsRange, zone: &Option<String>) -> Result<Vec<AppUsageSummary>, sqlx::Error> {
+    let rows: Vec<(String, i64, i64, String, String)> = sqlx::query_as(
+        "SELECT u.app, u.session_id, u.user_id, u.started_at, COALESCE(u.ended_at, datetime('now'))
+         FROM app_usage u
+         JOIN machines m ON m.id = u.machine_id
+         WHERE u.started_at < ? AND COALESCE(u.ended_at, datetime('now')) > ? AND (? IS NULL OR m.zone = ?)",
+    )
+    .bind(range.to.format(TIME_FORMAT).to_string())
+    .bind(range.from.format(TIME_FORMAT).to_string())
+    .bind(zone)
+    .bind(zone)
+    .fetch_all(pool)
+    .await?;
+
+    let parse = |v: &str| NaiveDateTime::parse_from_str(v, TIME_FORMAT).ok();
+
+    let mut tallies: HashMap<String, Tally> = HashMap::new();
+    for (app, session_id, user_id, start, end) in rows {
+        let (Some(start), Some(end)) = (parse(&start), parse(&end)) else {
+            continue;
+        };
+
+        let seconds = range.clip_seconds(start, end);
+        if seconds <= 0 {
+            continue;
+        }
+
+        let tally = tallies.entry(app).or_default();
+        tally.seconds += seconds;
+        tally.sessions.insert(session_id);
+        tally.users.insert(user_id);
+        tally.changes.push((start.max(range.from), 1));
+        tally.changes.push((end.min(range.to), -1));
+    }
+
+    let mut summaries: Vec<AppUsageSummary> = tallies
+        .into_iter()
+        .map(|(app, mut tally)| {
+            // a run stopping at the moment another starts does not overlap it
+            tally.changes.sort();
+
+            let mut running = 0;
+            let mut peak = 0;
+            let mut peak_at = None;
+            for (at, change) in tally.changes {
+                running += change;
+                if running > peak {
+                    peak = running;
+                    peak_at = Some(at.format(TIME_FORMAT).to_string());
+                }
+            }
+
+            AppUsageSummary {
+                app,
+                minutes: tally.seconds / 60,
+                hours: (tally.seconds as f64 / 36.0).round() / 100.0,
+                sessions: tally.sessions.len() as i64,
+                users: tally.users.len() as i64,
+                peak_concurrent: peak,
+                peak_at,
+            }
+        })
+        .collect();
+
+    summaries.sort_by(|a, b| b.minutes.cmp(&a.minutes).then_with(|| a.app.cmp(&b.app)));
+
+    Ok(summaries)
+}
\ No newline at end of file
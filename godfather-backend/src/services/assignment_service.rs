@@ -0,0 +1,90 @@
+use axum::http::StatusCode;
+use sqlx::SqlitePool;
+use crate::{
+    models::{machine::Machine, session::{Session, StartSessionReq}},
+    services::{reservation_service, session_service},
+};
+
+// candidates tried before giving up when other starts keep winning the race
+const MAX_ATTEMPTS: usize = 5;
+
+/// Picks the best free machine matching the request and opens the session on
+/// it. A machine held for the user comes first, then their favorites, then the
+/// least worn machine by time in use. A candidate taken in the meantime is
+/// skipped in favor of the next one.
+pub async fn assign_and_open(pool: &SqlitePool, req: &StartSessionReq) -> Result<(Session, Machine), (StatusCode, String)> {
+    let db_err = |e: sqlx::Error| (StatusCode::INTERNAL_SERVER_ERROR, format!("Failed to assign machine: {}", e));
+
+    let candidates: Vec<(i64, bool)> = sqlx::query_as(
+        "SELECT m.id,
+                EXISTS (SELECT 1 FROM reservations r WHERE r.machine_id = m.id AND r.user_id = ? AND r.status = 'held')
+                    OR EXISTS (SELECT 1 FROM waitlist_entries w WHERE w.offered_machine_id = m.id AND w.user_id = ? AND w.status = 'offered')
+                    AS held_for_user
+         FROM machines m
+         WHERE m.decommissioned_at IS NULL
+           -- machines held for other customers would only use up attempts
+           AND (m.status = 'available' OR (m.status = 'reserved' AND (
+               EXISTS (SELECT 1 FROM reservations r WHERE r.machine_id = m.id AND r.user_id = ? AND r.status = 'held')
+                   OR EXISTS (SELECT 1 FROM waitlist_entries w WHERE w.offered_machine_id = m.id AND w.user_id = ? AND w.status = 'offered')
+           )))
+           AND (? IS NULL OR m.zone = ?)
+           AND (? IS NULL OR m.class = ?)
+           AND (? IS NULL OR m.gpu LIKE '%' || ? || '%')
+           AND (? IS NULL OR m.ram_gb >= ?)
+           AND (? IS NULL OR m.monitor_hz >= ?)
+           AND NOT EXISTS (SELECT 1 FROM agent_blocked_versions b WHERE b.version = m.agent_version)
+           AND NOT EXISTS (
+               SELECT 1 FROM maintenance_windows mw
+               WHERE mw.machine_id = m.id AND mw.starts_at <= datetime('now') AND mw.ends_at > datetime('now')
+           )
+         ORDER BY held_for_user DESC,
+                  m.power_state = 'on' DESC,
+                  EXISTS (SELECT 1 FROM machine_favorites f WHERE f.machine_id = m.id AND f.user_id = ?) DESC,
+                  COALESCE((SELECT SUM(julianday(COALESCE(sg.ended_at, datetime('now'))) - julianday(sg.started_at))
+                            FROM session_segments sg WHERE sg.machine_id = m.id), 0),
+                  m.name",
+    )
+    .bind(req.user_id)
+    .bind(req.user_id)
+    .bind(req.user_id)
+    .bind(req.user_id)
+    .bind(&req.zone)
+    .bind(&req.zone)
+    .bind(&req.class)
+    .bind(&req.class)
+    .bind(&req.gpu)
+    .bind(&req.gpu)
+    .bind(req.min_ram_gb)
+    .bind(req.min_ram_gb)
+    .bind(req.min_monitor_hz)
+    .bind(req.min_monitor_hz)
+    .bind(req.user_id)
+    .fetch_all(pool)
+    .await
+    .map_err(db_err)?;
+
+    let mut conn = pool.acquire().await.map_err(db_err)?;
+    let mut last_err = (StatusCode::NOT_FOUND, "No free machine matches the request".to_string());
+    let mut attempts = 0;
+
+    for (machine_id, held_for_user) in candidates {
+        if attempts == MAX_ATTEMPTS {
+            break;
+        }
+
+        // reserved for someone else, or about to be
+        if !held_for_user && reservation_service::has_upcoming_booking(&mut conn, machine_id).await.map_err(db_err)? {
+            continue;
+        }
+
+        attempts += 1;
+
+        match session_service::open_session(pool, req.user_id, machine_id).await {
+            Ok(opened) => return Ok(opened),
+            Err(e) if matches!(e.0, StatusCode::CONFLICT | StatusCode::NOT_FOUND | StatusCode::FORBIDDEN) => last_err = e,
+            Err(e) => return Err(e),
+        }
+    }
+
+    Err(last_err)
+}
\ No newline at end of file
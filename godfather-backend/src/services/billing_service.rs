@@ -0,0 +1,109 @@
+use sqlx::SqliteConnection;
+use crate::{models::billing::BillingPolicy, services::receipt_service};
+
+pub async fn load_policy(conn: &mut SqliteConnection) -> Result<BillingPolicy, sqlx::Error> {
+    sqlx::query_as::<_, BillingPolicy>(
+        "SELECT granularity_seconds, rounding, minimum_seconds, grace_seconds, updated_at FROM billing_policy WHERE id = 1",
+    )
+    .fetch_one(conn)
+    .await
+}
+
+/// Billed time for a stretch of play. Sessions that end within the grace
+/// window are free, otherwise the whole duration is rounded to the billing
+/// granularity and raised to the minimum charge.
+pub fn billable_seconds(policy: &BillingPolicy, played_seconds: i64) -> i64 {
+    if played_seconds <= policy.grace_seconds {
+        return 0;
+    }
+
+    let granularity = policy.granularity_seconds.max(1);
+    let blocks = if policy.rounding == "up" {
+        (played_seconds + granularity - 1) / granularity
+    } else {
+        played_seconds / granularity
+    };
+
+    (blocks * granularity).max(policy.minimum_seconds)
+}
+
+/// Converts billed seconds to whole minutes, carrying any partial minute over
+/// to the user's next session. Returns the minutes and the new carry.
+pub fn billed_minutes(billed_seconds: i64, carry_seconds: i64) -> (i64, i64) {
+    let total = billed_seconds + carry_seconds;
+    (total / 60, total % 60)
+}
+
+/// Pays off minutes the user owes for a session they could not cover out of
+/// their money balance, at the minute rate. Returns the minutes settled.
+pub async fn settle_minutes_debt(conn: &mut SqliteConnection, user_id: i64) -> Result<i64, sqlx::Error> {
+    let (balance, minutes_balance): (i64, i64) = sqlx::query_as("SELECT balance, minutes_balance FROM users WHERE id = ?")
+        .bind(user_id)
+        .fetch_one(&mut *conn)
+        .await?;
+
+    let rate = receipt_service::minute_rate().max(1);
+    let settled = (-minutes_balance).min(balance / rate).max(0);
+
+    if settled > 0 {
+        sqlx::query("UPDATE users SET balance = balance - ?, minutes_balance = minutes_balance + ? WHERE id = ?")
+            .bind(settled * rate)
+            .bind(settled)
+            .bind(user_id)
+            .execute(&mut *conn)
+            .await?;
+    }
+
+    Ok(settled)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn policy(granularity_seconds: i64, rounding: &str, minimum_seconds: i64, grace_seconds: i64) -> BillingPolicy {
+        BillingPolicy {
+            granularity_seconds,
+            rounding: rounding.to_string(),
+            minimum_seconds,
+            grace_seconds,
+            updated_at: String::new(),
+        }
+    }
+
+    #[test]
+    fn grace_window_is_free_up_to_and_including_its_end() {
+        let policy = policy(60, "up", 0, 120);
+        assert_eq!(billable_seconds(&policy, 0), 0);
+        assert_eq!(billable_seconds(&policy, 120), 0);
+        assert_eq!(billable_seconds(&policy, 121), 180);
+    }
+
+    #[test]
+    fn rounds_to_granularity_up_or_down() {
+        assert_eq!(billable_seconds(&policy(300, "up", 0, 0), 301), 600);
+        assert_eq!(billable_seconds(&policy(300, "up", 0, 0), 600), 600);
+        assert_eq!(billable_seconds(&policy(300, "down", 0, 0), 599), 300);
+        assert_eq!(billable_seconds(&policy(300, "down", 0, 0), 600), 600);
+    }
+
+    #[test]
+    fn raises_short_sessions_to_the_minimum() {
+        let policy = policy(60, "up", 900, 60);
+        assert_eq!(billable_seconds(&policy, 61), 900);
+        assert_eq!(billable_seconds(&policy, 1000), 1020);
+    }
+
+    #[test]
+    fn zero_granularity_bills_by_the_second() {
+        assert_eq!(billable_seconds(&policy(0, "up", 0, 0), 61), 61);
+    }
+
+    #[test]
+    fn partial_minutes_carry_to_the_next_session() {
+        assert_eq!(billed_minutes(150, 0), (2, 30));
+        assert_eq!(billed_minutes(150, 30), (3, 0));
+        assert_eq!(billed_minutes(45, 50), (1, 35));
+        assert_eq!(billed_minutes(0, 59), (0, 59));
+    }
+}
\ No newline at end of file
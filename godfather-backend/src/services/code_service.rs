@@ -0,0 +1,27 @@
+use rand::Rng;
+
+// unambiguous characters only (no 0/O, 1/I/L) so printed codes are easy to type
+const CODE_ALPHABET: &[u8] = b"ABCDEFGHJKMNPQRSTUVWXYZ23456789";
+const CODE_GROUPS: usize = 3;
+const CODE_GROUP_LEN: usize = 4;
+
+/// A printable code for vouchers and guest tickets, e.g. `K7QM-3XPA-RT9H`.
+pub fn generate_code() -> String {
+    let mut rng = rand::thread_rng();
+    (0..CODE_GROUPS)
+        .map(|_| {
+            (0..CODE_GROUP_LEN)
+                .map(|_| CODE_ALPHABET[rng.gen_range(0..CODE_ALPHABET.len())] as char)
+                .collect::<String>()
+        })
+        .collect::<Vec<_>>()
+        .join("-")
+}
+
+/// Uppercases a typed code and drops stray spaces and punctuation.
+pub fn normalize_code(code: &str) -> String {
+    code.chars()
+        .filter(|c| c.is_ascii_alphanumeric() || *c == '-')
+        .collect::<String>()
+        .to_ascii_uppercase()
+}
\ No newline at end of file
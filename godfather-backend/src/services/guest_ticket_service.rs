@@ -0,0 +1,49 @@
+use sqlx::{SqliteConnection, SqlitePool};
+use crate::models::guest_ticket::GuestTicket;
+
+// how long an unused ticket stays valid
+const DEFAULT_VALID_DAYS: i64 = 30;
+
+pub fn valid_days() -> i64 {
+    std::env::var("GUEST_TICKET_VALID_DAYS")
+        .ok()
+        .and_then(|v| v.parse().ok())
+        .unwrap_or(DEFAULT_VALID_DAYS)
+}
+
+pub async fn find_by_code(conn: &mut SqliteConnection, code: &str) -> Result<Option<GuestTicket>, sqlx::Error> {
+    sqlx::query_as::<_, GuestTicket>(
+        "SELECT t.id, t.code, t.user_id, t.minutes_sold, u.minutes_balance AS minutes_remaining, t.price, t.status,
+                t.expires_at, t.sold_by, t.created_at, t.converted_at
+         FROM guest_tickets t
+         JOIN users u ON u.id = t.user_id
+         WHERE t.code = ?",
+    )
+    .bind(code)
+    .fetch_optional(conn)
+    .await
+}
+
+/// Expires tickets past their validity and forfeits the unused minutes.
+/// Tickets with a session still running are left until it ends. Run by the scheduler.
+pub async fn expire_tickets(pool: &SqlitePool) -> Result<(), sqlx::Error> {
+    let mut tx = pool.begin().await?;
+
+    let expired: Vec<i64> = sqlx::query_scalar(
+        "UPDATE guest_tickets SET status = 'expired'
+         WHERE status = 'active' AND expires_at <= datetime('now')
+           AND NOT EXISTS (SELECT 1 FROM sessions s WHERE s.user_id = guest_tickets.user_id AND s.ended_at IS NULL)
+         RETURNING user_id",
+    )
+    .fetch_all(&mut *tx)
+    .await?;
+
+    for user_id in expired {
+        sqlx::query("UPDATE users SET minutes_balance = 0, bonus_minutes = 0 WHERE id = ?")
+            .bind(user_id)
+            .execute(&mut *tx)
+            .await?;
+    }
+
+    tx.commit().await
+}
\ No newline at end of file
@@ -0,0 +1,480 @@
+use base64::{Engine, engine::general_purpose::STANDARD};
+use ring::{
+    aead::{AES_256_GCM, Aad, LessSafeKey, NONCE_LEN, Nonce, UnboundKey},
+    rand::{SecureRandom, SystemRandom},
+};
+use sqlx::SqliteConnection;
+use crate::{
+    models::license::{LicenseCheckout, LicenseDelivery},
+    services::{machine_service, notification_service},
+};
+
+pub const TITLE_COLUMNS: &str = "t.id, t.title, t.max_concurrent,
+    (SELECT COUNT(*) FROM license_accounts a WHERE a.title_id = t.id AND a.disabled_at IS NULL) AS accounts,
+    (SELECT COUNT(*) FROM license_checkouts c WHERE c.title_id = t.id AND c.returned_at IS NULL) AS in_use,
+    (SELECT COUNT(*) FROM license_waitlist w WHERE w.title_id = t.id AND w.status = 'waiting') AS waiting,
+    t.created_at";
+
+pub const ACCOUNT_COLUMNS: &str = "a.id, a.title_id, a.label, a.username,
+    EXISTS (SELECT 1 FROM license_checkouts c WHERE c.account_id = a.id AND c.returned_at IS NULL) AS checked_out,
+    a.disabled_at, a.created_at";
+
+pub const CHECKOUT_COLUMNS: &str = "c.id, c.title_id, t.title, c.account_id, c.session_id, c.user_id, c.machine_id,
+    c.checked_out_at, c.delivered_at, c.returned_at, c.return_reason";
+
+pub const WAITLIST_COLUMNS: &str = "w.id, w.title_id, t.title, w.user_id, w.session_id, w.status,
+    (SELECT COUNT(*) FROM license_waitlist o WHERE o.title_id = w.title_id AND o.status = 'waiting' AND o.id <= w.id) AS position,
+    w.created_at, w.resolved_at";
+
+/// The AES-256-GCM key account passwords are sealed with, from LICENSE_KEY
+/// (32 bytes, base64 encoded).
+fn sealing_key() -> Option<LessSafeKey> {
+    let encoded = std::env::var("LICENSE_KEY").ok()?;
+    let bytes = STANDARD.decode(encoded.trim()).ok()?;
+    let key = UnboundKey::new(&AES_256_GCM, &bytes).ok()?;
+    Some(LessSafeKey::new(key))
+}
+
+/// Seals a password for storage. None when no usable key is configured.
+pub fn seal(plain: &str) -> Option<String> {
+    let key = sealing_key()?;
+
+    let mut nonce = [0u8; NONCE_LEN];
+    SystemRandom::new().fill(&mut nonce).ok()?;
+
+    let mut in_out = plain.as_bytes().to_vec();
+    key.seal_in_place_append_tag(Nonce::assume_unique_for_key(nonce), Aad::empty(), &mut in_out)
+        .ok()?;
+
+    let mut sealed = nonce.to_vec();
+    sealed.extend(in_out);
+    Some(STANDARD.encode(sealed))
+}
+
+fn open(sealed: &str) -> Option<String> {
+    let key = sealing_key()?;
+    let bytes = STANDARD.decode(sealed).ok()?;
+    if bytes.len() < NONCE_LEN {
+        return None;
+    }
+
+    let (nonce, ciphertext) = bytes.split_at(NONCE_LEN);
+    let nonce = Nonce::try_assume_unique_for_key(nonce).ok()?;
+    let mut in_out = ciphertext.to_vec();
+    let plain = key.open_in_place(nonce, Aad::empty(), &mut in_out).ok()?;
+
+    String::from_utf8(plain.to_vec()).ok()
+}
+
+pub async fn load_checkout(conn: &mut SqliteConnection, checkout_id: i64) -> Result<Option<LicenseCheckout>, sqlx::Error> {
+    sqlx::query_as::<_, LicenseCheckout>(&format!(
+        "SELECT {} FROM license_checkouts c JOIN license_titles t ON t.id = c.title_id WHERE c.id = ?",
+        CHECKOUT_COLUMNS
+    ))
+    .bind(checkout_id)
+    .fetch_optional(&mut *conn)
+    .await
+}
+
+/// Lends the session a free account for the title, picking the one that has
+/// rested longest. None when the pool or the title's limit is exhausted.
+pub async fn try_checkout(
+    conn: &mut SqliteConnection,
+    title_id: i64,
+    session_id: i64,
+    user_id: i64,
+    machine_id: i64,
+) -> Result<Option<i64>, sqlx::Error> {
+    let (max_concurrent, in_use): (Option<i64>, i64) = sqlx::query_as(
+        "SELECT max_concurrent, (SELECT COUNT(*) FROM license_checkouts WHERE title_id = ? AND returned_at IS NULL)
+         FROM license_titles WHERE id = ?",
+    )
+    .bind(title_id)
+    .bind(title_id)
+    .fetch_one(&mut *conn)
+    .await?;
+
+    if max_concurrent.is_some_and(|max| in_use >= max) {
+        return Ok(None);
+    }
+
+    let account_id: Option<i64> = sqlx::query_scalar(
+        "SELECT a.id FROM license_accounts a
+         WHERE a.title_id = ? AND a.disabled_at IS NULL
+           AND NOT EXISTS (SELECT 1 FROM license_checkouts c WHERE c.account_id = a.id AND c.returned_at IS NULL)
+         ORDER BY (SELECT MAX(c.checked_out_at) FROM license_checkouts c WHERE c.account_id = a.id), a.id
+         LIMIT 1",
+    )
+    .bind(title_id)
+    .fetch_optional(&mut *conn)
+    .await?;
+
+    let Some(account_id) = account_id else {
+        return Ok(None);
+    };
+
+    let checkout_id: i64 = sqlx::query_scalar(
+        "INSERT INTO license_checkouts (title_id, account_id, session_id, user_id, machine_id)
+         VALUES (?, ?, ?, ?, ?)
+         RETURNING id",
+    )
+    .bind(title_id)
+    .bind(account_id)
+    .bind(session_id)
+    .bind(user_id)
+    .bind(machine_id)
+    .fetch_one(&mut *conn)
+    .await?;
+
+    Ok(Some(checkout_id))
+}
+
+/// Hands freed accounts to waiting sessions in the order they asked.
+/// Entries whose session has ended meanwhile are dropped.
+pub async fn fulfill_waitlist(conn: &mut SqliteConnection, title_id: i64) -> Result<(), sqlx::Error> {
+    loop {
+        let next: Option<(i64, i64, i64, i64, bool, String)> = sqlx::query_as(
+            "SELECT w.id, w.session_id, w.user_id, s.machine_id, s.ended_at IS NULL, t.title
+             FROM license_waitlist w
+             JOIN sessions s ON s.id = w.session_id
+             JOIN license_titles t ON t.id = w.title_id
+             WHERE w.title_id = ? AND w.status = 'waiting'
+             ORDER BY w.id
+             LIMIT 1",
+        )
+        .bind(title_id)
+        .fetch_optional(&mut *conn)
+        .await?;
+
+        let Some((entry_id, session_id, user_id, machine_id, session_open, title)) = next else {
+            return Ok(());
+        };
+
+        if !session_open {
+            resolve_entry(conn, entry_id, "cancelled").await?;
+            continue;
+        }
+
+        if try_checkout(conn, title_id, session_id, user_id, machine_id).await?.is_none() {
+            return Ok(());
+        }
+
+        resolve_entry(conn, entry_id, "fulfilled").await?;
+
+        notification_service::notify_user(
+            conn,
+            user_id,
+            "license_ready",
+            &format!("A {} account is ready and being signed in on your machine", title),
+        )
+        .await?;
+    }
+}
+
+async fn resolve_entry(conn: &mut SqliteConnection, entry_id: i64, status: &str) -> Result<(), sqlx::Error> {
+    sqlx::query("UPDATE license_waitlist SET status = ?, resolved_at = datetime('now') WHERE id = ?")
+        .bind(status)
+        .bind(entry_id)
+        .execute(&mut *conn)
+        .await?;
+
+    Ok(())
+}
+
+/// Returns a checkout to the pool and tells the agent to sign the account
+/// out. The caller passes the freed title on to the waitlist.
+pub async fn return_checkout(conn: &mut SqliteConnection, checkout_id: i64, reason: &str) -> Result<Option<i64>, sqlx::Error> {
+    let returned: Option<(i64, i64)> = sqlx::query_as(
+        "UPDATE license_checkouts SET returned_at = datetime('now'), return_reason = ?
+         WHERE id = ? AND returned_at IS NULL
+         RETURNING machine_id, title_id",
+    )
+    .bind(reason)
+    .bind(checkout_id)
+    .fetch_optional(&mut *conn)
+    .await?;
+
+    let Some((machine_id, title_id)) = returned else {
+        return Ok(None);
+    };
+
+    machine_service::queue_command(conn, machine_id, &format!("license_logout:{}", checkout_id)).await?;
+
+    Ok(Some(title_id))
+}
+
+/// Gives back everything the session borrowed and drops its place in any
+/// license queue. Called when the session ends.
+pub async fn return_for_session(conn: &mut SqliteConnection, session_id: i64) -> Result<(), sqlx::Error> {
+    sqlx::query("UPDATE license_waitlist SET status = 'cancelled', resolved_at = datetime('now') WHERE session_id = ? AND status = 'waiting'")
+        .bind(session_id)
+        .execute(&mut *conn)
+        .await?;
+
+    let checkouts: Vec<i64> = sqlx::query_scalar("SELECT id FROM license_checkouts WHERE session_id = ? AND returned_at IS NULL")
+        .bind(session_id)
+        .fetch_all(&mut *conn)
+        .await?;
+
+    for checkout_id in checkouts {
+        if let Some(title_id) = return_checkout(conn, checkout_id, "session_end").await? {
+            fulfill_waitlist(conn, title_id).await?;
+        }
+    }
+
+    Ok(())
+}
+
+/// Follows a transferred session to its new machine: the old agent signs out
+/// and the new one is handed the credentials on its next heartbeat.
+pub async fn move_to_machine(conn: &mut SqliteConnection, session_id: i64, machine_id: i64) -> Result<(), sqlx::Error> {
+    let moved: Vec<(i64, i64)> = sqlx::query_as(
+        "SELECT id, machine_id FROM license_checkouts WHERE session_id = ? AND returned_at IS NULL AND machine_id != ?",
+    )
+    .bind(session_id)
+    .bind(machine_id)
+    .fetch_all(&mut *conn)
+    .await?;
+
+    for (checkout_id, previous_machine_id) in moved {
+        machine_service::queue_command(conn, previous_machine_id, &format!("license_logout:{}", checkout_id)).await?;
+
+        sqlx::query("UPDATE license_checkouts SET machine_id = ?, offered_at = NULL, delivered_at = NULL WHERE id = ?")
+            .bind(machine_id)
+            .bind(checkout_id)
+            .execute(&mut *conn)
+            .await?;
+    }
+
+    Ok(())
+}
+
+/// Credentials the machine's agent has not confirmed signing in with yet.
+/// They are handed out on every heartbeat until the agent confirms, since a
+/// response lost on the way would otherwise leave the session without them.
+/// A password that cannot be opened is reported to staff the first time.
+pub async fn take_deliveries(conn: &mut SqliteConnection, machine_id: i64) -> Result<Vec<LicenseDelivery>, sqlx::Error> {
+    let pending: Vec<(i64, String, String, String, bool)> = sqlx::query_as(
+        "SELECT c.id, t.title, a.username, a.password_ciphertext, c.offered_at IS NULL
+         FROM license_checkouts c
+         JOIN license_titles t ON t.id = c.title_id
+         JOIN license_accounts a ON a.id = c.account_id
+         WHERE c.machine_id = ? AND c.returned_at IS NULL AND c.delivered_at IS NULL
+         ORDER BY c.id",
+    )
+    .bind(machine_id)
+    .fetch_all(&mut *conn)
+    .await?;
+
+    let mut deliveries = Vec::new();
+    for (checkout_id, title, username, sealed, first_offer) in pending {
+        if first_offer {
+            sqlx::query("UPDATE license_checkouts SET offered_at = datetime('now') WHERE id = ?")
+                .bind(checkout_id)
+                .execute(&mut *conn)
+                .await?;
+        }
+
+        match open(&sealed) {
+            Some(password) => deliveries.push(LicenseDelivery {
+                checkout_id,
+                title,
+                username,
+                password,
+            }),
+            None if first_offer => {
+                notification_service::notify_staff(
+                    conn,
+                    "license_delivery_failed",
+                    &format!("Could not decrypt the {} account {}, check LICENSE_KEY", title, username),
+                )
+                .await?
+            }
+            None => {}
+        }
+    }
+
+    Ok(deliveries)
+}
+
+/// Marks checkouts the agent reports it has signed in with as delivered.
+/// Ids for other machines or returned checkouts are ignored.
+pub async fn confirm_deliveries(conn: &mut SqliteConnection, machine_id: i64, checkout_ids: &[i64]) -> Result<(), sqlx::Error> {
+    for checkout_id in checkout_ids {
+        sqlx::query(
+            "UPDATE license_checkouts SET delivered_at = datetime('now')
+             WHERE id = ? AND machine_id = ? AND returned_at IS NULL AND delivered_at IS NULL",
+        )
+        .bind(checkout_id)
+        .bind(machine_id)
+        .execute(&mut *conn)
+        .await?;
+    }
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use sqlx::{SqlitePool, sqlite::SqlitePoolOptions};
+
+    async fn test_pool() -> SqlitePool {
+        // a single connection, every in-memory connection is its own database
+        let pool = SqlitePoolOptions::new().max_connections(1).connect("sqlite::memory:").await.unwrap();
+        sqlx::migrate!().run(&pool).await.unwrap();
+        pool
+    }
+
+    /// A user playing on their own machine, returned as (user_id, session_id, machine_id).
+    async fn start_session(conn: &mut SqliteConnection, username: &str) -> (i64, i64, i64) {
+        let user_id: i64 = sqlx::query_scalar(
+            "INSERT INTO users (username, email, role, balance, minutes_balance) VALUES (?, ?, 'user', 0, 0) RETURNING id",
+        )
+        .bind(username)
+        .bind(format!("{}@example.com", username))
+        .fetch_one(&mut *conn)
+        .await
+        .unwrap();
+
+        let machine_id: i64 = sqlx::query_scalar("INSERT INTO machines (name, status) VALUES (?, 'in_use') RETURNING id")
+            .bind(format!("PC-{}", username))
+            .fetch_one(&mut *conn)
+            .await
+            .unwrap();
+
+        let session_id: i64 = sqlx::query_scalar(
+            "INSERT INTO sessions (user_id, machine_id, started_at) VALUES (?, ?, datetime('now')) RETURNING id",
+        )
+        .bind(user_id)
+        .bind(machine_id)
+        .fetch_one(&mut *conn)
+        .await
+        .unwrap();
+
+        (user_id, session_id, machine_id)
+    }
+
+    /// A title with a single pool account.
+    async fn add_title(conn: &mut SqliteConnection) -> i64 {
+        let title_id: i64 = sqlx::query_scalar("INSERT INTO license_titles (title) VALUES ('Arena') RETURNING id")
+            .fetch_one(&mut *conn)
+            .await
+            .unwrap();
+
+        sqlx::query("INSERT INTO license_accounts (title_id, username, password_ciphertext) VALUES (?, 'arena01', 'sealed')")
+            .bind(title_id)
+            .execute(&mut *conn)
+            .await
+            .unwrap();
+
+        title_id
+    }
+
+    async fn join_waitlist(conn: &mut SqliteConnection, title_id: i64, user_id: i64, session_id: i64) {
+        sqlx::query("INSERT INTO license_waitlist (title_id, user_id, session_id) VALUES (?, ?, ?)")
+            .bind(title_id)
+            .bind(user_id)
+            .bind(session_id)
+            .execute(&mut *conn)
+            .await
+            .unwrap();
+    }
+
+    #[tokio::test]
+    async fn returned_account_goes_to_the_first_waiting_session() {
+        let pool = test_pool().await;
+        let conn = &mut pool.acquire().await.unwrap();
+        let title_id = add_title(conn).await;
+        let (alice, alice_session, alice_machine) = start_session(conn, "alice").await;
+        let (bob, bob_session, bob_machine) = start_session(conn, "bob").await;
+
+        let checkout_id = try_checkout(conn, title_id, alice_session, alice, alice_machine).await.unwrap().unwrap();
+        assert!(try_checkout(conn, title_id, bob_session, bob, bob_machine).await.unwrap().is_none());
+        join_waitlist(conn, title_id, bob, bob_session).await;
+
+        let freed = return_checkout(conn, checkout_id, "returned").await.unwrap();
+        assert_eq!(freed, Some(title_id));
+        fulfill_waitlist(conn, title_id).await.unwrap();
+
+        let holder: i64 = sqlx::query_scalar("SELECT session_id FROM license_checkouts WHERE title_id = ? AND returned_at IS NULL")
+            .bind(title_id)
+            .fetch_one(&mut **conn)
+            .await
+            .unwrap();
+        assert_eq!(holder, bob_session);
+
+        let status: String = sqlx::query_scalar("SELECT status FROM license_waitlist WHERE session_id = ?")
+            .bind(bob_session)
+            .fetch_one(&mut **conn)
+            .await
+            .unwrap();
+        assert_eq!(status, "fulfilled");
+
+        let logout: i64 = sqlx::query_scalar("SELECT COUNT(*) FROM machine_commands WHERE machine_id = ? AND command = ?")
+            .bind(alice_machine)
+            .bind(format!("license_logout:{}", checkout_id))
+            .fetch_one(&mut **conn)
+            .await
+            .unwrap();
+        assert_eq!(logout, 1);
+    }
+
+    #[tokio::test]
+    async fn ended_sessions_lose_their_place_in_line() {
+        let pool = test_pool().await;
+        let conn = &mut pool.acquire().await.unwrap();
+        let title_id = add_title(conn).await;
+        let (alice, alice_session, alice_machine) = start_session(conn, "alice").await;
+        let (bob, bob_session, _) = start_session(conn, "bob").await;
+        let (carol, carol_session, _) = start_session(conn, "carol").await;
+
+        let checkout_id = try_checkout(conn, title_id, alice_session, alice, alice_machine).await.unwrap().unwrap();
+        join_waitlist(conn, title_id, bob, bob_session).await;
+        join_waitlist(conn, title_id, carol, carol_session).await;
+
+        sqlx::query("UPDATE sessions SET ended_at = datetime('now') WHERE id = ?")
+            .bind(bob_session)
+            .execute(&mut **conn)
+            .await
+            .unwrap();
+
+        return_checkout(conn, checkout_id, "returned").await.unwrap();
+        fulfill_waitlist(conn, title_id).await.unwrap();
+
+        let statuses: Vec<String> = sqlx::query_scalar("SELECT status FROM license_waitlist ORDER BY id")
+            .fetch_all(&mut **conn)
+            .await
+            .unwrap();
+        assert_eq!(statuses, ["cancelled", "fulfilled"]);
+    }
+
+    #[tokio::test]
+    async fn credentials_are_offered_until_the_agent_confirms() {
+        let pool = test_pool().await;
+        let conn = &mut pool.acquire().await.unwrap();
+        let title_id = add_title(conn).await;
+        let (alice, alice_session, alice_machine) = start_session(conn, "alice").await;
+        let checkout_id = try_checkout(conn, title_id, alice_session, alice, alice_machine).await.unwrap().unwrap();
+
+        // the test account's password cannot be opened, staff hear about it once
+        take_deliveries(conn, alice_machine).await.unwrap();
+        take_deliveries(conn, alice_machine).await.unwrap();
+
+        let checkout = load_checkout(conn, checkout_id).await.unwrap().unwrap();
+        assert!(checkout.delivered_at.is_none());
+
+        let warnings: i64 = sqlx::query_scalar("SELECT COUNT(*) FROM notifications WHERE kind = 'license_delivery_failed'")
+            .fetch_one(&mut **conn)
+            .await
+            .unwrap();
+        assert_eq!(warnings, 1);
+
+        // another machine cannot confirm it
+        confirm_deliveries(conn, alice_machine + 1, &[checkout_id]).await.unwrap();
+        assert!(load_checkout(conn, checkout_id).await.unwrap().unwrap().delivered_at.is_none());
+
+        confirm_deliveries(conn, alice_machine, &[checkout_id]).await.unwrap();
+        assert!(load_checkout(conn, checkout_id).await.unwrap().unwrap().delivered_at.is_some());
+    }
+}
\ No newline at end of file
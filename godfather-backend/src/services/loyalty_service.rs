@@ -0,0 +1,240 @@
+use sqlx::{SqliteConnection, SqlitePool};
+use crate::{models::loyalty::LoyaltyTier, services::notification_service};
+
+pub async fn current_tier(
+    conn: &mut SqliteConnection,
+    user_id: i64,
+) -> Result<Option<LoyaltyTier>, sqlx::Error> {
+    sqlx::query_as::<_, LoyaltyTier>(
+        "SELECT t.id, t.name, t.min_hours, t.rate_discount_percent, t.bonus_minutes, t.bonus_period_days, t.created_at
+         FROM loyalty_tiers t
+         JOIN users u ON u.loyalty_tier_id = t.id
+         WHERE u.id = ?",
+    )
+    .bind(user_id)
+    .fetch_optional(conn)
+    .await
+}
+
+/// Moves the user to the highest tier their lifetime hours qualify for and
+/// notifies them when that is a promotion or demotion.
+pub async fn refresh_tier(conn: &mut SqliteConnection, user_id: i64) -> Result<(), sqlx::Error> {
+    retier(conn, user_id, None).await
+}
+
+/// `refresh_tier`, leaving `excluded_tier_id` out of the running.
+async fn retier(conn: &mut SqliteConnection, user_id: i64, excluded_tier_id: Option<i64>) -> Result<(), sqlx::Error> {
+    let row: Option<(i64, Option<i64>)> = sqlx::query_as(
+        "SELECT lifetime_hours, loyalty_tier_id FROM users WHERE id = ?",
+    )
+    .bind(user_id)
+    .fetch_optional(&mut *conn)
+    .await?;
+
+    let Some((lifetime_hours, current_tier_id)) = row else {
+        return Ok(());
+    };
+
+    let earned = sqlx::query_as::<_, LoyaltyTier>(
+        "SELECT id, name, min_hours, rate_discount_percent, bonus_minutes, bonus_period_days, created_at
+         FROM loyalty_tiers WHERE min_hours <= ? AND id IS NOT ? ORDER BY min_hours DESC LIMIT 1",
+    )
+    .bind(lifetime_hours)
+    .bind(excluded_tier_id)
+    .fetch_optional(&mut *conn)
+    .await?;
+
+    let earned_id = earned.as_ref().map(|t| t.id);
+    if earned_id == current_tier_id {
+        return Ok(());
+    }
+
+    let previous_min_hours: Option<i64> = match current_tier_id {
+        Some(id) => sqlx::query_scalar("SELECT min_hours FROM loyalty_tiers WHERE id = ?")
+            .bind(id)
+            .fetch_optional(&mut *conn)
+            .await?,
+        None => None,
+    };
+
+    sqlx::query("UPDATE users SET loyalty_tier_id = ? WHERE id = ?")
+        .bind(earned_id)
+        .bind(user_id)
+        .execute(&mut *conn)
+        .await?;
+
+    // the first tier assignment is not worth a notification
+    if current_tier_id.is_none() {
+        return Ok(());
+    }
+
+    match (&earned, previous_min_hours) {
+        (Some(tier), Some(previous)) if tier.min_hours > previous => {
+            let message = format!("Congratulations! You have been promoted to {} tier", tier.name);
+            notification_service::notify_user(conn, user_id, "tier_promoted", &message).await?;
+        }
+        (Some(tier), _) => {
+            let message = format!("Your loyalty tier has changed to {}", tier.name);
+            notification_service::notify_user(conn, user_id, "tier_demoted", &message).await?;
+        }
+        (None, _) => {
+            notification_service::notify_user(conn, user_id, "tier_demoted", "You no longer hold a loyalty tier").await?;
+        }
+    }
+
+    Ok(())
+}
+
+/// Deletes a tier after moving its members to the best tier left for them,
+/// notifying each of the change. None when the tier does not exist.
+pub async fn delete_tier(conn: &mut SqliteConnection, tier_id: i64) -> Result<Option<LoyaltyTier>, sqlx::Error> {
+    let members: Vec<i64> = sqlx::query_scalar("SELECT id FROM users WHERE loyalty_tier_id = ?")
+        .bind(tier_id)
+        .fetch_all(&mut *conn)
+        .await?;
+
+    // re-tiered while the tier still exists, so each move is announced against it
+    for user_id in members {
+        retier(conn, user_id, Some(tier_id)).await?;
+    }
+
+    sqlx::query_as::<_, LoyaltyTier>(
+        "DELETE FROM loyalty_tiers WHERE id = ?
+         RETURNING id, name, min_hours, rate_discount_percent, bonus_minutes, bonus_period_days, created_at",
+    )
+    .bind(tier_id)
+    .fetch_optional(conn)
+    .await
+}
+
+/// Re-evaluates every user, used after tier thresholds change.
+pub async fn refresh_all_tiers(pool: &SqlitePool) -> Result<(), sqlx::Error> {
+    let user_ids: Vec<i64> = sqlx::query_scalar("SELECT id FROM users")
+        .fetch_all(pool)
+        .await?;
+
+    let mut tx = pool.begin().await?;
+    for user_id in user_ids {
+        refresh_tier(&mut tx, user_id).await?;
+    }
+    tx.commit().await?;
+
+    Ok(())
+}
+
+/// Credits each tier's periodic bonus minutes to members whose last grant is
+/// at least one bonus period old.
+pub async fn grant_tier_bonuses(pool: &SqlitePool) -> Result<(), sqlx::Error> {
+    let due: Vec<(i64, i64, String)> = sqlx::query_as(
+        "SELECT u.id, t.bonus_minutes, t.name
+         FROM users u
+         JOIN loyalty_tiers t ON t.id = u.loyalty_tier_id
+         WHERE t.bonus_minutes > 0
+           AND u.banned = 0
+           AND (u.tier_bonus_at IS NULL
+                OR u.tier_bonus_at <= datetime('now', '-' || t.bonus_period_days || ' days'))",
+    )
+    .fetch_all(pool)
+    .await?;
+
+    for (user_id, minutes, tier_name) in due {
+        let mut tx = pool.begin().await?;
+
+        sqlx::query("UPDATE users SET bonus_minutes = bonus_minutes + ?, tier_bonus_at = datetime('now') WHERE id = ?")
+            .bind(minutes)
+            .bind(user_id)
+            .execute(&mut *tx)
+            .await?;
+
+        let message = format!("{} bonus minutes added as a {} tier perk", minutes, tier_name);
+        notification_service::notify_user(&mut tx, user_id, "tier_bonus", &message).await?;
+
+        tx.commit().await?;
+    }
+
+    Ok(())
+}
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use sqlx::sqlite::SqlitePoolOptions;
+
+    async fn test_pool() -> SqlitePool {
+        // a single connection, every in-memory connection is its own database
+        let pool = SqlitePoolOptions::new().max_connections(1).connect("sqlite::memory:").await.unwrap();
+        sqlx::migrate!().run(&pool).await.unwrap();
+        pool
+    }
+
+    async fn add_user(pool: &SqlitePool, username: &str, lifetime_hours: i64) -> i64 {
+        let user_id: i64 = sqlx::query_scalar(
+            "INSERT INTO users (username, email, role, balance, minutes_balance, lifetime_hours) VALUES (?, ?, 'user', 0, 0, ?) RETURNING id",
+        )
+        .bind(username)
+        .bind(format!("{}@example.com", username))
+        .bind(lifetime_hours)
+        .fetch_one(pool)
+        .await
+        .unwrap();
+
+        refresh_tier(&mut pool.acquire().await.unwrap(), user_id).await.unwrap();
+        user_id
+    }
+
+    async fn tier_of(pool: &SqlitePool, user_id: i64) -> Option<String> {
+        sqlx::query_scalar("SELECT t.name FROM users u LEFT JOIN loyalty_tiers t ON t.id = u.loyalty_tier_id WHERE u.id = ?")
+            .bind(user_id)
+            .fetch_one(pool)
+            .await
+            .unwrap()
+    }
+
+    async fn notifications(pool: &SqlitePool, user_id: i64) -> Vec<String> {
+        sqlx::query_scalar("SELECT kind FROM notifications WHERE user_id = ? ORDER BY id")
+            .bind(user_id)
+            .fetch_all(pool)
+            .await
+            .unwrap()
+    }
+
+    async fn tier_id(pool: &SqlitePool, name: &str) -> i64 {
+        sqlx::query_scalar("SELECT id FROM loyalty_tiers WHERE name = ?").bind(name).fetch_one(pool).await.unwrap()
+    }
+
+    #[tokio::test]
+    async fn deleting_a_tier_moves_its_members_down_and_tells_them() {
+        let pool = test_pool().await;
+        let silver_member = add_user(&pool, "silver", 80).await;
+        let gold_member = add_user(&pool, "gold", 250).await;
+        assert_eq!(tier_of(&pool, silver_member).await.as_deref(), Some("Silver"));
+
+        let silver = tier_id(&pool, "Silver").await;
+        let deleted = delete_tier(&mut pool.acquire().await.unwrap(), silver).await.unwrap();
+
+        assert_eq!(deleted.map(|t| t.name).as_deref(), Some("Silver"));
+        assert_eq!(tier_of(&pool, silver_member).await.as_deref(), Some("Bronze"));
+        assert_eq!(notifications(&pool, silver_member).await, vec!["tier_demoted"]);
+        assert_eq!(tier_of(&pool, gold_member).await.as_deref(), Some("Gold"));
+        assert!(notifications(&pool, gold_member).await.is_empty());
+    }
+
+    #[tokio::test]
+    async fn deleting_the_lowest_tier_leaves_members_without_one() {
+        let pool = test_pool().await;
+        let newcomer = add_user(&pool, "newcomer", 3).await;
+
+        let bronze = tier_id(&pool, "Bronze").await;
+        delete_tier(&mut pool.acquire().await.unwrap(), bronze).await.unwrap();
+
+        assert_eq!(tier_of(&pool, newcomer).await, None);
+        assert_eq!(notifications(&pool, newcomer).await, vec!["tier_demoted"]);
+    }
+
+    #[tokio::test]
+    async fn deleting_a_missing_tier_finds_nothing() {
+        let pool = test_pool().await;
+        assert!(delete_tier(&mut pool.acquire().await.unwrap(), 999).await.unwrap().is_none());
+    }
+}
\ No newline at end of file
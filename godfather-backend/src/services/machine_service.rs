@@ -0,0 +1,211 @@
+use base64::{Engine, engine::general_purpose::STANDARD};
+use ring::{
+    constant_time::verify_slices_are_equal,
+    digest::{SHA256, digest},
+    rand::{SecureRandom, SystemRandom},
+};
+use sqlx::SqliteConnection;
+use crate::{
+    models::machine::MachineCommand,
+    services::{notification_service, reconcile_service, reservation_service, waitlist_service},
+};
+
+/// Decides what a machine does once its current occupant or hold goes away:
+/// it goes to maintenance while a blocking ticket is open, stays held while a
+/// reservation or waitlist offer still claims it, waits for an imminent
+/// booking, or is offered to the next person in the waitlist before going back
+/// to available.
+pub async fn release_machine(conn: &mut SqliteConnection, machine_id: i64) -> Result<(), sqlx::Error> {
+    if has_blocking_ticket(conn, machine_id).await? {
+        withdraw_holds(conn, machine_id).await?;
+
+        sqlx::query("UPDATE machines SET status = 'maintenance' WHERE id = ? AND status IN ('in_use', 'reserved', 'available')")
+            .bind(machine_id)
+            .execute(&mut *conn)
+            .await?;
+
+        return Ok(());
+    }
+
+    let still_held = sqlx::query(
+        "SELECT 1 FROM reservations WHERE machine_id = ? AND status = 'held'
+         UNION ALL
+         SELECT 1 FROM waitlist_entries WHERE offered_machine_id = ? AND status = 'offered'
+         LIMIT 1",
+    )
+    .bind(machine_id)
+    .bind(machine_id)
+    .fetch_optional(&mut *conn)
+    .await?;
+
+    let next_status = if still_held.is_some() { "reserved" } else { "available" };
+
+    sqlx::query("UPDATE machines SET status = ? WHERE id = ? AND status IN ('in_use', 'reserved', 'available')")
+        .bind(next_status)
+        .bind(machine_id)
+        .execute(&mut *conn)
+        .await?;
+
+    if still_held.is_none() && !reservation_service::has_upcoming_booking(conn, machine_id).await? {
+        waitlist_service::offer_machine(conn, machine_id).await?;
+    }
+
+    Ok(())
+}
+
+/// Lets go of whoever the machine was held for when it has to go to
+/// maintenance instead. A held reservation goes back to booked, so it is held
+/// again if the machine returns in time, and a waitlist offer goes back to
+/// waiting without losing its place. Both customers are told.
+async fn withdraw_holds(conn: &mut SqliteConnection, machine_id: i64) -> Result<(), sqlx::Error> {
+    let reservations: Vec<i64> = sqlx::query_scalar(
+        "UPDATE reservations SET status = 'booked' WHERE machine_id = ? AND status = 'held' RETURNING user_id",
+    )
+    .bind(machine_id)
+    .fetch_all(&mut *conn)
+    .await?;
+
+    let offers: Vec<i64> = sqlx::query_scalar(
+        "UPDATE waitlist_entries SET status = 'waiting', offered_machine_id = NULL, offered_at = NULL, offer_expires_at = NULL
+         WHERE offered_machine_id = ? AND status = 'offered'
+         RETURNING user_id",
+    )
+    .bind(machine_id)
+    .fetch_all(&mut *conn)
+    .await?;
+
+    if reservations.is_empty() && offers.is_empty() {
+        return Ok(());
+    }
+
+    let machine_name: String = sqlx::query_scalar("SELECT name FROM machines WHERE id = ?")
+        .bind(machine_id)
+        .fetch_one(&mut *conn)
+        .await?;
+
+    for user_id in reservations {
+        let message = format!("{} needs maintenance and is no longer held for you, please ask staff for another machine", machine_name);
+        notification_service::notify_user(conn, user_id, "reservation_hold_withdrawn", &message).await?;
+    }
+
+    for user_id in offers {
+        let message = format!("{} needs maintenance, you keep your place in line for the next machine", machine_name);
+        notification_service::notify_user(conn, user_id, "waitlist_offer_withdrawn", &message).await?;
+    }
+
+    Ok(())
+}
+
+pub async fn has_blocking_ticket(conn: &mut SqliteConnection, machine_id: i64) -> Result<bool, sqlx::Error> {
+    let open = sqlx::query("SELECT 1 FROM maintenance_tickets WHERE machine_id = ? AND blocking = 1 AND status != 'resolved' LIMIT 1")
+        .bind(machine_id)
+        .fetch_optional(conn)
+        .await?;
+
+    Ok(open.is_some())
+}
+
+/// Brings a machine out of maintenance once its last blocking ticket is resolved.
+/// Returns false when the machine is not in maintenance or still blocked.
+pub async fn return_to_service(conn: &mut SqliteConnection, machine_id: i64) -> Result<bool, sqlx::Error> {
+    if has_blocking_ticket(conn, machine_id).await? {
+        return Ok(false);
+    }
+
+    let updated = sqlx::query("UPDATE machines SET status = 'available' WHERE id = ? AND status = 'maintenance'")
+        .bind(machine_id)
+        .execute(&mut *conn)
+        .await?;
+
+    if updated.rows_affected() == 0 {
+        return Ok(false);
+    }
+
+    release_machine(conn, machine_id).await?;
+
+    Ok(true)
+}
+
+/// Extends the machine's current uptime span, or starts a new one when the
+/// last heartbeat is too long ago.
+pub async fn track_uptime(conn: &mut SqliteConnection, machine_id: i64) -> Result<(), sqlx::Error> {
+    let extended = sqlx::query(
+        "UPDATE machine_uptime_spans SET last_seen_at = datetime('now')
+         WHERE id = (SELECT id FROM machine_uptime_spans WHERE machine_id = ? ORDER BY last_seen_at DESC, id DESC LIMIT 1)
+           AND last_seen_at > datetime('now', ?)",
+    )
+    .bind(machine_id)
+    .bind(format!("-{} minutes", reconcile_service::silent_minutes()))
+    .execute(&mut *conn)
+    .await?;
+
+    if extended.rows_affected() == 0 {
+        sqlx::query("INSERT INTO machine_uptime_spans (machine_id) VALUES (?)")
+            .bind(machine_id)
+            .execute(&mut *conn)
+            .await?;
+    }
+
+    Ok(())
+}
+
+/// Queues an instruction for the machine's agent, delivered with the next heartbeat.
+pub async fn queue_command(conn: &mut SqliteConnection, machine_id: i64, command: &str) -> Result<(), sqlx::Error> {
+    sqlx::query("INSERT INTO machine_commands (machine_id, command) VALUES (?, ?)")
+        .bind(machine_id)
+        .bind(command)
+        .execute(conn)
+        .await?;
+
+    Ok(())
+}
+
+/// Hands over queued commands in order and marks them delivered.
+pub async fn take_pending_commands(conn: &mut SqliteConnection, machine_id: i64) -> Result<Vec<MachineCommand>, sqlx::Error> {
+    sqlx::query_as::<_, MachineCommand>(
+        "UPDATE machine_commands SET delivered_at = datetime('now')
+         WHERE machine_id = ? AND delivered_at IS NULL
+         RETURNING id, machine_id, command, created_at",
+    )
+    .bind(machine_id)
+    .fetch_all(conn)
+    .await
+    .map(|mut commands| {
+        commands.sort_by_key(|c| c.id);
+        commands
+    })
+}
+
+// agents send the secret they were issued at registration in this header
+pub const AGENT_TOKEN_HEADER: &str = "X-Agent-Token";
+
+fn hash_agent_token(token: &str) -> String {
+    STANDARD.encode(digest(&SHA256, token.as_bytes()))
+}
+
+/// A fresh agent secret and the hash stored for it. The secret itself is only
+/// ever handed to the agent that registered.
+pub fn issue_agent_token() -> Option<(String, String)> {
+    let mut bytes = [0u8; 32];
+    SystemRandom::new().fill(&mut bytes).ok()?;
+
+    let token = STANDARD.encode(bytes);
+    let hash = hash_agent_token(&token);
+    Some((token, hash))
+}
+
+/// True when the token is the one issued to the machine. Decommissioned
+/// machines, and machines enrolled before agents had tokens, never pass.
+pub async fn authenticate_agent(conn: &mut SqliteConnection, machine_id: i64, token: Option<&str>) -> Result<bool, sqlx::Error> {
+    let Some(token) = token else {
+        return Ok(false);
+    };
+
+    let stored: Option<String> = sqlx::query_scalar("SELECT agent_token_hash FROM machines WHERE id = ? AND decommissioned_at IS NULL")
+        .bind(machine_id)
+        .fetch_optional(conn)
+        .await?
+        .flatten();
+
+    Ok(stored.is_some_and(|stored| verify_slices_are_equal(stored.as_bytes(), hash_agent_token(token).as_bytes()).is_ok()))
+}
\ No newline at end of file
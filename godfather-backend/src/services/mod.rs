@@ -1 +1,21 @@
-pub mod user_service;
\ No newline at end of file
+pub mod user_service;
+pub mod subscription_service;
+pub mod session_service;
+pub mod notification_service;
+pub mod loyalty_service;
+pub mod receipt_service;
+pub mod receipt_render;
+pub mod reservation_service;
+pub mod machine_service;
+pub mod waitlist_service;
+pub mod reconcile_service;
+pub mod billing_service;
+pub mod guest_ticket_service;
+pub mod telemetry_service;
+pub mod assignment_service;
+pub mod analytics_service;
+pub mod agent_service;
+pub mod power_service;
+pub mod app_usage_service;
+pub mod license_service;
+pub mod code_service;
\ No newline at end of file
@@ -0,0 +1,32 @@
+use sqlx::SqliteConnection;
+
+pub async fn notify_user(
+    conn: &mut SqliteConnection,
+    user_id: i64,
+    kind: &str,
+    message: &str,
+) -> Result<(), sqlx::Error> {
+    sqlx::query("INSERT INTO notifications (user_id, audience, kind, message) VALUES (?, 'user', ?, ?)")
+        .bind(user_id)
+        .bind(kind)
+        .bind(message)
+        .execute(conn)
+        .await?;
+
+    Ok(())
+}
+
+
+pub async fn notify_staff(
+    conn: &mut SqliteConnection,
+    kind: &str,
+    message: &str,
+) -> Result<(), sqlx::Error> {
+    sqlx::query("INSERT INTO notifications (audience, kind, message) VALUES ('staff', ?, ?)")
+        .bind(kind)
+        .bind(message)
+        .execute(conn)
+        .await?;
+
+    Ok(())
+}
\ No newline at end of file
@@ -0,0 +1,400 @@
+use std::collections::HashMap;
+use chrono::{Duration, Utc};
+use sqlx::{SqliteConnection, SqlitePool};
+use crate::{
+    models::{machine::MachineCommand, power::{PowerPolicy, PowerSavings}},
+    services::{
+        analytics_service::{self, AnalyticsRange},
+        machine_service, notification_service, reconcile_service, reservation_service,
+    },
+};
+
+// used for zones without a policy of their own
+const DEFAULT_IDLE_WATTS: i64 = 90;
+const DEFAULT_OFF_WATTS: i64 = 2;
+// electricity price per kWh in minor currency units
+const DEFAULT_PRICE_PER_KWH: i64 = 30;
+
+pub const POLICY_COLUMNS: &str =
+    "zone, enabled, idle_shutdown_minutes, shutdown_after, wake_at, idle_watts, off_watts, last_wake_on, updated_by, updated_at";
+
+fn price_per_kwh() -> i64 {
+    std::env::var("POWER_PRICE_PER_KWH")
+        .ok()
+        .and_then(|v| v.parse().ok())
+        .unwrap_or(DEFAULT_PRICE_PER_KWH)
+}
+
+/// True when `clock` (HH:MM) falls in the window from `starts` until `ends`,
+/// which may wrap past midnight. Without an end the window runs to midnight.
+fn in_window(clock: &str, starts: &str, ends: Option<&str>) -> bool {
+    match ends {
+        None => clock >= starts,
+        Some(ends) if starts < ends => clock >= starts && clock < ends,
+        Some(ends) => clock >= starts || clock < ends,
+    }
+}
+
+/// True when idle machines may be shut down at `clock`. The window runs from
+/// `shutdown_after` until `wake_at`; without a start it is always open.
+fn in_shutdown_window(clock: &str, shutdown_after: Option<&str>, wake_at: Option<&str>) -> bool {
+    shutdown_after.is_none_or(|after| in_window(clock, after, wake_at))
+}
+
+/// True when the zone should be awake at `clock`: from `wake_at` until
+/// `shutdown_after`, so a restart or a new policy at night wakes nothing.
+fn in_wake_window(clock: &str, wake_at: Option<&str>, shutdown_after: Option<&str>) -> bool {
+    wake_at.is_some_and(|wake| in_window(clock, wake, shutdown_after))
+}
+
+/// Shuts down machines left idle past their zone's limit and wakes the zone
+/// ahead of opening. Run by the scheduler. Policy times are UTC, like every
+/// other time the server stores.
+pub async fn run_policies(pool: &SqlitePool) -> Result<(), sqlx::Error> {
+    let now = Utc::now().naive_utc();
+    let clock = now.format("%H:%M").to_string();
+
+    let policies = sqlx::query_as::<_, PowerPolicy>(&format!("SELECT {} FROM power_policies WHERE enabled = 1", POLICY_COLUMNS))
+        .fetch_all(pool)
+        .await?;
+
+    for policy in policies {
+        // past midnight in a window that wrapped, the wake belongs to yesterday
+        let wake_day = match policy.wake_at.as_deref() {
+            Some(wake_at) if clock.as_str() < wake_at => (now - Duration::days(1)).format("%Y-%m-%d").to_string(),
+            _ => now.format("%Y-%m-%d").to_string(),
+        };
+
+        let wake_due = in_wake_window(&clock, policy.wake_at.as_deref(), policy.shutdown_after.as_deref())
+            && policy.last_wake_on.as_deref() != Some(wake_day.as_str());
+
+        if wake_due {
+            wake_zone(pool, &policy.zone, &wake_day).await?;
+            continue;
+        }
+
+        if in_shutdown_window(&clock, policy.shutdown_after.as_deref(), policy.wake_at.as_deref()) {
+            shutdown_idle(pool, &policy).await?;
+        }
+    }
+
+    Ok(())
+}
+
+async fn shutdown_idle(pool: &SqlitePool, policy: &PowerPolicy) -> Result<(), sqlx::Error> {
+    // idle since the last session left or the machine booted, whichever is later
+    let idle: Vec<i64> = sqlx::query_scalar(
+        "SELECT m.id FROM machines m
+         WHERE m.zone = ? AND m.decommissioned_at IS NULL AND m.power_state = 'on'
+           AND m.status = 'available'
+           AND m.last_seen_at > datetime('now', ?)
+           AND NOT EXISTS (SELECT 1 FROM sessions s WHERE s.machine_id = m.id AND s.ended_at IS NULL)
+           AND NOT EXISTS (
+               SELECT 1 FROM waitlist_entries w
+               WHERE w.status = 'waiting' AND (w.machine_class IS NULL OR w.machine_class = m.class)
+           )
+           AND MAX(COALESCE((SELECT MAX(sg.ended_at) FROM session_segments sg WHERE sg.machine_id = m.id), ''),
+                   COALESCE((SELECT MAX(u.started_at) FROM machine_uptime_spans u WHERE u.machine_id = m.id), ''))
+               <= datetime('now', ?)",
+    )
+    .bind(&policy.zone)
+    .bind(format!("-{} minutes", reconcile_service::silent_minutes()))
+    .bind(format!("-{} minutes", policy.idle_shutdown_minutes))
+    .fetch_all(pool)
+    .await?;
+
+    for machine_id in idle {
+        let mut tx = pool.begin().await?;
+
+        if reservation_service::has_upcoming_booking(&mut tx, machine_id).await? {
+            continue;
+        }
+
+        machine_service::queue_command(&mut tx, machine_id, "shutdown").await?;
+
+        sqlx::query("UPDATE machines SET power_state = 'shutdown_pending' WHERE id = ?")
+            .bind(machine_id)
+            .execute(&mut *tx)
+            .await?;
+
+        tx.commit().await?;
+    }
+
+    Ok(())
+}
+
+/// Machines that are off cannot hear their own heartbeat, so the wake-on-LAN
+/// packet is sent by a peer that is still on, preferably in the same zone.
+async fn wake_zone(pool: &SqlitePool, zone: &str, wake_day: &str) -> Result<(), sqlx::Error> {
+    let mut tx = pool.begin().await?;
+
+    // shutdowns that were never picked up are simply called off
+    let pending: Vec<i64> = sqlx::query_scalar(
+        "UPDATE machines SET power_state = 'on' WHERE zone = ? AND power_state = 'shutdown_pending' RETURNING id",
+    )
+    .bind(zone)
+    .fetch_all(&mut *tx)
+    .await?;
+
+    for machine_id in pending {
+        sqlx::query("DELETE FROM machine_commands WHERE machine_id = ? AND command = 'shutdown' AND delivered_at IS NULL")
+            .bind(machine_id)
+            .execute(&mut *tx)
+            .await?;
+    }
+
+    let off: Vec<(String, Option<String>)> = sqlx::query_as(
+        "SELECT name, mac_address FROM machines WHERE zone = ? AND power_state = 'off' AND decommissioned_at IS NULL ORDER BY name",
+    )
+    .bind(zone)
+    .fetch_all(&mut *tx)
+    .await?;
+
+    let peer = wake_peer(&mut tx, Some(zone)).await?;
+
+    let mut unreachable = Vec::new();
+    for (name, mac_address) in off {
+        match (peer, mac_address) {
+            (Some(peer), Some(mac)) => machine_service::queue_command(&mut tx, peer, &format!("wake:{}", mac)).await?,
+            _ => unreachable.push(name),
+        }
+    }
+
+    if !unreachable.is_empty() {
+        notification_service::notify_staff(
+            &mut tx,
+            "power_wake_failed",
+            &format!("Could not wake {}, switch them on by hand", unreachable.join(", ")),
+        )
+        .await?;
+    }
+
+    sqlx::query("UPDATE power_policies SET last_wake_on = ? WHERE zone = ?")
+        .bind(wake_day)
+        .bind(zone)
+        .execute(&mut *tx)
+        .await?;
+
+    tx.commit().await?;
+
+    Ok(())
+}
+
+/// A machine that is on and reporting, preferably in the zone, to send wake-on-LAN packets from.
+async fn wake_peer(conn: &mut SqliteConnection, zone: Option<&str>) -> Result<Option<i64>, sqlx::Error> {
+    sqlx::query_scalar(
+        "SELECT id FROM machines
+         WHERE power_state = 'on' AND decommissioned_at IS NULL AND last_seen_at > datetime('now', ?)
+         ORDER BY zone IS ? DESC, last_seen_at DESC
+         LIMIT 1",
+    )
+    .bind(format!("-{} minutes", reconcile_service::silent_minutes()))
+    .bind(zone)
+    .fetch_optional(conn)
+    .await
+}
+
+/// Makes sure a machine a session starts on or moves to stays on: a shutdown not yet
+/// picked up is called off, and a machine that is already off is woken.
+pub async fn keep_awake(conn: &mut SqliteConnection, machine_id: i64) -> Result<(), sqlx::Error> {
+    sqlx::query("DELETE FROM machine_commands WHERE machine_id = ? AND command = 'shutdown' AND delivered_at IS NULL")
+        .bind(machine_id)
+        .execute(&mut *conn)
+        .await?;
+
+    sqlx::query("UPDATE machines SET power_state = 'on' WHERE id = ? AND power_state = 'shutdown_pending'")
+        .bind(machine_id)
+        .execute(&mut *conn)
+        .await?;
+
+    let off: Option<(String, Option<String>, Option<String>)> = sqlx::query_as(
+        "SELECT name, zone, mac_address FROM machines WHERE id = ? AND power_state = 'off'",
+    )
+    .bind(machine_id)
+    .fetch_optional(&mut *conn)
+    .await?;
+
+    let Some((name, zone, mac_address)) = off else {
+        return Ok(());
+    };
+
+    match (wake_peer(conn, zone.as_deref()).await?, mac_address) {
+        (Some(peer), Some(mac)) => machine_service::queue_command(conn, peer, &format!("wake:{}", mac)).await,
+        _ => {
+            notification_service::notify_staff(conn, "power_wake_failed", &format!("Could not wake {}, switch it on by hand", name))
+                .await
+        }
+    }
+}
+
+/// Keeps the machine's power state in step with its heartbeats: a heartbeat
+/// means it is on, and handing it a shutdown command means it is about to be off.
+pub async fn on_heartbeat(
+    conn: &mut SqliteConnection,
+    machine_id: i64,
+    delivered: &[MachineCommand],
+) -> Result<(), sqlx::Error> {
+    let woke = sqlx::query("UPDATE machines SET power_state = 'on' WHERE id = ? AND power_state = 'off'")
+        .bind(machine_id)
+        .execute(&mut *conn)
+        .await?;
+
+    if woke.rows_affected() > 0 {
+        sqlx::query("UPDATE machine_power_offs SET ended_at = datetime('now') WHERE machine_id = ? AND ended_at IS NULL")
+            .bind(machine_id)
+            .execute(&mut *conn)
+            .await?;
+    }
+
+    if delivered.iter().any(|c| c.command == "shutdown") {
+        sqlx::query("UPDATE machines SET power_state = 'off' WHERE id = ?")
+            .bind(machine_id)
+            .execute(&mut *conn)
+            .await?;
+
+        sqlx::query("INSERT INTO machine_power_offs (machine_id) VALUES (?)")
+            .bind(machine_id)
+            .execute(&mut *conn)
+            .await?;
+    }
+
+    Ok(())
+}
+
+/// Energy saved by policy shutdowns over the range, per zone, next to the
+/// energy idle machines that stayed on used. Idle time is uptime minus time in use.
+pub async fn savings(pool: &SqlitePool, range: &AnalyticsRange, zone: &Option<String>) -> Result<Vec<PowerSavings>, sqlx::Error> {
+    let machines = analytics_service::machine_utilization(pool, range, zone).await?;
+
+    let offs: Vec<(i64, String, String)> = sqlx::query_as(
+        "SELECT machine_id, started_at, COALESCE(ended_at, datetime('now')) FROM machine_power_offs
+         WHERE started_at < ? AND COALESCE(ended_at, datetime('now')) > ?",
+    )
+    .bind(range.to.format("%Y-%m-%d %H:%M:%S").to_string())
+    .bind(range.from.format("%Y-%m-%d %H:%M:%S").to_string())
+    .fetch_all(pool)
+    .await?;
+
+    let mut off_seconds: HashMap<i64, i64> = HashMap::new();
+    for (machine_id, start, end) in offs {
+        let parse = |v: &str| chrono::NaiveDateTime::parse_from_str(v, "%Y-%m-%d %H:%M:%S").ok();
+        if let (Some(start), Some(end)) = (parse(&start), parse(&end)) {
+            *off_seconds.entry(machine_id).or_default() += range.clip_seconds(start, end);
+        }
+    }
+
+    let watts: HashMap<String, (i64, i64)> = sqlx::query_as::<_, (String, i64, i64)>("SELECT zone, idle_watts, off_watts FROM power_policies")
+        .fetch_all(pool)
+        .await?
+        .into_iter()
+        .map(|(zone, idle, off)| (zone, (idle, off)))
+        .collect();
+
+    let price = price_per_kwh();
+    let kwh = |minutes: i64, watts: i64| (minutes as f64 * watts as f64 / 60_000.0 * 100.0).round() / 100.0;
+
+    let mut zones: Vec<PowerSavings> = Vec::new();
+    for machine in machines {
+        let (idle_watts, off_watts) = machine
+            .zone
+            .as_ref()
+            .and_then(|z| watts.get(z).copied())
+            .unwrap_or((DEFAULT_IDLE_WATTS, DEFAULT_OFF_WATTS));
+
+        let off_minutes = off_seconds.get(&machine.machine_id).copied().unwrap_or(0) / 60;
+        let idle_on_minutes = (machine.uptime_minutes - machine.occupied_minutes).max(0);
+        let saved_kwh = kwh(off_minutes, idle_watts - off_watts);
+        let wasted_kwh = kwh(idle_on_minutes, idle_watts);
+
+        let index = match zones.iter().position(|z| z.zone == machine.zone) {
+            Some(index) => index,
+            None => {
+                zones.push(PowerSavings {
+                    zone: machine.zone.clone(),
+                    machines: 0,
+                    off_minutes: 0,
+                    saved_kwh: 0.0,
+                    saved_cost: 0,
+                    idle_on_minutes: 0,
+                    wasted_kwh: 0.0,
+                    wasted_cost: 0,
+                });
+                zones.len() - 1
+            }
+        };
+
+        let entry = &mut zones[index];
+        entry.machines += 1;
+        entry.off_minutes += off_minutes;
+        entry.saved_kwh += saved_kwh;
+        entry.idle_on_minutes += idle_on_minutes;
+        entry.wasted_kwh += wasted_kwh;
+    }
+
+    for entry in &mut zones {
+        entry.saved_kwh = (entry.saved_kwh * 100.0).round() / 100.0;
+        entry.wasted_kwh = (entry.wasted_kwh * 100.0).round() / 100.0;
+        entry.saved_cost = (entry.saved_kwh * price as f64).round() as i64;
+        entry.wasted_cost = (entry.wasted_kwh * price as f64).round() as i64;
+    }
+
+    Ok(zones)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn no_start_time_means_always_in_window() {
+        assert!(in_shutdown_window("12:00", None, None));
+        assert!(in_shutdown_window("12:00", None, Some("08:00")));
+    }
+
+    #[test]
+    fn open_ended_window_runs_to_midnight() {
+        assert!(!in_shutdown_window("21:59", Some("22:00"), None));
+        assert!(in_shutdown_window("22:00", Some("22:00"), None));
+        assert!(in_shutdown_window("23:59", Some("22:00"), None));
+        assert!(!in_shutdown_window("00:30", Some("22:00"), None));
+    }
+
+    #[test]
+    fn same_day_window() {
+        assert!(!in_shutdown_window("12:59", Some("13:00"), Some("15:00")));
+        assert!(in_shutdown_window("13:00", Some("13:00"), Some("15:00")));
+        assert!(in_shutdown_window("14:59", Some("13:00"), Some("15:00")));
+        assert!(!in_shutdown_window("15:00", Some("13:00"), Some("15:00")));
+    }
+
+    #[test]
+    fn window_wraps_past_midnight() {
+        assert!(!in_shutdown_window("21:59", Some("22:00"), Some("08:00")));
+        assert!(in_shutdown_window("22:00", Some("22:00"), Some("08:00")));
+        assert!(in_shutdown_window("00:00", Some("22:00"), Some("08:00")));
+        assert!(in_shutdown_window("07:59", Some("22:00"), Some("08:00")));
+        assert!(!in_shutdown_window("08:00", Some("22:00"), Some("08:00")));
+        assert!(!in_shutdown_window("12:00", Some("22:00"), Some("08:00")));
+    }
+    #[test]
+    fn no_wake_time_never_wakes() {
+        assert!(!in_wake_window("09:00", None, Some("22:00")));
+    }
+
+    #[test]
+    fn wake_window_runs_until_shutdown() {
+        assert!(!in_wake_window("07:59", Some("08:00"), Some("22:00")));
+        assert!(in_wake_window("08:00", Some("08:00"), Some("22:00")));
+        assert!(in_wake_window("21:59", Some("08:00"), Some("22:00")));
+        // a restart or a new policy late at night wakes nothing
+        assert!(!in_wake_window("23:30", Some("08:00"), Some("22:00")));
+    }
+
+    #[test]
+    fn wake_window_wraps_past_midnight() {
+        assert!(in_wake_window("17:30", Some("17:30"), Some("04:00")));
+        assert!(in_wake_window("01:00", Some("17:30"), Some("04:00")));
+        assert!(!in_wake_window("04:00", Some("17:30"), Some("04:00")));
+        assert!(!in_wake_window("12:00", Some("17:30"), Some("04:00")));
+    }
+}
\ No newline at end of file
@@ -0,0 +1,180 @@
+use crate::models::receipt::{Receipt, ReceiptDetail};
+use crate::services::receipt_service::SETTLED_FROM_PREPAID;
+
+const SHOP_NAME: &str = "Godfather Gaming";
+// characters per line in font A on 80mm thermal paper
+const ESCPOS_WIDTH: usize = 42;
+const PDF_WIDTH: usize = 60;
+
+fn format_money(amount: i64, currency: &str) -> String {
+    let sign = if amount < 0 { "-" } else { "" };
+    format!("{}{}.{:02} {}", sign, amount.abs() / 100, amount.abs() % 100, currency)
+}
+
+fn html_escape(text: &str) -> String {
+    text.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+}
+
+/// How the total was paid, for receipts not paid at the desk.
+fn settlement_note(receipt: &Receipt) -> Option<&'static str> {
+    match receipt.settled_by.as_deref() {
+        Some(SETTLED_FROM_PREPAID) => Some("Settled from prepaid minutes"),
+        _ => None,
+    }
+}
+
+/// Left text and right-aligned amount on one fixed-width line.
+fn columns(left: &str, right: &str, width: usize) -> String {
+    let room = width.saturating_sub(right.len() + 1);
+    let left: String = left.chars().take(room).collect();
+    format!("{:<room$} {}", left, right, room = room)
+}
+
+/// The receipt as plain fixed-width lines, shared by the PDF and printer output.
+fn text_lines(detail: &ReceiptDetail, width: usize) -> Vec<String> {
+    let receipt = &detail.receipt;
+    let currency = &receipt.currency;
+    let rule = "-".repeat(width);
+
+    let mut lines = vec![
+        format!("Receipt {}", receipt.number),
+        receipt.created_at.clone(),
+        rule.clone(),
+    ];
+
+    for line in &detail.lines {
+        lines.push(line.description.clone());
+        lines.push(columns(
+            &format!("  {} x {}", line.quantity, format_money(line.unit_price, currency)),
+            &format_money(line.amount, currency),
+            width,
+        ));
+    }
+
+    lines.push(rule);
+    lines.push(columns("Net", &format_money(receipt.subtotal, currency), width));
+    lines.push(columns(
+        &format!("Tax {}.{:02}%", receipt.tax_rate_bp / 100, receipt.tax_rate_bp % 100),
+        &format_money(receipt.tax, currency),
+        width,
+    ));
+    lines.push(columns("TOTAL", &format_money(receipt.total, currency), width));
+    if let Some(note) = settlement_note(receipt) {
+        lines.push(note.to_string());
+    }
+
+    lines
+}
+
+pub fn render_html(detail: &ReceiptDetail) -> String {
+    let receipt = &detail.receipt;
+    let currency = &receipt.currency;
+
+    let rows: String = detail.lines.iter()
+        .map(|line| format!(
+            "<tr><td>{}</td><td class=\"num\">{}</td><td class=\"num\">{}</td><td class=\"num\">{}</td></tr>",
+            html_escape(&line.description),
+            line.quantity,
+            format_money(line.unit_price, currency),
+            format_money(line.amount, currency),
+        ))
+        .collect();
+
+    format!(
+        "<!DOCTYPE html>
+<html><head><meta charset=\"utf-8\"><title>Receipt {number}</title>
+<style>body{{font-family:sans-serif;max-width:40em;margin:2em auto}}table{{width:100%;border-collapse:collapse}}
+td,th{{padding:.3em;border-bottom:1px solid #ddd;text-align:left}}.num{{text-align:right}}</style></head>
+<body><h1>{shop}</h1><p>Receipt <strong>{number}</strong><br>{date}</p>
+<table><thead><tr><th>Item</th><th class=\"num\">Qty</th><th class=\"num\">Unit</th><th class=\"num\">Amount</th></tr></thead>
+<tbody>{rows}</tbody>
+<tfoot><tr><td colspan=\"3\">Net</td><td class=\"num\">{net}</td></tr>
+<tr><td colspan=\"3\">Tax {rate}.{rate_frac:02}%</td><td class=\"num\">{tax}</td></tr>
+<tr><th colspan=\"3\">Total</th><th class=\"num\">{total}</th></tr></tfoot></table>
+{settlement}</body></html>",
+        shop = SHOP_NAME,
+        number = html_escape(&receipt.number),
+        date = html_escape(&receipt.created_at),
+        rows = rows,
+        net = format_money(receipt.subtotal, currency),
+        rate = receipt.tax_rate_bp / 100,
+        rate_frac = receipt.tax_rate_bp % 100,
+        tax = format_money(receipt.tax, currency),
+        total = format_money(receipt.total, currency),
+        settlement = settlement_note(receipt).map(|note| format!("<p>{}</p>\n", note)).unwrap_or_default(),
+    )
+}
+
+/// A single-page PDF using the built-in Courier font, so no font embedding is needed.
+pub fn render_pdf(detail: &ReceiptDetail) -> Vec<u8> {
+    let mut content = String::from("BT /F1 10 Tf 12 TL 50 800 Td\n");
+    content.push_str(&format!("({}) Tj T*\n", pdf_escape(SHOP_NAME)));
+    for line in text_lines(detail, PDF_WIDTH) {
+        content.push_str(&format!("({}) Tj T*\n", pdf_escape(&line)));
+    }
+    content.push_str("ET");
+
+    let objects = [
+        "<< /Type /Catalog /Pages 2 0 R >>".to_string(),
+        "<< /Type /Pages /Kids [3 0 R] /Count 1 >>".to_string(),
+        "<< /Type /Page /Parent 2 0 R /MediaBox [0 0 595 842] /Contents 4 0 R /Resources << /Font << /F1 5 0 R >> >> >>".to_string(),
+        format!("<< /Length {} >>\nstream\n{}\nendstream", content.len(), content),
+        "<< /Type /Font /Subtype /Type1 /BaseFont /Courier >>".to_string(),
+    ];
+
+    let mut pdf = String::from("%PDF-1.4\n");
+    let mut offsets = Vec::with_capacity(objects.len());
+    for (i, object) in objects.iter().enumerate() {
+        offsets.push(pdf.len());
+        pdf.push_str(&format!("{} 0 obj\n{}\nendobj\n", i + 1, object));
+    }
+
+    let xref_offset = pdf.len();
+    pdf.push_str(&format!("xref\n0 {}\n0000000000 65535 f \n", objects.len() + 1));
+    for offset in offsets {
+        pdf.push_str(&format!("{:010} 00000 n \n", offset));
+    }
+    pdf.push_str(&format!(
+        "trailer\n<< /Size {} /Root 1 0 R >>\nstartxref\n{}\n%%EOF\n",
+        objects.len() + 1,
+        xref_offset
+    ));
+
+    pdf.into_bytes()
+}
+
+fn pdf_escape(text: &str) -> String {
+    text.chars()
+        .map(|c| if c.is_ascii() { c } else { '?' })
+        .collect::<String>()
+        .replace('\\', "\\\\")
+        .replace('(', "\\(")
+        .replace(')', "\\)")
+}
+
+/// Raw ESC/POS bytes for the front desk thermal printer, ending with a paper cut.
+pub fn render_escpos(detail: &ReceiptDetail) -> Vec<u8> {
+    const ESC: u8 = 0x1b;
+    const GS: u8 = 0x1d;
+
+    let mut out = vec![ESC, b'@'];
+
+    out.extend_from_slice(&[ESC, b'a', 1, ESC, b'E', 1]);
+    out.extend_from_slice(SHOP_NAME.as_bytes());
+    out.push(b'\n');
+    out.extend_from_slice(&[ESC, b'E', 0, ESC, b'a', 0]);
+
+    for line in text_lines(detail, ESCPOS_WIDTH) {
+        let ascii: String = line.chars().map(|c| if c.is_ascii() { c } else { '?' }).collect();
+        out.extend_from_slice(ascii.as_bytes());
+        out.push(b'\n');
+    }
+
+    out.extend_from_slice(&[ESC, b'd', 4]);
+    out.extend_from_slice(&[GS, b'V', 66, 0]);
+
+    out
+}
@@ -0,0 +1,205 @@
+use sqlx::SqliteConnection;
+use crate::models::receipt::{Receipt, ReceiptDetail, ReceiptLine};
+
+const DEFAULT_MINUTE_RATE: i64 = 5;
+const DEFAULT_TAX_RATE_BP: i64 = 2000;
+const DEFAULT_CURRENCY: &str = "EUR";
+
+pub const SETTLED_FROM_PREPAID: &str = "prepaid_minutes";
+
+/// A line item before it is written to a receipt.
+pub struct LineItem {
+    pub description: String,
+    pub quantity: i64,
+    pub unit_price: i64,
+}
+
+impl LineItem {
+    pub fn new(description: impl Into<String>, quantity: i64, unit_price: i64) -> Self {
+        Self { description: description.into(), quantity, unit_price }
+    }
+}
+
+/// Price of one minute of playtime in minor currency units.
+pub fn minute_rate() -> i64 {
+    std::env::var("MINUTE_RATE")
+        .ok()
+        .and_then(|v| v.parse().ok())
+        .unwrap_or(DEFAULT_MINUTE_RATE)
+}
+
+fn tax_rate_bp() -> i64 {
+    std::env::var("TAX_RATE_BP")
+        .ok()
+        .and_then(|v| v.parse().ok())
+        .unwrap_or(DEFAULT_TAX_RATE_BP)
+}
+
+fn currency() -> String {
+    std::env::var("CURRENCY").unwrap_or_else(|_| DEFAULT_CURRENCY.to_string())
+}
+
+/// Writes a sequentially numbered receipt. Prices are tax inclusive, so the
+/// tax is the portion of the total attributable to the configured rate.
+pub async fn issue_receipt(
+    conn: &mut SqliteConnection,
+    user_id: Option<i64>,
+    kind: &str,
+    reference_id: Option<i64>,
+    items: Vec<LineItem>,
+) -> Result<Receipt, sqlx::Error> {
+    write_receipt(conn, user_id, kind, reference_id, items, None).await
+}
+
+/// Receipt for an ended session, settled from minutes the user prepaid on an
+/// earlier top-up rather than paid at the desk.
+pub async fn issue_session_receipt(
+    conn: &mut SqliteConnection,
+    user_id: i64,
+    session_id: i64,
+    items: Vec<LineItem>,
+) -> Result<Receipt, sqlx::Error> {
+    write_receipt(conn, Some(user_id), "session", Some(session_id), items, Some(SETTLED_FROM_PREPAID)).await
+}
+
+async fn write_receipt(
+    conn: &mut SqliteConnection,
+    user_id: Option<i64>,
+    kind: &str,
+    reference_id: Option<i64>,
+    items: Vec<LineItem>,
+    settled_by: Option<&str>,
+) -> Result<Receipt, sqlx::Error> {
+    let rate_bp = tax_rate_bp();
+    let total: i64 = items.iter().map(|i| i.quantity * i.unit_price).sum();
+    let tax = total * rate_bp / (10_000 + rate_bp);
+
+    // bumping the counter takes the write lock, so numbers never repeat or skip
+    let sequence: i64 = sqlx::query_scalar(
+        "UPDATE receipt_counter SET last_number = last_number + 1 WHERE id = 1 RETURNING last_number",
+    )
+    .fetch_one(&mut *conn)
+    .await?;
+
+    let receipt = sqlx::query_as::<_, Receipt>(
+        "INSERT INTO receipts (number, user_id, kind, reference_id, subtotal, tax, total, tax_rate_bp, currency, settled_by)
+         VALUES (?, ?, ?, ?, ?, ?, ?, ?, ?, ?)
+         RETURNING id, number, user_id, kind, reference_id, subtotal, tax, total, tax_rate_bp, currency, settled_by, created_at",
+    )
+    .bind(format!("R-{:06}", sequence))
+    .bind(user_id)
+    .bind(kind)
+    .bind(reference_id)
+    .bind(total - tax)
+    .bind(tax)
+    .bind(total)
+    .bind(rate_bp)
+    .bind(currency())
+    .bind(settled_by)
+    .fetch_one(&mut *conn)
+    .await?;
+
+    for item in items {
+        sqlx::query(
+            "INSERT INTO receipt_lines (receipt_id, description, quantity, unit_price, amount) VALUES (?, ?, ?, ?, ?)",
+        )
+        .bind(receipt.id)
+        .bind(&item.description)
+        .bind(item.quantity)
+        .bind(item.unit_price)
+        .bind(item.quantity * item.unit_price)
+        .execute(&mut *conn)
+        .await?;
+    }
+
+    Ok(receipt)
+}
+
+/// Itemised statement for an ended session. Billable minutes are priced at
+/// the tariff and the discount or staff override is taken off as its own line,
+/// so the total is what the charged minutes are worth. Plan allowance was paid
+/// for on the subscription receipt and is listed at 0.
+pub fn session_line_items(
+    machine_name: &str,
+    minutes_consumed: i64,
+    included_minutes: i64,
+    charged_minutes: i64,
+    discount_label: &str,
+) -> Vec<LineItem> {
+    let rate = minute_rate();
+    let billable = minutes_consumed - included_minutes;
+    let mut items = Vec::new();
+
+    if included_minutes > 0 {
+        items.push(LineItem::new(format!("Playtime on {} (plan allowance)", machine_name), included_minutes, 0));
+    }
+    if billable > 0 {
+        items.push(LineItem::new(format!("Playtime on {} (tariff)", machine_name), billable, rate));
+    }
+    if charged_minutes < billable {
+        items.push(LineItem::new(format!("{} (minutes saved)", discount_label), billable - charged_minutes, -rate));
+    } else if charged_minutes > billable {
+        items.push(LineItem::new(format!("{} (minutes added)", discount_label), charged_minutes - billable, rate));
+    }
+
+    items
+}
+
+pub async fn load_receipt(
+    conn: &mut SqliteConnection,
+    receipt_id: i64,
+) -> Result<Option<ReceiptDetail>, sqlx::Error> {
+    let receipt = sqlx::query_as::<_, Receipt>(
+        "SELECT id, number, user_id, kind, reference_id, subtotal, tax, total, tax_rate_bp, currency, settled_by, created_at
+         FROM receipts WHERE id = ?",
+    )
+    .bind(receipt_id)
+    .fetch_optional(&mut *conn)
+    .await?;
+
+    let Some(receipt) = receipt else {
+        return Ok(None);
+    };
+
+    let lines = sqlx::query_as::<_, ReceiptLine>(
+        "SELECT id, receipt_id, description, quantity, unit_price, amount FROM receipt_lines WHERE receipt_id = ? ORDER BY id",
+    )
+    .bind(receipt.id)
+    .fetch_all(&mut *conn)
+    .await?;
+
+    Ok(Some(ReceiptDetail { receipt, lines }))
+}
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn total(items: &[LineItem]) -> i64 {
+        items.iter().map(|i| i.quantity * i.unit_price).sum()
+    }
+
+    #[test]
+    fn session_lines_total_the_charged_minutes() {
+        let items = session_line_items("PC-01", 90, 30, 45, "Member and loyalty discount");
+        assert_eq!(items.len(), 3);
+        assert_eq!(items[1].quantity, 60);
+        assert_eq!(items[2].quantity, 15);
+        assert_eq!(total(&items), 45 * minute_rate());
+    }
+
+    #[test]
+    fn staff_override_above_usage_is_added() {
+        let items = session_line_items("PC-01", 10, 0, 25, "Staff adjustment");
+        assert_eq!(items.len(), 2);
+        assert_eq!(total(&items), 25 * minute_rate());
+    }
+
+    #[test]
+    fn fully_included_session_totals_zero() {
+        let items = session_line_items("PC-01", 30, 30, 0, "Member and loyalty discount");
+        assert_eq!(items.len(), 1);
+        assert_eq!(total(&items), 0);
+    }
+}
\ No newline at end of file
@@ -0,0 +1,102 @@
+use chrono::NaiveDateTime;
+use sqlx::SqlitePool;
+use crate::{
+    models::reconciliation::{ReconciledMachine, ReconciledSession, ReconciliationReport},
+    services::{machine_service, session_service::{self, SessionEnd}},
+};
+
+// a machine that has not sent a heartbeat for this long is considered gone
+const DEFAULT_SILENT_MINUTES: i64 = 5;
+
+pub fn silent_minutes() -> i64 {
+    std::env::var("MACHINE_SILENT_MINUTES")
+        .ok()
+        .and_then(|v| v.parse().ok())
+        .unwrap_or(DEFAULT_SILENT_MINUTES)
+}
+
+/// Closes sessions whose machine stopped sending heartbeats, billed up to the
+/// last heartbeat, then brings machine statuses back in line with the open
+/// sessions. Runs once agents have had time to report in after startup, and
+/// on demand.
+pub async fn reconcile(pool: &SqlitePool) -> Result<ReconciliationReport, sqlx::Error> {
+    let orphaned: Vec<(i64, i64, i64, String)> = sqlx::query_as(
+        "SELECT s.id, s.user_id, s.machine_id, MAX(COALESCE(m.last_seen_at, s.started_at), s.started_at)
+         FROM sessions s
+         JOIN machines m ON m.id = s.machine_id
+         WHERE s.ended_at IS NULL
+           AND (m.last_seen_at IS NULL OR m.last_seen_at <= datetime('now', ?))
+         ORDER BY s.id",
+    )
+    .bind(format!("-{} minutes", silent_minutes()))
+    .fetch_all(pool)
+    .await?;
+
+    let mut sessions = Vec::with_capacity(orphaned.len());
+    for (session_id, user_id, machine_id, last_seen) in orphaned {
+        let end = SessionEnd {
+            ended_at: NaiveDateTime::parse_from_str(&last_seen, "%Y-%m-%d %H:%M:%S").ok(),
+            ..SessionEnd::new("machine_offline")
+        };
+
+        let reconciled = match session_service::close_session(pool, session_id, end).await {
+            Ok(closed) => ReconciledSession {
+                session_id,
+                user_id,
+                machine_id,
+                ended_at: closed.session.ended_at,
+                minutes_consumed: closed.session.minutes_consumed,
+                charged_minutes: closed.session.charged_minutes,
+                error: None,
+            },
+            Err((_, e)) => ReconciledSession {
+                session_id,
+                user_id,
+                machine_id,
+                ended_at: None,
+                minutes_consumed: 0,
+                charged_minutes: 0,
+                error: Some(e),
+            },
+        };
+
+        sessions.push(reconciled);
+    }
+
+    // occupied machines without a session, and machines whose session is still open
+    // but whose status was overwritten
+    let mismatched: Vec<(i64, String, String, bool)> = sqlx::query_as(
+        "SELECT m.id, m.name, m.status, EXISTS (SELECT 1 FROM sessions s WHERE s.machine_id = m.id AND s.ended_at IS NULL)
+         FROM machines m
+         WHERE (m.status = 'in_use' AND NOT EXISTS (SELECT 1 FROM sessions s WHERE s.machine_id = m.id AND s.ended_at IS NULL))
+            OR (m.status NOT IN ('in_use', 'maintenance') AND EXISTS (SELECT 1 FROM sessions s WHERE s.machine_id = m.id AND s.ended_at IS NULL))
+         ORDER BY m.name",
+    )
+    .fetch_all(pool)
+    .await?;
+
+    let mut machines = Vec::with_capacity(mismatched.len());
+    for (machine_id, name, previous_status, occupied) in mismatched {
+        let mut tx = pool.begin().await?;
+
+        if occupied {
+            sqlx::query("UPDATE machines SET status = 'in_use' WHERE id = ?")
+                .bind(machine_id)
+                .execute(&mut *tx)
+                .await?;
+        } else {
+            machine_service::release_machine(&mut tx, machine_id).await?;
+        }
+
+        let status: String = sqlx::query_scalar("SELECT status FROM machines WHERE id = ?")
+            .bind(machine_id)
+            .fetch_one(&mut *tx)
+            .await?;
+
+        tx.commit().await?;
+
+        machines.push(ReconciledMachine { machine_id, name, previous_status, status });
+    }
+
+    Ok(ReconciliationReport { sessions, machines })
+}
\ No newline at end of file
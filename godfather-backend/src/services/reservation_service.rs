@@ -0,0 +1,197 @@
+use axum::http::StatusCode;
+use chrono::NaiveDateTime;
+use sqlx::{SqliteConnection, SqlitePool};
+use crate::services::{machine_service, notification_service};
+
+// machines are held this long before a slot starts
+const DEFAULT_HOLD_MINUTES: i64 = 15;
+// a held machine is released if nobody checks in this long after the slot starts
+const DEFAULT_GRACE_MINUTES: i64 = 15;
+
+fn hold_minutes() -> i64 {
+    std::env::var("RESERVATION_HOLD_MINUTES")
+        .ok()
+        .and_then(|v| v.parse().ok())
+        .unwrap_or(DEFAULT_HOLD_MINUTES)
+}
+
+fn grace_minutes() -> i64 {
+    std::env::var("RESERVATION_GRACE_MINUTES")
+        .ok()
+        .and_then(|v| v.parse().ok())
+        .unwrap_or(DEFAULT_GRACE_MINUTES)
+}
+
+pub fn validate_slot(starts_at: &str, ends_at: &str) -> Result<(), (StatusCode, String)> {
+    let parse = |value: &str| {
+        NaiveDateTime::parse_from_str(value, "%Y-%m-%d %H:%M:%S")
+            .map_err(|_| (StatusCode::BAD_REQUEST, "Times must be formatted as YYYY-MM-DD HH:MM:SS".to_string()))
+    };
+
+    let starts = parse(starts_at)?;
+    let ends = parse(ends_at)?;
+
+    if ends <= starts {
+        return Err((StatusCode::BAD_REQUEST, "End time must be after start time".to_string()));
+    }
+
+    Ok(())
+}
+
+/// True when no live reservation or maintenance window on the machine overlaps the slot.
+pub async fn machine_is_free(
+    conn: &mut SqliteConnection,
+    machine_id: i64,
+    starts_at: &str,
+    ends_at: &str,
+) -> Result<bool, sqlx::Error> {
+    let conflict = sqlx::query(
+        "SELECT 1 FROM reservations
+         WHERE machine_id = ? AND status IN ('booked', 'held', 'checked_in') AND starts_at < ? AND ends_at > ?
+         UNION ALL
+         SELECT 1 FROM maintenance_windows
+         WHERE machine_id = ? AND starts_at < ? AND ends_at > ?
+         LIMIT 1",
+    )
+    .bind(machine_id)
+    .bind(ends_at)
+    .bind(starts_at)
+    .bind(machine_id)
+    .bind(ends_at)
+    .bind(starts_at)
+    .fetch_optional(conn)
+    .await?;
+
+    Ok(conflict.is_none())
+}
+
+/// Checks a reserved machine before a session starts on it. Returns the
+/// reservation the user is checking in to, or refuses anyone else.
+pub async fn check_in(
+    conn: &mut SqliteConnection,
+    machine_id: i64,
+    user_id: i64,
+) -> Result<Option<i64>, (StatusCode, String)> {
+    let held: Option<(i64, i64)> = sqlx::query_as(
+        "SELECT id, user_id FROM reservations WHERE machine_id = ? AND status = 'held' ORDER BY starts_at LIMIT 1",
+    )
+    .bind(machine_id)
+    .fetch_optional(&mut *conn)
+    .await
+    .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, format!("Database error: {}", e)))?;
+
+    let Some((reservation_id, holder_id)) = held else {
+        return Ok(None);
+    };
+
+    if holder_id != user_id {
+        return Err((StatusCode::FORBIDDEN, "Machine is reserved for another customer".to_string()));
+    }
+
+    sqlx::query("UPDATE reservations SET status = 'checked_in', checked_in_at = datetime('now') WHERE id = ?")
+        .bind(reservation_id)
+        .execute(&mut *conn)
+        .await
+        .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, format!("Failed to check in: {}", e)))?;
+
+    Ok(Some(reservation_id))
+}
+
+/// True when a booked slot on the machine starts within the hold window, in
+/// which case the machine should stay free for that booking.
+pub async fn has_upcoming_booking(conn: &mut SqliteConnection, machine_id: i64) -> Result<bool, sqlx::Error> {
+    Ok(upcoming_booking_holder(conn, machine_id).await?.is_some())
+}
+
+/// The customer whose booked slot on the machine starts within the hold window.
+pub async fn upcoming_booking_holder(conn: &mut SqliteConnection, machine_id: i64) -> Result<Option<i64>, sqlx::Error> {
+    sqlx::query_scalar(
+        "SELECT user_id FROM reservations
+         WHERE machine_id = ? AND status = 'booked' AND starts_at <= datetime('now', ?) AND ends_at > datetime('now')
+         ORDER BY starts_at
+         LIMIT 1",
+    )
+    .bind(machine_id)
+    .bind(format!("+{} minutes", hold_minutes()))
+    .fetch_optional(conn)
+    .await
+}
+
+/// Holds machines for upcoming slots, releases no-shows and closes out
+/// reservations whose slot has passed. Run by the scheduler.
+pub async fn run_holds(pool: &SqlitePool) -> Result<(), sqlx::Error> {
+    let due: Vec<(i64, i64, i64, String)> = sqlx::query_as(
+        "SELECT r.id, r.user_id, r.machine_id, m.name
+         FROM reservations r
+         JOIN machines m ON m.id = r.machine_id
+         WHERE r.status = 'booked'
+           AND r.starts_at <= datetime('now', ?)
+           AND r.ends_at > datetime('now')
+           AND m.status = 'available'
+         ORDER BY r.starts_at",
+    )
+    .bind(format!("+{} minutes", hold_minutes()))
+    .fetch_all(pool)
+    .await?;
+
+    for (reservation_id, user_id, machine_id, machine_name) in due {
+        let mut tx = pool.begin().await?;
+
+        let held = sqlx::query("UPDATE machines SET status = 'reserved' WHERE id = ? AND status = 'available'")
+            .bind(machine_id)
+            .execute(&mut *tx)
+            .await?;
+
+        if held.rows_affected() == 1 {
+            sqlx::query("UPDATE reservations SET status = 'held' WHERE id = ?")
+                .bind(reservation_id)
+                .execute(&mut *tx)
+                .await?;
+
+            let message = format!("{} is now held for your reservation", machine_name);
+            notification_service::notify_user(&mut tx, user_id, "reservation_held", &message).await?;
+        }
+
+        tx.commit().await?;
+    }
+
+    let no_shows: Vec<(i64, i64, i64)> = sqlx::query_as(
+        "SELECT id, user_id, machine_id FROM reservations
+         WHERE status = 'held' AND starts_at <= datetime('now', ?)",
+    )
+    .bind(format!("-{} minutes", grace_minutes()))
+    .fetch_all(pool)
+    .await?;
+
+    for (reservation_id, user_id, machine_id) in no_shows {
+        let mut tx = pool.begin().await?;
+
+        sqlx::query("UPDATE reservations SET status = 'no_show' WHERE id = ?")
+            .bind(reservation_id)
+            .execute(&mut *tx)
+            .await?;
+
+        machine_service::release_machine(&mut tx, machine_id).await?;
+
+        notification_service::notify_user(
+            &mut tx,
+            user_id,
+            "reservation_no_show",
+            "Your reservation was released because you did not check in",
+        )
+        .await?;
+
+        tx.commit().await?;
+    }
+
+    // slots that passed without the machine ever freeing up, and finished stays
+    sqlx::query("UPDATE reservations SET status = 'expired' WHERE status = 'booked' AND ends_at <= datetime('now')")
+        .execute(pool)
+        .await?;
+
+    sqlx::query("UPDATE reservations SET status = 'completed' WHERE status = 'checked_in' AND ends_at <= datetime('now')")
+        .execute(pool)
+        .await?;
+
+    Ok(())
+}
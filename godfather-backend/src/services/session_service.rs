@@ -0,0 +1,500 @@
+use axum::http::StatusCode;
+use chrono::{DateTime, NaiveDateTime, Utc};
+use sqlx::{SqliteConnection, SqlitePool};
+use crate::{
+    models::{machine::Machine, session::Session, subscription::ActiveSubscription},
+    services::{
+        app_usage_service, billing_service, license_service, loyalty_service, machine_service, notification_service,
+        power_service, receipt_service, reservation_service, subscription_service, waitlist_service,
+    },
+};
+
+// a pause running longer than this ends the session
+const DEFAULT_MAX_PAUSE_MINUTES: i64 = 15;
+const DEFAULT_MAX_PAUSES: i64 = 2;
+
+pub fn max_pause_minutes() -> i64 {
+    std::env::var("SESSION_MAX_PAUSE_MINUTES")
+        .ok()
+        .and_then(|v| v.parse().ok())
+        .unwrap_or(DEFAULT_MAX_PAUSE_MINUTES)
+}
+
+pub fn max_pauses() -> i64 {
+    std::env::var("SESSION_MAX_PAUSES")
+        .ok()
+        .and_then(|v| v.parse().ok())
+        .unwrap_or(DEFAULT_MAX_PAUSES)
+}
+
+pub const END_REASONS: &[&str] = &[
+    "user_logout",
+    "balance_exhausted",
+    "staff_forced",
+    "machine_offline",
+    "ban",
+    "crash",
+    "pause_expired",
+];
+
+/// How and why a session is being ended.
+pub struct SessionEnd {
+    pub reason: String,
+    // backdates the end, as when a machine went silent; otherwise the session ends now
+    pub ended_at: Option<NaiveDateTime>,
+    // staff member forcing the end
+    pub ended_by: Option<i64>,
+    // staff override of the charge, replacing plan allowance and discounts
+    pub charged_minutes: Option<i64>,
+    // lock the seat so the customer cannot keep playing, lifted when the next session opens
+    pub lock_machine: bool,
+}
+
+impl SessionEnd {
+    pub fn new(reason: &str) -> Self {
+        Self { reason: reason.to_string(), ended_at: None, ended_by: None, charged_minutes: None, lock_machine: false }
+    }
+}
+
+pub fn validate_end_reason(reason: &str) -> Result<(), (StatusCode, String)> {
+    if END_REASONS.contains(&reason) {
+        Ok(())
+    } else {
+        Err((StatusCode::BAD_REQUEST, format!("End reason must be one of {}", END_REASONS.join(", "))))
+    }
+}
+
+pub struct ClosedSession {
+    pub session: Session,
+    pub minutes_played: i64,
+}
+
+/// Splits elapsed minutes into minutes covered by the plan allowance and
+/// minutes charged to the balance. The rest is discounted by the member or
+/// loyalty tier discount, whichever is greater.
+pub fn session_charge(
+    minutes: i64,
+    subscription: Option<&ActiveSubscription>,
+    machine_class: &str,
+    tier_discount_percent: i64,
+) -> (i64, i64) {
+    let (included, member_discount) = match subscription.filter(|s| s.covers(machine_class)) {
+        Some(sub) => (minutes.min(sub.remaining_minutes()), sub.extra_discount_percent),
+        None => (0, 0),
+    };
+
+    let discount = member_discount.max(tier_discount_percent);
+    let charged = ((minutes - included) * (100 - discount) + 99) / 100;
+
+    (included, charged)
+}
+
+/// Refuses a machine the user's session may not run on: one in a maintenance
+/// window, one on a blocked agent version, or one not yet held whose booked
+/// slot for someone else is about to start. Shared by opening and transferring.
+pub async fn check_machine_usable(
+    conn: &mut SqliteConnection,
+    machine: &Machine,
+    user_id: i64,
+) -> Result<(), (StatusCode, String)> {
+    let db_err = |e: sqlx::Error| (StatusCode::INTERNAL_SERVER_ERROR, format!("Database error: {}", e));
+
+    let in_maintenance = sqlx::query(
+        "SELECT 1 FROM maintenance_windows WHERE machine_id = ? AND starts_at <= datetime('now') AND ends_at > datetime('now')",
+    )
+    .bind(machine.id)
+    .fetch_optional(&mut *conn)
+    .await
+    .map_err(db_err)?;
+
+    if in_maintenance.is_some() {
+        return Err((StatusCode::CONFLICT, format!("{} is in a scheduled maintenance window", machine.name)));
+    }
+
+    let blocked_version: Option<String> = sqlx::query_scalar(
+        "SELECT m.agent_version FROM machines m
+         JOIN agent_blocked_versions b ON b.version = m.agent_version
+         WHERE m.id = ?",
+    )
+    .bind(machine.id)
+    .fetch_optional(&mut *conn)
+    .await
+    .map_err(db_err)?;
+
+    if let Some(version) = blocked_version {
+        return Err((StatusCode::CONFLICT, format!("{} runs blocked agent version {} and must update first", machine.name, version)));
+    }
+
+    // a held machine is checked against its holder instead
+    if machine.status != "reserved" {
+        let holder = reservation_service::upcoming_booking_holder(conn, machine.id).await.map_err(db_err)?;
+        if holder.is_some_and(|holder_id| holder_id != user_id) {
+            return Err((StatusCode::FORBIDDEN, format!("{} is booked for another customer shortly", machine.name)));
+        }
+    }
+
+    Ok(())
+}
+
+/// Opens a session for the user on the machine after checking the machine is
+/// free, the user has no other session running and can pay for playtime.
+/// Reserved machines, and machines whose booked slot is about to start, only
+/// open for the reservation or waitlist offer holder.
+pub async fn open_session(
+    pool: &SqlitePool,
+    user_id: i64,
+    machine_id: i64,
+) -> Result<(Session, Machine), (StatusCode, String)> {
+    // balance check
+    let user_minutes: i64 = sqlx::query_scalar(
+        "SELECT minutes_balance + bonus_minutes FROM users WHERE id = ?",
+    )
+    .bind(user_id)
+    .fetch_one(pool)
+    .await
+    .map_err(|_| (StatusCode::NOT_FOUND, "User not found".to_string()))?;
+
+    // machine check, reserved machines only open for the reservation holder
+    let machine: Machine = sqlx::query_as(
+        "SELECT id, name, status, class, zone FROM machines WHERE id = ? AND status IN ('available', 'reserved')",
+    )
+    .bind(machine_id)
+    .fetch_optional(pool)
+    .await
+    .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, format!("Database error: {}", e)))?
+    .ok_or((StatusCode::NOT_FOUND, "Machine not found or not available".to_string()))?;
+
+    let running_on_machine = sqlx::query("SELECT 1 FROM sessions WHERE machine_id = ? AND ended_at IS NULL")
+        .bind(machine_id)
+        .fetch_optional(pool)
+        .await
+        .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, format!("Database error: {}", e)))?;
+
+    if running_on_machine.is_some() {
+        return Err((StatusCode::CONFLICT, format!("{} already has an active session", machine.name)));
+    }
+
+    // checking in to a group reservation lets one customer run several machines
+    let group_booking_id: Option<i64> = sqlx::query_scalar(
+        "SELECT group_id FROM reservations WHERE machine_id = ? AND user_id = ? AND status = 'held'",
+    )
+    .bind(machine_id)
+    .bind(user_id)
+    .fetch_optional(pool)
+    .await
+    .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, format!("Database error: {}", e)))?
+    .flatten();
+
+    if group_booking_id.is_none() {
+        let running_for_user: Option<String> = sqlx::query_scalar(
+            "SELECT m.name FROM sessions s JOIN machines m ON m.id = s.machine_id
+             WHERE s.user_id = ? AND s.ended_at IS NULL AND s.group_booking_id IS NULL",
+        )
+        .bind(user_id)
+        .fetch_optional(pool)
+        .await
+        .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, format!("Database error: {}", e)))?;
+
+        if let Some(name) = running_for_user {
+            return Err((StatusCode::CONFLICT, format!("User already has an active session on {}", name)));
+        }
+    }
+
+    if user_minutes <= 0 {
+        // members may still play on their plan allowance
+        let mut conn = pool.acquire()
+            .await
+            .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, format!("Database error: {}", e)))?;
+        let subscription = subscription_service::active_subscription(&mut conn, user_id).await?;
+        let has_allowance = subscription
+            .is_some_and(|sub| sub.covers(&machine.class) && sub.remaining_minutes() > 0);
+
+        if !has_allowance {
+            return Err((StatusCode::FORBIDDEN, "Insufficient balance".to_string()));
+        }
+    }
+
+    let db_err = |e: sqlx::Error| (StatusCode::INTERNAL_SERVER_ERROR, format!("Failed to start session: {}", e));
+
+    // the check-in or offer claim, the session, its first segment and the
+    // machine status land together
+    let mut tx = pool.begin().await.map_err(db_err)?;
+
+    check_machine_usable(&mut tx, &machine, user_id).await?;
+
+    if machine.status == "reserved" && reservation_service::check_in(&mut tx, machine.id, user_id).await?.is_none() {
+        waitlist_service::claim_offer(&mut tx, machine.id, user_id).await?;
+    }
+
+    let session: Session = sqlx::query_as(
+        "INSERT INTO sessions (user_id, machine_id, started_at, group_booking_id) 
+         VALUES (?, ?, datetime('now'), ?) 
+         RETURNING id, user_id, machine_id, started_at, ended_at, minutes_consumed, included_minutes, charged_minutes, paused_minutes, end_reason",
+    )
+    .bind(user_id)
+    .bind(machine_id)
+    .bind(group_booking_id)
+    .fetch_one(&mut *tx)
+    .await
+    .map_err(|e| match e {
+        // lost a race with another start on the same machine or for the same user
+        sqlx::Error::Database(ref d) if d.is_unique_violation() => {
+            if d.message().contains("sessions.machine_id") {
+                (StatusCode::CONFLICT, format!("{} already has an active session", machine.name))
+            } else {
+                (StatusCode::CONFLICT, "User already has an active session".to_string())
+            }
+        }
+        e => db_err(e),
+    })?;
+
+    sqlx::query("INSERT INTO session_segments (session_id, machine_id, started_at) VALUES (?, ?, ?)")
+        .bind(session.id)
+        .bind(session.machine_id)
+        .bind(&session.started_at)
+        .execute(&mut *tx)
+        .await
+        .map_err(db_err)?;
+
+    // machine used
+    sqlx::query("UPDATE machines SET status = 'in_use' WHERE id = ?")
+        .bind(machine_id)
+        .execute(&mut *tx)
+        .await
+        .map_err(db_err)?;
+
+    // a pause or forced end may have left the seat locked for the last customer
+    machine_service::queue_command(&mut tx, machine_id, "unlock").await.map_err(db_err)?;
+
+    // an idle shutdown queued for the machine must not reach it now, and one already off is woken
+    power_service::keep_awake(&mut tx, machine_id).await.map_err(db_err)?;
+
+    tx.commit().await.map_err(db_err)?;
+
+    Ok((session, machine))
+}
+
+/// Total time the session has spent paused, counting a running pause up to `until`.
+async fn paused_seconds(conn: &mut SqliteConnection, session_id: i64, until: &str) -> Result<i64, sqlx::Error> {
+    sqlx::query_scalar(
+        "SELECT COALESCE(SUM(strftime('%s', COALESCE(resumed_at, ?)) - strftime('%s', paused_at)), 0)
+         FROM session_pauses WHERE session_id = ?",
+    )
+    .bind(until)
+    .bind(session_id)
+    .fetch_one(conn)
+    .await
+}
+
+/// Ends an open session, bills it and records why it ended. The session always
+/// ends: time the user cannot cover leaves their minutes balance negative until
+/// money credited to the account buys it back at the minute rate.
+pub async fn close_session(
+    pool: &SqlitePool,
+    session_id: i64,
+    end: SessionEnd,
+) -> Result<ClosedSession, (StatusCode, String)> {
+    let mut tx = pool.begin()
+        .await
+        .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, format!("Database error: {}", e)))?;
+
+    let db_err = |e: sqlx::Error| (StatusCode::INTERNAL_SERVER_ERROR, format!("Failed to end session: {}", e));
+
+    // claiming the row is the first write, so racing ends wait for the lock
+    // here and then find the session already ended
+    let session = sqlx::query_as::<_, Session>(
+        "UPDATE sessions SET end_reason = ? WHERE id = ? AND ended_at IS NULL
+         RETURNING id, user_id, machine_id, started_at, ended_at, minutes_consumed, included_minutes, charged_minutes, paused_minutes, end_reason",
+    )
+    .bind(&end.reason)
+    .bind(session_id)
+    .fetch_optional(&mut *tx)
+    .await
+    .map_err(db_err)?;
+
+    let Some(session) = session else {
+        let exists = sqlx::query("SELECT 1 FROM sessions WHERE id = ?")
+            .bind(session_id)
+            .fetch_optional(&mut *tx)
+            .await
+            .map_err(db_err)?;
+
+        return Err(match exists {
+            Some(_) => (StatusCode::CONFLICT, "Session already ended".to_string()),
+            None => (StatusCode::NOT_FOUND, "Session not found".to_string()),
+        });
+    };
+
+    // calculate elapsed time
+    let started_at_naive = NaiveDateTime::parse_from_str(&session.started_at, "%Y-%m-%d %H:%M:%S")
+        .map_err(|_| (StatusCode::INTERNAL_SERVER_ERROR, "Invalid timestamp format".to_string()))?;
+
+    let started_at: DateTime<Utc> = DateTime::from_naive_utc_and_offset(started_at_naive, Utc);
+    let now: DateTime<Utc> = end.ended_at.map_or_else(Utc::now, |t| t.and_utc()).max(started_at);
+    let ended_at = now.format("%Y-%m-%d %H:%M:%S").to_string();
+
+    let duration = now.signed_duration_since(started_at);
+
+    // ending a paused session closes the pause, paused time is never billed
+    sqlx::query("UPDATE session_pauses SET resumed_at = MAX(paused_at, ?) WHERE session_id = ? AND resumed_at IS NULL")
+        .bind(&ended_at)
+        .bind(session.id)
+        .execute(&mut *tx)
+        .await
+        .map_err(db_err)?;
+
+    let paused_seconds = paused_seconds(&mut tx, session.id, &ended_at).await.map_err(db_err)?;
+    let paused_minutes = paused_seconds / 60;
+    let played_seconds = (duration.num_seconds() - paused_seconds).max(0);
+
+    let (bonus_minutes, carry_seconds): (i64, i64) = sqlx::query_as(
+        "SELECT bonus_minutes, billing_carry_seconds FROM users WHERE id = ?"
+    )
+    .bind(session.user_id)
+    .fetch_one(&mut *tx)
+    .await
+    .map_err(|_| (StatusCode::NOT_FOUND, "User not found".to_string()))?;
+
+    let policy = billing_service::load_policy(&mut tx).await.map_err(db_err)?;
+    let (minutes_consumed, carry_seconds) =
+        billing_service::billed_minutes(billing_service::billable_seconds(&policy, played_seconds), carry_seconds);
+
+    let (machine_name, machine_class): (String, String) = sqlx::query_as("SELECT name, class FROM machines WHERE id = ?")
+        .bind(session.machine_id)
+        .fetch_one(&mut *tx)
+        .await
+        .map_err(|_| (StatusCode::NOT_FOUND, "Machine not found".to_string()))?;
+
+    let subscription = subscription_service::active_subscription(&mut tx, session.user_id).await?;
+    let tier = loyalty_service::current_tier(&mut tx, session.user_id).await.map_err(db_err)?;
+    let (included_minutes, charged_minutes) = match end.charged_minutes {
+        Some(minutes) => (0, minutes.clamp(0, minutes_consumed)),
+        None => session_charge(
+            minutes_consumed,
+            subscription.as_ref(),
+            &machine_class,
+            tier.as_ref().map_or(0, |t| t.rate_discount_percent),
+        ),
+    };
+
+    // deduct from user balance, bonus minutes first, any shortfall is owed
+    let from_bonus = charged_minutes.min(bonus_minutes);
+    let minutes_played = played_seconds / 60;
+
+    // update user's lifetime playtime and balance
+    sqlx::query(
+        "UPDATE users SET lifetime_minutes = lifetime_minutes + ?, lifetime_hours = (lifetime_minutes + ?) / 60,
+                bonus_minutes = bonus_minutes - ?, minutes_balance = minutes_balance - ?, billing_carry_seconds = ?
+         WHERE id = ?"
+    )
+    .bind(minutes_played)
+    .bind(minutes_played)
+    .bind(from_bonus)
+    .bind(charged_minutes - from_bonus)
+    .bind(carry_seconds)
+    .bind(session.user_id)
+    .execute(&mut *tx)
+    .await
+    .map_err(db_err)?;
+
+    loyalty_service::refresh_tier(&mut tx, session.user_id).await.map_err(db_err)?;
+
+    if let Some(sub) = subscription.as_ref().filter(|_| included_minutes > 0) {
+        sqlx::query("UPDATE subscriptions SET minutes_used = minutes_used + ? WHERE id = ?")
+            .bind(included_minutes)
+            .bind(sub.id)
+            .execute(&mut *tx)
+            .await
+            .map_err(db_err)?;
+    }
+
+    // another path (logout, reconcile, pause expiry, ban, staff) may have ended it
+    // first, the rollback then undoes the billing above
+    let updated_session = sqlx::query_as::<_, Session>(
+        "UPDATE sessions SET ended_at = ?, minutes_consumed = ?, included_minutes = ?, charged_minutes = ?, paused_minutes = ?, end_reason = ?, ended_by = ?
+         WHERE id = ? AND ended_at IS NULL
+         RETURNING id, user_id, machine_id, started_at, ended_at, minutes_consumed, included_minutes, charged_minutes, paused_minutes, end_reason",
+    )
+    .bind(&ended_at)
+    .bind(minutes_consumed)
+    .bind(included_minutes)
+    .bind(charged_minutes)
+    .bind(paused_minutes)
+    .bind(&end.reason)
+    .bind(end.ended_by)
+    .bind(session.id)
+    .fetch_optional(&mut *tx)
+    .await
+    .map_err(db_err)?
+    .ok_or((StatusCode::CONFLICT, "Session already ended".to_string()))?;
+
+    receipt_service::issue_session_receipt(
+        &mut tx,
+        session.user_id,
+        session.id,
+        receipt_service::session_line_items(
+            &machine_name,
+            minutes_consumed,
+            included_minutes,
+            charged_minutes,
+            if end.charged_minutes.is_some() { "Staff adjustment" } else { "Member and loyalty discount" },
+        ),
+    )
+    .await
+    .map_err(db_err)?;
+
+    sqlx::query("UPDATE session_segments SET ended_at = ?, end_reason = 'session_end' WHERE session_id = ? AND ended_at IS NULL")
+        .bind(&ended_at)
+        .bind(session.id)
+        .execute(&mut *tx)
+        .await
+        .map_err(db_err)?;
+
+    app_usage_service::close_running(&mut tx, session.id, &ended_at).await.map_err(db_err)?;
+
+    license_service::return_for_session(&mut tx, session.id).await.map_err(db_err)?;
+
+    machine_service::release_machine(&mut tx, session.machine_id).await.map_err(db_err)?;
+
+    if end.lock_machine {
+        machine_service::queue_command(&mut tx, session.machine_id, "lock").await.map_err(db_err)?;
+    }
+
+    tx.commit().await.map_err(db_err)?;
+
+    Ok(ClosedSession {
+        session: updated_session,
+        minutes_played,
+    })
+}
+
+
+/// Ends sessions whose current pause has run past the limit. Run by the scheduler.
+pub async fn end_overlong_pauses(pool: &SqlitePool) -> Result<(), (StatusCode, String)> {
+    let db_err = |e: sqlx::Error| (StatusCode::INTERNAL_SERVER_ERROR, format!("Failed to end paused sessions: {}", e));
+
+    let overdue: Vec<(i64, i64)> = sqlx::query_as(
+        "SELECT s.id, s.user_id FROM session_pauses p
+         JOIN sessions s ON s.id = p.session_id
+         WHERE p.resumed_at IS NULL AND s.ended_at IS NULL AND p.paused_at <= datetime('now', ?)",
+    )
+    .bind(format!("-{} minutes", max_pause_minutes()))
+    .fetch_all(pool)
+    .await
+    .map_err(db_err)?;
+
+    for (session_id, user_id) in overdue {
+        match close_session(pool, session_id, SessionEnd::new("pause_expired")).await {
+            Ok(_) => {}
+            // ended by someone else since the query above
+            Err((StatusCode::CONFLICT, _)) => continue,
+            Err(e) => return Err(e),
+        }
+
+        let mut conn = pool.acquire().await.map_err(db_err)?;
+        let message = format!("Your session was ended because it was paused for more than {} minutes", max_pause_minutes());
+        notification_service::notify_user(&mut conn, user_id, "session_pause_expired", &message).await.map_err(db_err)?;
+    }
+
+    Ok(())
+}
\ No newline at end of file
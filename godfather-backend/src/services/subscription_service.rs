@@ -0,0 +1,176 @@
+use axum::http::StatusCode;
+use sqlx::{SqliteConnection, SqlitePool};
+use crate::{
+    models::subscription::{ActiveSubscription, Subscription, SubscriptionPlan},
+    services::receipt_service::{self, LineItem},
+};
+
+pub async fn active_subscription(
+    conn: &mut SqliteConnection,
+    user_id: i64,
+) -> Result<Option<ActiveSubscription>, (StatusCode, String)> {
+    sqlx::query_as::<_, ActiveSubscription>(
+        "SELECT s.id, p.included_minutes, s.minutes_used, p.extra_discount_percent, p.machine_classes
+         FROM subscriptions s
+         JOIN subscription_plans p ON p.id = s.plan_id
+         WHERE s.user_id = ? AND s.status = 'active' AND s.current_period_end > datetime('now')",
+    )
+    .bind(user_id)
+    .fetch_optional(conn)
+    .await
+    .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, format!("Failed to fetch subscription: {}", e)))
+}
+
+pub async fn subscribe(
+    pool: &SqlitePool,
+    user_id: i64,
+    plan_id: i64,
+) -> Result<Subscription, (StatusCode, String)> {
+    let plan = sqlx::query_as::<_, SubscriptionPlan>(
+        "SELECT id, name, price, period_days, included_minutes, extra_discount_percent, machine_classes, active, created_at
+         FROM subscription_plans WHERE id = ? AND active = 1",
+    )
+    .bind(plan_id)
+    .fetch_optional(pool)
+    .await
+    .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, format!("Database error: {}", e)))?
+    .ok_or((StatusCode::NOT_FOUND, "Plan not found".to_string()))?;
+
+    let mut tx = pool.begin()
+        .await
+        .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, format!("Database error: {}", e)))?;
+
+    let existing = sqlx::query("SELECT 1 FROM subscriptions WHERE user_id = ? AND status = 'active'")
+        .bind(user_id)
+        .fetch_optional(&mut *tx)
+        .await
+        .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, format!("Database error: {}", e)))?;
+
+    if existing.is_some() {
+        return Err((StatusCode::CONFLICT, "You already have an active subscription".to_string()));
+    }
+
+    let paid = sqlx::query("UPDATE users SET balance = balance - ? WHERE id = ? AND balance >= ?")
+        .bind(plan.price)
+        .bind(user_id)
+        .bind(plan.price)
+        .execute(&mut *tx)
+        .await
+        .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, format!("Failed to charge subscription: {}", e)))?;
+
+    if paid.rows_affected() == 0 {
+        return Err((StatusCode::PAYMENT_REQUIRED, "Insufficient balance for this plan".to_string()));
+    }
+
+    let subscription = sqlx::query_as::<_, Subscription>(
+        "INSERT INTO subscriptions (user_id, plan_id, current_period_start, current_period_end)
+         VALUES (?, ?, datetime('now'), datetime('now', ?))
+         RETURNING id, user_id, plan_id, status, auto_renew, started_at, current_period_start, current_period_end, minutes_used, cancelled_at, ended_at",
+    )
+    .bind(user_id)
+    .bind(plan.id)
+    .bind(format!("+{} days", plan.period_days))
+    .fetch_one(&mut *tx)
+    .await
+    .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, format!("Failed to create subscription: {}", e)))?;
+
+    receipt_service::issue_receipt(
+        &mut tx,
+        Some(user_id),
+        "package",
+        Some(subscription.id),
+        vec![LineItem::new(format!("{} plan ({} days)", plan.name, plan.period_days), 1, plan.price)],
+    )
+    .await
+    .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, format!("Failed to issue receipt: {}", e)))?;
+
+    tx.commit()
+        .await
+        .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, format!("Failed to create subscription: {}", e)))?;
+
+    Ok(subscription)
+}
+
+#[derive(sqlx::FromRow)]
+struct DueSubscription {
+    id: i64,
+    user_id: i64,
+    auto_renew: i64,
+    plan_name: String,
+    price: i64,
+    period_days: i64,
+    plan_active: i64,
+}
+
+/// Renews, cancels or lapses every active subscription whose period has ended.
+/// Run periodically by the scheduler. A renewal keeps the schedule when it is
+/// less than a period late; one further behind, e.g. after downtime, is
+/// charged once and starts its new period now rather than paying for the
+/// periods it missed.
+pub async fn run_lifecycle(pool: &SqlitePool) -> Result<(), sqlx::Error> {
+    let due = sqlx::query_as::<_, DueSubscription>(
+        "SELECT s.id, s.user_id, s.auto_renew, p.name AS plan_name, p.price, p.period_days, p.active AS plan_active
+         FROM subscriptions s
+         JOIN subscription_plans p ON p.id = s.plan_id
+         WHERE s.status = 'active' AND s.current_period_end <= datetime('now')",
+    )
+    .fetch_all(pool)
+    .await?;
+
+    for sub in due {
+        if sub.auto_renew == 0 || sub.plan_active == 0 {
+            sqlx::query("UPDATE subscriptions SET status = 'cancelled', ended_at = current_period_end WHERE id = ?")
+                .bind(sub.id)
+                .execute(pool)
+                .await?;
+            continue;
+        }
+
+        let mut tx = pool.begin().await?;
+
+        let paid = sqlx::query("UPDATE users SET balance = balance - ? WHERE id = ? AND balance >= ?")
+            .bind(sub.price)
+            .bind(sub.user_id)
+            .bind(sub.price)
+            .execute(&mut *tx)
+            .await?;
+
+        if paid.rows_affected() == 0 {
+            sqlx::query("UPDATE subscriptions SET status = 'lapsed', ended_at = current_period_end WHERE id = ?")
+                .bind(sub.id)
+                .execute(&mut *tx)
+                .await?;
+        } else {
+            let period = format!("+{} days", sub.period_days);
+            sqlx::query(
+                "UPDATE subscriptions
+                 SET current_period_start = CASE WHEN datetime(current_period_end, ?) > datetime('now')
+                                                 THEN current_period_end ELSE datetime('now') END,
+                     current_period_end = CASE WHEN datetime(current_period_end, ?) > datetime('now')
+                                               THEN datetime(current_period_end, ?) ELSE datetime('now', ?) END,
+                     minutes_used = 0
+                 WHERE id = ?",
+            )
+            .bind(&period)
+            .bind(&period)
+            .bind(&period)
+            .bind(&period)
+            .bind(sub.id)
+            .execute(&mut *tx)
+            .await?;
+
+            receipt_service::issue_receipt(
+                &mut tx,
+                Some(sub.user_id),
+                "package",
+                Some(sub.id),
+                vec![LineItem::new(format!("{} plan renewal ({} days)", sub.plan_name, sub.period_days), 1, sub.price)],
+            )
+            .await?;
+        }
+
+        tx.commit().await?;
+    }
+
+    Ok(())
+}
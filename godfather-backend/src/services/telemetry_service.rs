@@ -0,0 +1,143 @@
+use sqlx::{SqliteConnection, SqlitePool};
+use crate::{models::machine::HeartbeatReq, services::notification_service};
+
+const DEFAULT_CPU_TEMP_MAX: f64 = 90.0;
+const DEFAULT_GPU_TEMP_MAX: f64 = 90.0;
+const DEFAULT_DISK_FREE_MIN_MB: f64 = 5120.0;
+// raw samples are kept this long, hourly rollups much longer
+const DEFAULT_RAW_RETENTION_HOURS: i64 = 24;
+const DEFAULT_ROLLUP_RETENTION_DAYS: i64 = 90;
+
+fn env_or<T: std::str::FromStr>(name: &str, default: T) -> T {
+    std::env::var(name)
+        .ok()
+        .and_then(|v| v.parse().ok())
+        .unwrap_or(default)
+}
+
+pub fn raw_retention_hours() -> i64 {
+    env_or("TELEMETRY_RAW_RETENTION_HOURS", DEFAULT_RAW_RETENTION_HOURS)
+}
+
+fn rollup_retention_days() -> i64 {
+    env_or("TELEMETRY_ROLLUP_RETENTION_DAYS", DEFAULT_ROLLUP_RETENTION_DAYS)
+}
+
+/// Stores the readings carried by a heartbeat and raises or resolves alerts.
+/// Heartbeats from agents that report nothing store nothing.
+pub async fn record(conn: &mut SqliteConnection, machine_name: &str, req: &HeartbeatReq) -> Result<(), sqlx::Error> {
+    if let Some(version) = &req.agent_version {
+        sqlx::query("UPDATE machines SET agent_version = ?, logged_in_user = ? WHERE id = ?")
+            .bind(version)
+            .bind(&req.logged_in_user)
+            .bind(req.machine_id)
+            .execute(&mut *conn)
+            .await?;
+    }
+
+    let has_readings = req.cpu_temp_c.is_some()
+        || req.gpu_temp_c.is_some()
+        || req.cpu_load_percent.is_some()
+        || req.gpu_load_percent.is_some()
+        || req.ram_used_mb.is_some()
+        || req.disk_free_mb.is_some()
+        || req.uptime_seconds.is_some();
+
+    if !has_readings {
+        return Ok(());
+    }
+
+    sqlx::query(
+        "INSERT INTO telemetry_samples
+             (machine_id, cpu_temp_c, gpu_temp_c, cpu_load_percent, gpu_load_percent, ram_used_mb, ram_total_mb, disk_free_mb, uptime_seconds)
+         VALUES (?, ?, ?, ?, ?, ?, ?, ?, ?)",
+    )
+    .bind(req.machine_id)
+    .bind(req.cpu_temp_c)
+    .bind(req.gpu_temp_c)
+    .bind(req.cpu_load_percent)
+    .bind(req.gpu_load_percent)
+    .bind(req.ram_used_mb)
+    .bind(req.ram_total_mb)
+    .bind(req.disk_free_mb)
+    .bind(req.uptime_seconds)
+    .execute(&mut *conn)
+    .await?;
+
+    let checks = [
+        ("cpu_temp", req.cpu_temp_c, env_or("TELEMETRY_CPU_TEMP_MAX", DEFAULT_CPU_TEMP_MAX), true),
+        ("gpu_temp", req.gpu_temp_c, env_or("TELEMETRY_GPU_TEMP_MAX", DEFAULT_GPU_TEMP_MAX), true),
+        ("disk_free", req.disk_free_mb.map(|d| d as f64), env_or("TELEMETRY_DISK_FREE_MIN_MB", DEFAULT_DISK_FREE_MIN_MB), false),
+    ];
+
+    for (kind, value, threshold, upper) in checks {
+        let Some(value) = value else {
+            continue;
+        };
+
+        let breached = if upper { value > threshold } else { value < threshold };
+
+        if breached {
+            let raised = sqlx::query(
+                "INSERT INTO machine_alerts (machine_id, kind, value, threshold)
+                 SELECT ?, ?, ?, ?
+                 WHERE NOT EXISTS (SELECT 1 FROM machine_alerts WHERE machine_id = ? AND kind = ? AND resolved_at IS NULL)",
+            )
+            .bind(req.machine_id)
+            .bind(kind)
+            .bind(value)
+            .bind(threshold)
+            .bind(req.machine_id)
+            .bind(kind)
+            .execute(&mut *conn)
+            .await?;
+
+            if raised.rows_affected() == 1 {
+                let message = match kind {
+                    "disk_free" => format!("{} is low on disk space: {} MB free", machine_name, value),
+                    _ => format!("{} {} reached {:.1}°C (limit {:.1}°C)", machine_name, kind.replace('_', " "), value, threshold),
+                };
+                notification_service::notify_staff(conn, "machine_alert", &message).await?;
+            }
+        } else {
+            sqlx::query("UPDATE machine_alerts SET resolved_at = datetime('now') WHERE machine_id = ? AND kind = ? AND resolved_at IS NULL")
+                .bind(req.machine_id)
+                .bind(kind)
+                .execute(&mut *conn)
+                .await?;
+        }
+    }
+
+    Ok(())
+}
+
+/// Folds raw samples into hourly rollups and applies retention. Run by the scheduler.
+pub async fn run_rollups(pool: &SqlitePool) -> Result<(), sqlx::Error> {
+    // each completed hour is rolled up once, its samples never change afterwards
+    sqlx::query(
+        "INSERT OR IGNORE INTO telemetry_hourly
+             (machine_id, hour, samples, avg_cpu_temp_c, max_cpu_temp_c, avg_gpu_temp_c, max_gpu_temp_c,
+              avg_cpu_load_percent, avg_gpu_load_percent, avg_ram_used_mb, min_disk_free_mb, max_uptime_seconds)
+         SELECT machine_id, strftime('%Y-%m-%d %H:00:00', recorded_at), COUNT(*),
+                AVG(cpu_temp_c), MAX(cpu_temp_c), AVG(gpu_temp_c), MAX(gpu_temp_c),
+                AVG(cpu_load_percent), AVG(gpu_load_percent), AVG(ram_used_mb), MIN(disk_free_mb), MAX(uptime_seconds)
+         FROM telemetry_samples
+         WHERE recorded_at < strftime('%Y-%m-%d %H:00:00', 'now')
+         GROUP BY machine_id, strftime('%Y-%m-%d %H:00:00', recorded_at)",
+    )
+    .execute(pool)
+    .await?;
+
+    // whole hours only, so no hour is pruned before it has been rolled up
+    sqlx::query("DELETE FROM telemetry_samples WHERE recorded_at < strftime('%Y-%m-%d %H:00:00', 'now', ?)")
+        .bind(format!("-{} hours", raw_retention_hours()))
+        .execute(pool)
+        .await?;
+
+    sqlx::query("DELETE FROM telemetry_hourly WHERE hour < datetime('now', ?)")
+        .bind(format!("-{} days", rollup_retention_days()))
+        .execute(pool)
+        .await?;
+
+    Ok(())
+}
\ No newline at end of file
@@ -29,7 +29,7 @@ pub async fn create_user(
     let user = sqlx::query_as::<_, User>(
         "INSERT INTO users (username, email, role, password_hash, balance, minutes_balance) 
          VALUES (?, ?, ?, ?, 60, 60) 
-         RETURNING id, username, email, role, banned, created_at, last_login, balance, minutes_balance, password_hash",
+         RETURNING id, username, email, role, banned, created_at, last_login, balance, minutes_balance, bonus_minutes, lifetime_hours, password_hash",
     )
     .bind(&req.username)
     .bind(&req.email)
@@ -48,7 +48,7 @@ pub async fn authenticate_user(
     req: LoginReq,
 ) -> Result<Json<LoginResponse>, (StatusCode, String)> {
     let user = sqlx::query_as::<_, User>(
-        "SELECT id, username, email, role, banned, created_at, last_login, balance, minutes_balance, password_hash 
+        "SELECT id, username, email, role, banned, created_at, last_login, balance, minutes_balance, bonus_minutes, lifetime_hours, password_hash 
          FROM users WHERE username = ?",
     )
     .bind(&req.username)
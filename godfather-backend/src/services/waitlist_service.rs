@@ -0,0 +1,195 @@
+use axum::http::StatusCode;
+use sqlx::{SqliteConnection, SqlitePool};
+use crate::{
+    models::waitlist::{WaitlistEntry, WaitlistStatus},
+    services::{machine_service, notification_service, reservation_service},
+};
+
+// how long an offered machine is held before it goes to the next person
+const DEFAULT_OFFER_MINUTES: i64 = 5;
+// assumed session length when there is no recent history to average
+const DEFAULT_SESSION_MINUTES: i64 = 60;
+
+fn offer_minutes() -> i64 {
+    std::env::var("WAITLIST_OFFER_MINUTES")
+        .ok()
+        .and_then(|v| v.parse().ok())
+        .unwrap_or(DEFAULT_OFFER_MINUTES)
+}
+
+/// Offers an available machine to the longest waiting compatible entry.
+/// Returns whether anyone was offered the machine.
+pub async fn offer_machine(conn: &mut SqliteConnection, machine_id: i64) -> Result<bool, sqlx::Error> {
+    let next: Option<(i64, i64, String)> = sqlx::query_as(
+        "SELECT w.id, w.user_id, m.name
+         FROM waitlist_entries w, machines m
+         WHERE m.id = ? AND m.status = 'available'
+           AND w.status = 'waiting'
+           AND (w.machine_class IS NULL OR w.machine_class = m.class)
+           -- someone who started playing meanwhile keeps their place but is passed over
+           AND NOT EXISTS (SELECT 1 FROM sessions s WHERE s.user_id = w.user_id AND s.ended_at IS NULL)
+         ORDER BY w.created_at, w.id
+         LIMIT 1",
+    )
+    .bind(machine_id)
+    .fetch_optional(&mut *conn)
+    .await?;
+
+    let Some((entry_id, user_id, machine_name)) = next else {
+        return Ok(false);
+    };
+
+    let minutes = offer_minutes();
+
+    sqlx::query(
+        "UPDATE waitlist_entries
+         SET status = 'offered', offered_machine_id = ?, offered_at = datetime('now'), offer_expires_at = datetime('now', ?)
+         WHERE id = ?",
+    )
+    .bind(machine_id)
+    .bind(format!("+{} minutes", minutes))
+    .bind(entry_id)
+    .execute(&mut *conn)
+    .await?;
+
+    sqlx::query("UPDATE machines SET status = 'reserved' WHERE id = ?")
+        .bind(machine_id)
+        .execute(&mut *conn)
+        .await?;
+
+    let message = format!("{} is ready for you. Start your session within {} minutes to keep it", machine_name, minutes);
+    notification_service::notify_user(conn, user_id, "waitlist_offer", &message).await?;
+
+    Ok(true)
+}
+
+/// Seats the user holding the machine's waitlist offer, and refuses anyone else.
+/// Returns whether the machine was held by a waitlist offer at all.
+pub async fn claim_offer(
+    conn: &mut SqliteConnection,
+    machine_id: i64,
+    user_id: i64,
+) -> Result<bool, (StatusCode, String)> {
+    let offer: Option<(i64, i64)> = sqlx::query_as(
+        "SELECT id, user_id FROM waitlist_entries WHERE offered_machine_id = ? AND status = 'offered'",
+    )
+    .bind(machine_id)
+    .fetch_optional(&mut *conn)
+    .await
+    .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, format!("Database error: {}", e)))?;
+
+    let Some((entry_id, holder_id)) = offer else {
+        return Ok(false);
+    };
+
+    if holder_id != user_id {
+        return Err((StatusCode::FORBIDDEN, "Machine is held for a waitlisted customer".to_string()));
+    }
+
+    sqlx::query("UPDATE waitlist_entries SET status = 'seated', seated_at = datetime('now') WHERE id = ?")
+        .bind(entry_id)
+        .execute(&mut *conn)
+        .await
+        .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, format!("Failed to claim offer: {}", e)))?;
+
+    Ok(true)
+}
+
+/// Queue position and a rough wait estimate, assuming the busy compatible
+/// machines free up in order of how long their sessions have been running.
+pub async fn status_for(conn: &mut SqliteConnection, entry: WaitlistEntry) -> Result<WaitlistStatus, sqlx::Error> {
+    if entry.status != "waiting" {
+        return Ok(WaitlistStatus { entry, position: 0, estimated_wait_minutes: Some(0) });
+    }
+
+    let ahead: i64 = sqlx::query_scalar(
+        "SELECT COUNT(*) FROM waitlist_entries
+         WHERE status = 'waiting' AND (created_at < ? OR (created_at = ? AND id < ?))
+           AND (? IS NULL OR machine_class IS NULL OR machine_class = ?)",
+    )
+    .bind(&entry.created_at)
+    .bind(&entry.created_at)
+    .bind(entry.id)
+    .bind(&entry.machine_class)
+    .bind(&entry.machine_class)
+    .fetch_one(&mut *conn)
+    .await?;
+
+    let average: Option<f64> = sqlx::query_scalar(
+        "SELECT AVG(minutes_consumed) FROM sessions
+         WHERE ended_at IS NOT NULL AND ended_at > datetime('now', '-7 days')",
+    )
+    .fetch_one(&mut *conn)
+    .await?;
+    let average = average.map_or(DEFAULT_SESSION_MINUTES, |a| a.round() as i64).max(1);
+
+    let mut remaining: Vec<i64> = sqlx::query_scalar(
+        "SELECT CAST((julianday('now') - julianday(s.started_at)) * 1440 AS INTEGER)
+         FROM sessions s
+         JOIN machines m ON m.id = s.machine_id
+         WHERE s.ended_at IS NULL AND (? IS NULL OR m.class = ?)",
+    )
+    .bind(&entry.machine_class)
+    .bind(&entry.machine_class)
+    .fetch_all(&mut *conn)
+    .await?
+    .into_iter()
+    .map(|elapsed: i64| (average - elapsed).max(1))
+    .collect();
+    remaining.sort_unstable();
+
+    let estimated_wait_minutes = if remaining.is_empty() {
+        None
+    } else {
+        let k = ahead as usize;
+        Some(remaining[k % remaining.len()] + (k / remaining.len()) as i64 * average)
+    };
+
+    Ok(WaitlistStatus { entry, position: ahead + 1, estimated_wait_minutes })
+}
+
+/// Expires offers nobody took up and offers any idle machine to the queue.
+/// Run by the scheduler.
+pub async fn run_offers(pool: &SqlitePool) -> Result<(), sqlx::Error> {
+    let expired: Vec<(i64, i64, i64)> = sqlx::query_as(
+        "SELECT id, user_id, offered_machine_id FROM waitlist_entries
+         WHERE status = 'offered' AND offer_expires_at <= datetime('now')",
+    )
+    .fetch_all(pool)
+    .await?;
+
+    for (entry_id, user_id, machine_id) in expired {
+        let mut tx = pool.begin().await?;
+
+        sqlx::query("UPDATE waitlist_entries SET status = 'expired' WHERE id = ?")
+            .bind(entry_id)
+            .execute(&mut *tx)
+            .await?;
+
+        notification_service::notify_user(
+            &mut tx,
+            user_id,
+            "waitlist_expired",
+            "Your waitlist offer expired because the session was not started in time",
+        )
+        .await?;
+
+        machine_service::release_machine(&mut tx, machine_id).await?;
+
+        tx.commit().await?;
+    }
+
+    let idle: Vec<i64> = sqlx::query_scalar("SELECT id FROM machines WHERE status = 'available' ORDER BY name")
+        .fetch_all(pool)
+        .await?;
+
+    for machine_id in idle {
+        let mut tx = pool.begin().await?;
+        if !reservation_service::has_upcoming_booking(&mut tx, machine_id).await? {
+            offer_machine(&mut tx, machine_id).await?;
+        }
+        tx.commit().await?;
+    }
+
+    Ok(())
+}